@@ -0,0 +1,117 @@
+use super::{App, UIMode};
+
+#[cfg(test)]
+use super::fixture_app;
+
+/// A record to come back to, by index so it survives pattern edits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub index: usize,
+    pub id: String,
+}
+
+impl App<'_> {
+    /// Bookmark the record at the top of the viewer, or remove its bookmark
+    pub fn toggle_bookmark(&mut self) {
+        let index = self.scroll_status.0;
+        match self.bookmarks.binary_search_by_key(&index, |x| x.index) {
+            Ok(i) => {
+                let bookmark = self.bookmarks.remove(i);
+                self.set_message(format!(
+                    "Removed bookmark of record {} ({})",
+                    index + 1,
+                    bookmark.id
+                ));
+            }
+            Err(i) => {
+                let id = match self.record_at(index) {
+                    Ok(Some(record)) => record.id().to_string(),
+                    Ok(None) => return,
+                    Err(e) => return self.set_message(format!("Error reading the record: {}", e)),
+                };
+                self.set_message(format!("Bookmarked record {} ({})", index + 1, id));
+                self.bookmarks.insert(i, Bookmark { index, id });
+                self.selected_bookmark = i;
+            }
+        }
+    }
+
+    pub fn toggle_bookmarks_popup(&mut self) {
+        match self.mode {
+            UIMode::Bookmarks => self.mode = UIMode::Viewer,
+            _ if self.bookmarks.is_empty() => self.set_message(String::from("No bookmarks yet")),
+            _ => {
+                self.selected_bookmark = self.selected_bookmark.min(self.bookmarks.len() - 1);
+                self.mode = UIMode::Bookmarks;
+            }
+        }
+    }
+
+    pub fn cycle_bookmarks(&mut self, reverse: bool) {
+        let len = self.bookmarks.len();
+        if len > 0 {
+            self.selected_bookmark = if reverse {
+                (self.selected_bookmark + len - 1) % len
+            } else {
+                (self.selected_bookmark + 1) % len
+            };
+        }
+    }
+
+    /// Jump to the selected bookmark and close the popup
+    pub fn jump_to_bookmark(&mut self) {
+        if let Some(bookmark) = self.bookmarks.get(self.selected_bookmark) {
+            self.mode = UIMode::Viewer;
+            self.go_to_record(bookmark.index);
+        }
+    }
+
+    /// Delete the selected bookmark, closing the popup if none is left
+    pub fn delete_bookmark(&mut self) {
+        if self.selected_bookmark < self.bookmarks.len() {
+            self.bookmarks.remove(self.selected_bookmark);
+            self.selected_bookmark = self
+                .selected_bookmark
+                .min(self.bookmarks.len().saturating_sub(1));
+        }
+        if self.bookmarks.is_empty() {
+            self.mode = UIMode::Viewer;
+        }
+    }
+}
+
+#[test]
+fn test_bookmarks() {
+    let content: String = (0..5)
+        .map(|i| format!("@read{}\nACGT\n+\nIIII\n", i))
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    for index in [3, 0, 1] {
+        app.go_to_record(index);
+        app.toggle_bookmark();
+    }
+    let bookmarked = |app: &App| {
+        app.bookmarks
+            .iter()
+            .map(|x| x.index)
+            .collect::<Vec<usize>>()
+    };
+    assert_eq!(bookmarked(&app), vec![0, 1, 3]);
+    assert_eq!(app.bookmarks[1].id, "read1");
+    assert_eq!(app.selected_bookmark, 1);
+    // cycling wraps around both ends
+    app.cycle_bookmarks(false);
+    app.cycle_bookmarks(false);
+    assert_eq!(app.selected_bookmark, 0);
+    app.cycle_bookmarks(true);
+    assert_eq!(app.selected_bookmark, 2);
+    // toggling again removes the bookmark of the top record
+    app.toggle_bookmark();
+    assert_eq!(bookmarked(&app), vec![0, 3]);
+    app.toggle_bookmarks_popup();
+    assert_eq!(app.mode, UIMode::Bookmarks);
+    assert_eq!(app.selected_bookmark, 1);
+    app.jump_to_bookmark();
+    assert_eq!(app.mode, UIMode::Viewer);
+    assert_eq!(app.scroll_status.0, 3);
+}
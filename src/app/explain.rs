@@ -0,0 +1,171 @@
+use super::{App, Hit, MatchAlgorithm, Molecule, SearchPattern};
+use bio::alignment::AlignmentOperation;
+use bio::io::fastq;
+
+#[cfg(test)]
+use ratatui::prelude::Color;
+
+impl App<'_> {
+    /// Smallest edit distance (up to the pattern length) at which a pattern without members
+    /// matches the record, with the best hit at that distance, e.g. to explain why an adaptor
+    /// isn't highlighted
+    pub fn near_miss(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Option<Hit> {
+        let best_hit = |edit_distance: u8| {
+            let pattern = SearchPattern {
+                edit_distance,
+                ..pattern.clone()
+            };
+            Self::search_with_alignment(record, &pattern, molecule)
+                .into_iter()
+                .min_by_key(|(start, end, dist, _)| (*dist, usize::MAX - (end - start)))
+        };
+        // a match within a distance is also one within any larger distance
+        let (mut low, mut high) = (0, pattern.search_string.len().min(u8::MAX.into()) as u8);
+        best_hit(high)?;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if best_hit(mid).is_some() {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        best_hit(low)
+    }
+
+    /// Explain the best (near) matches in the record at the top of the patterns selected in
+    /// the search panel, or all patterns, in a popup
+    pub fn explain_matches(&mut self) {
+        let record = match self.record_at(self.scroll_status.0) {
+            Ok(Some(record)) => record,
+            Ok(None) => return,
+            Err(e) => return self.set_message(format!("Error reading the record: {}", e)),
+        };
+        let patterns: Vec<SearchPattern> = match self.search_panel.selected_pattern() {
+            Some(index) if index < self.search_patterns.len() => {
+                vec![self.search_patterns[index].clone()]
+            }
+            _ => self.search_patterns.clone(),
+        };
+        if patterns.is_empty() {
+            self.set_message(String::from("No patterns to explain"));
+            return;
+        }
+        let text = patterns
+            .iter()
+            .flat_map(|pattern| {
+                pattern.members().map(|member| {
+                    let member = SearchPattern {
+                        search_string: member.to_string(),
+                        ..pattern.clone()
+                    };
+                    explain_match(&record, &member, self.styling_config.molecule())
+                })
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.show_report(format!("Best matches in {}", record.id()), text);
+    }
+}
+
+/// Where a pattern matches best in a record, and at which edit distance compared to the
+/// pattern's, with the alignment
+fn explain_match(record: &fastq::Record, pattern: &SearchPattern, molecule: Molecule) -> String {
+    let name = if pattern.comment.is_empty() {
+        pattern.search_string.clone()
+    } else {
+        format!("{} ({})", pattern.search_string, pattern.comment)
+    };
+    let Some((start, end, dist, ops)) = App::near_miss(record, pattern, molecule) else {
+        return format!("{}: no match\n", name);
+    };
+    if pattern.algorithm == MatchAlgorithm::Regex {
+        return format!(
+            "{}: regex matches bases {}-{}\n  read     {}\n",
+            name,
+            start + 1,
+            end + 1,
+            String::from_utf8_lossy(&record.seq()[start..=end])
+        );
+    }
+    let verdict = if dist <= pattern.edit_distance.into() {
+        "matches"
+    } else {
+        "would match"
+    };
+    let [read_row, bars, pattern_row] =
+        alignment_rows(record.seq(), pattern.search_string.as_bytes(), start, &ops);
+    format!(
+        concat!(
+            "{}: {} at edit distance {} (allowed {}), bases {}-{}\n",
+            "  read     {}\n           {}\n  pattern  {}\n"
+        ),
+        name,
+        verdict,
+        dist,
+        pattern.edit_distance,
+        start + 1,
+        end + 1,
+        read_row,
+        bars,
+        pattern_row
+    )
+}
+
+/// Read and pattern rows of an alignment starting at read position `start`, with gaps as
+/// `-` and `|` between matching bases
+fn alignment_rows(
+    read: &[u8],
+    pattern: &[u8],
+    start: usize,
+    ops: &[AlignmentOperation],
+) -> [String; 3] {
+    let mut rows = [String::new(), String::new(), String::new()];
+    let (mut read_pos, mut pattern_pos) = (start, 0);
+    for op in ops {
+        let (read_base, pattern_base, bar) = match op {
+            AlignmentOperation::Match => (Some(read_pos), Some(pattern_pos), '|'),
+            AlignmentOperation::Subst => (Some(read_pos), Some(pattern_pos), ' '),
+            AlignmentOperation::Del => (Some(read_pos), None, ' '),
+            AlignmentOperation::Ins => (None, Some(pattern_pos), ' '),
+            _ => continue,
+        };
+        let base = |seq: &[u8], pos: Option<usize>| match pos.and_then(|pos| seq.get(pos)) {
+            Some(base) => *base as char,
+            None => '-',
+        };
+        rows[0].push(base(read, read_base));
+        rows[1].push(bar);
+        rows[2].push(base(pattern, pattern_base));
+        read_pos += read_base.is_some() as usize;
+        pattern_pos += pattern_base.is_some() as usize;
+    }
+    rows
+}
+
+#[test]
+fn test_explain_match() {
+    let record = fastq::Record::with_attrs("read", None, b"TTTTACGAACGTTTTT", b"IIIIIIIIIIIIIIII");
+    let pattern = SearchPattern::new(String::from("ACGTACGT"), Color::Red, 1, "adaptor");
+    let (start, end, dist, _) = App::near_miss(&record, &pattern, Molecule::Dna).unwrap();
+    assert_eq!((start, end, dist), (4, 11, 1));
+    assert_eq!(
+        explain_match(&record, &pattern, Molecule::Dna),
+        "ACGTACGT (adaptor): matches at edit distance 1 (allowed 1), bases 5-12\n  \
+         read     ACGAACGT\n           ||| ||||\n  pattern  ACGTACGT\n"
+    );
+    let pattern = SearchPattern::new(String::from("GGGG"), Color::Red, 0, "");
+    assert_eq!(
+        App::near_miss(&record, &pattern, Molecule::Dna).unwrap().2,
+        3
+    );
+    let record = fastq::Record::with_attrs("read", None, b"", b"");
+    assert_eq!(
+        explain_match(&record, &pattern, Molecule::Dna),
+        "GGGG: no match\n"
+    );
+}
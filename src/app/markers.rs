@@ -0,0 +1,96 @@
+use super::{App, RecordMatches, SearchPattern};
+use ratatui::prelude::{Line, Modifier, Span, Style};
+
+#[cfg(test)]
+use super::Hit;
+#[cfg(test)]
+use ratatui::prelude::Color;
+
+/// Most patterns marked by letter in the match marker column, more matches are counted instead
+const MAX_MATCH_MARKERS: usize = 6;
+
+impl App<'_> {
+    /// Columns of the match marker column including its gap, 0 if hidden
+    pub fn marker_column_width(&self) -> usize {
+        if self.styling_config.match_markers {
+            self.search_patterns.len().clamp(1, MAX_MATCH_MARKERS) + 1
+        } else {
+            0
+        }
+    }
+
+    /// Match marker of each rendered record, from the cached matches
+    pub fn match_markers(&self) -> Vec<Line<'static>> {
+        (self.scroll_status.0..self.scroll_status.0 + self.rendered_records.len())
+            .map(|index| match self.match_cache.get(&index) {
+                Some(matches) => match_marker(&self.search_patterns, matches),
+                None => Line::default(),
+            })
+            .collect()
+    }
+}
+
+/// First letter of the comment (or sequence) of each pattern matched in a record, in the
+/// pattern color, or the number of patterns matched if too many to list
+fn match_marker(search_patterns: &[SearchPattern], matches: &RecordMatches) -> Line<'static> {
+    let matched: Vec<&SearchPattern> = search_patterns
+        .iter()
+        .zip(matches.hits.iter().zip(&matches.junction_hits))
+        .filter(|(_, (hits, junction_hits))| !hits.is_empty() || !junction_hits.is_empty())
+        .map(|(pattern, _)| pattern)
+        .collect();
+    if matched.len() > MAX_MATCH_MARKERS {
+        return Line::styled(
+            format!("{}", matched.len()),
+            Style::new().add_modifier(Modifier::BOLD),
+        );
+    }
+    Line::from(
+        matched
+            .into_iter()
+            .map(|pattern| {
+                let label = if pattern.comment.is_empty() {
+                    &pattern.search_string
+                } else {
+                    &pattern.comment
+                };
+                Span::styled(
+                    label.chars().next().unwrap_or('?').to_string(),
+                    Style::new().fg(pattern.color).add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect::<Vec<Span>>(),
+    )
+}
+
+#[test]
+fn test_match_marker() {
+    let pattern =
+        |seq: &str, comment: &str| SearchPattern::new(seq.to_string(), Color::Red, 0, comment);
+    let patterns = vec![
+        pattern("ACGT", "adaptor"),
+        pattern("TTTT", ""),
+        pattern("GGGG", ""),
+    ];
+    let hit: Hit = (0, 3, 0, Vec::new());
+    let matches = RecordMatches {
+        hits: vec![vec![hit.clone()], Vec::new(), Vec::new()],
+        junction_hits: vec![Vec::new(), Vec::new(), vec![(2, 5)]],
+        read_len: 10,
+    };
+    let bold = Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
+    assert_eq!(
+        match_marker(&patterns, &matches),
+        Line::from(vec![Span::styled("a", bold), Span::styled("G", bold)])
+    );
+    let many = vec![pattern("ACGT", ""); MAX_MATCH_MARKERS + 1];
+    let matches = RecordMatches {
+        hits: vec![vec![hit]; MAX_MATCH_MARKERS + 1],
+        junction_hits: vec![Vec::new(); MAX_MATCH_MARKERS + 1],
+        read_len: 10,
+    };
+    assert_eq!(
+        match_marker(&many, &matches).to_string(),
+        (MAX_MATCH_MARKERS + 1).to_string()
+    );
+}
@@ -0,0 +1,126 @@
+use super::Hit;
+use crate::read_stylizing::{confidence_color, EditKind};
+use bio::alignment::AlignmentOperation;
+use gcollections::ops::set::{Difference, Union};
+use interval::interval_set::{IntervalSet, ToIntervalSet};
+use ratatui::prelude::Color;
+
+/// Hits of a pattern as one layer per edit distance colored by `confidence_color`, each
+/// position only in the layer of the best hit covering it so that overlapping hits of the
+/// same pattern aren't styled as overlaps of different patterns
+pub(super) fn confidence_layers(
+    hits: &[Hit],
+    color: Color,
+    max_dist: u8,
+) -> Vec<(IntervalSet<usize>, Color)> {
+    let mut dists: Vec<usize> = hits.iter().map(|(_, _, dist, _)| *dist).collect();
+    dists.sort_unstable();
+    dists.dedup();
+    let mut covered: IntervalSet<usize> = Vec::<(usize, usize)>::new().to_interval_set();
+    let mut layers: Vec<(IntervalSet<usize>, Color)> = Vec::new();
+    for dist in dists {
+        let set = hits
+            .iter()
+            .filter(|(_, _, x, _)| *x == dist)
+            .map(|(start, end, _, _)| (*start, *end))
+            .collect::<Vec<(usize, usize)>>()
+            .to_interval_set();
+        layers.push((
+            set.difference(&covered),
+            confidence_color(color, dist, max_dist.into()),
+        ));
+        covered = covered.union(&set);
+    }
+    layers
+}
+
+/// Longest run of consecutive exact matches within an alignment
+/// returns the (start, end) read positions (inclusive) of the run
+pub(super) fn exact_core(start: usize, ops: &[AlignmentOperation]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut run_start: Option<usize> = None;
+    let mut pos = start;
+    for op in ops {
+        match op {
+            AlignmentOperation::Match => {
+                let run = *run_start.get_or_insert(pos);
+                if best.is_none_or(|(s, e)| pos - run > e - s) {
+                    best = Some((run, pos));
+                }
+                pos += 1;
+            }
+            AlignmentOperation::Ins => run_start = None, // pattern base missing in read
+            _ => {
+                run_start = None;
+                pos += 1;
+            }
+        }
+    }
+    best
+}
+
+/// Read positions where an alignment deviates from the pattern
+pub(super) fn alignment_edits(start: usize, ops: &[AlignmentOperation]) -> Vec<(usize, EditKind)> {
+    let mut edits: Vec<(usize, EditKind)> = Vec::new();
+    let mut pos = start;
+    for op in ops {
+        match op {
+            AlignmentOperation::Subst => edits.push((pos, EditKind::Substitution)),
+            AlignmentOperation::Del => edits.push((pos, EditKind::ExtraBase)),
+            AlignmentOperation::Ins => {
+                // no read base to mark, consecutive missing bases are marked once
+                if edits.last() != Some(&(pos, EditKind::MissingBase)) {
+                    edits.push((pos, EditKind::MissingBase));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    edits
+}
+
+#[test]
+fn test_confidence_layers() {
+    let hits: Vec<Hit> = vec![
+        (0, 5, 1, Vec::new()),
+        (3, 8, 0, Vec::new()),
+        (20, 25, 2, Vec::new()),
+    ];
+    let layers = confidence_layers(&hits, Color::Rgb(100, 100, 100), 2);
+    assert_eq!(
+        layers,
+        vec![
+            (vec![(3, 8)].to_interval_set(), Color::Rgb(100, 100, 100)),
+            (vec![(0, 2)].to_interval_set(), Color::Rgb(70, 70, 70)),
+            (vec![(20, 25)].to_interval_set(), Color::Rgb(40, 40, 40)),
+        ]
+    );
+}
+
+#[test]
+fn test_alignment_edits() {
+    use AlignmentOperation::*;
+    assert_eq!(
+        alignment_edits(3, &[Match, Subst, Match, Del, Match, Ins, Ins, Match]),
+        vec![
+            (4, EditKind::Substitution),
+            (6, EditKind::ExtraBase),
+            (8, EditKind::MissingBase)
+        ]
+    );
+    assert!(alignment_edits(0, &[Match, Match]).is_empty());
+}
+
+#[test]
+fn test_exact_core() {
+    use AlignmentOperation::*;
+    assert_eq!(
+        exact_core(5, &[Match, Match, Subst, Match, Match, Match]),
+        Some((8, 10))
+    );
+    assert_eq!(exact_core(0, &[Match, Match, Del, Match]), Some((0, 1)));
+    assert_eq!(exact_core(0, &[Match, Ins, Match, Match]), Some((1, 2)));
+    assert_eq!(exact_core(0, &[Subst]), None);
+}
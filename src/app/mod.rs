@@ -0,0 +1,2997 @@
+mod bookmarks;
+mod explain;
+mod markers;
+mod match_styling;
+mod search;
+mod selection;
+pub use bookmarks::Bookmark;
+use match_styling::{alignment_edits, confidence_layers, exact_core};
+pub use search::compile_regex;
+pub use selection::{SELECTION_FASTQ_FILE, SELECTION_IDS_FILE, VIEW_EXPORT_FILE};
+
+use crate::config::ScrollConfig;
+use crate::io::fastq::{DecompressOptions, FastqReader};
+use crate::io::output::{write_replacing, OutputWriter};
+use crate::keymap::Keymap;
+use crate::read_stylizing::{
+    base_color_intervals, get_quality_styling, highlight_window, length_color, subtract_intervals,
+    QualityStyleMode, StyleInput, PHRED_OFFSET,
+};
+use crate::search_panel::SearchPanel;
+use crate::trimming::{trim_bounds, TRIM_END_WINDOW};
+
+use bio::alignment::AlignmentOperation;
+use bio::alphabets::dna;
+use bio::io::fastq;
+use gcollections::ops::set::Difference;
+use interval::interval_set::{IntervalSet, ToIntervalSet};
+use ratatui::prelude::{Color, Line, Modifier, Size, Span, Style};
+use ratatui::widgets::{Block, Borders};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tui_textarea::TextArea;
+
+#[cfg(test)]
+use crate::test_utils::TempFileGuard;
+
+/// Records rendered ahead of scrolling unless set with `--buffer-size`
+#[cfg(debug_assertions)]
+pub const DEFAULT_RENDER_BUF_SIZE: usize = 24;
+#[cfg(not(debug_assertions))]
+pub const DEFAULT_RENDER_BUF_SIZE: usize = 100;
+
+/// Reads longer than this are rendered a window of this many bases at a time, moving with the
+/// scroll position, so giant reads don't materialize the styles and lines of the whole
+/// sequence. Only rendering is windowed: records are still read and searched whole, so the
+/// matches and their counts cover the whole read and the reader holds the whole sequence
+#[cfg(debug_assertions)]
+const MAX_RENDERED_BASES: usize = 1000;
+#[cfg(not(debug_assertions))]
+const MAX_RENDERED_BASES: usize = 1 << 16;
+
+/// Width of the `RecordSeparator::Rule` line, short enough to never wrap
+const SEPARATOR_RULE_WIDTH: usize = 20;
+
+/// Separates the member sequences of a pattern group, see `SearchPattern::members`
+pub const PATTERN_MEMBER_SEPARATOR: char = '|';
+
+/// Symbols allowed in DNA patterns: bases and IUPAC ambiguity codes
+pub const DNA_ALPHABET: &str = "ACGTMRWSYKVHDBN";
+
+/// Symbols allowed in RNA patterns: the DNA symbols plus U, matching T only in RNA reads
+pub const RNA_ALPHABET: &str = "ACGTUMRWSYKVHDBN";
+
+/// Symbols allowed in protein patterns: amino acids, ambiguity codes and the stop `*`
+pub const PROTEIN_ALPHABET: &str = "ACDEFGHIKLMNPQRSTVWYUOBZJX*";
+
+/// Kind of the sequences searched, deciding which symbols match each other and whether
+/// patterns and reads have a reverse complement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Molecule {
+    #[default]
+    Dna,
+    Rna,     // U matches T, A complements to U
+    Protein, // amino acid ambiguity codes, no complement
+}
+
+impl Molecule {
+    pub fn from_flags(rna: bool, protein: bool) -> Self {
+        match (rna, protein) {
+            (_, true) => Molecule::Protein,
+            (true, false) => Molecule::Rna,
+            (false, false) => Molecule::Dna,
+        }
+    }
+
+    /// Symbols allowed in patterns unless set in the config file
+    pub fn alphabet(&self) -> &'static str {
+        match self {
+            Molecule::Dna => DNA_ALPHABET,
+            Molecule::Rna => RNA_ALPHABET,
+            Molecule::Protein => PROTEIN_ALPHABET,
+        }
+    }
+}
+
+/// Complement of a base, U complements to A and A to U for RNA reads; amino acids are kept
+fn complement(base: u8, molecule: Molecule) -> u8 {
+    match (base, molecule) {
+        (_, Molecule::Protein) => base,
+        (b'U', _) => b'A',
+        (b'u', _) => b'a',
+        (b'A', Molecule::Rna) => b'U',
+        (b'a', Molecule::Rna) => b'u',
+        _ => dna::complement(base),
+    }
+}
+
+pub(crate) fn revcomp(seq: &[u8], molecule: Molecule) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|base| complement(*base, molecule))
+        .collect()
+}
+
+/// Shown in place of sequence bytes that aren't printable ASCII
+const UNDISPLAYABLE_BASE: char = '?';
+
+/// Sequence as displayed, one character per byte so match positions index the text, bytes
+/// other than printable ASCII (never bases, e.g. UTF-8 or control characters) are replaced
+fn display_sequence(seq: &[u8]) -> String {
+    seq.iter()
+        .map(|&base| {
+            if base.is_ascii_graphic() {
+                base as char
+            } else {
+                UNDISPLAYABLE_BASE
+            }
+        })
+        .collect()
+}
+
+/// Whether the record has a quality score for each base, e.g. not with an empty quality
+/// line. Records without are never styled by quality rather than styled as if high quality
+pub(crate) fn has_quality(record: &fastq::Record) -> bool {
+    !record.qual().is_empty() && record.qual().len() == record.seq().len()
+}
+
+/// Bases shown either side of the base in `base_quality_report`
+const BASE_REPORT_CONTEXT: usize = 10;
+
+/// The base at a (0-based) position with its quality character, ASCII code and Phred score
+fn base_quality_report(record: &fastq::Record, pos: usize) -> Result<String, String> {
+    let seq = record.seq();
+    let Some(&base) = seq.get(pos) else {
+        return Err(format!(
+            "Base position {} beyond the read length {}",
+            pos + 1,
+            seq.len()
+        ));
+    };
+    let from = pos.saturating_sub(BASE_REPORT_CONTEXT);
+    let to = (pos + 1 + BASE_REPORT_CONTEXT).min(seq.len());
+    let mut text = format!(
+        "Base:     {}\nContext:  {}[{}]{}  (bases {}-{})\n",
+        base as char,
+        display_sequence(&seq[from..pos]),
+        display_sequence(&[base]),
+        display_sequence(&seq[pos + 1..to]),
+        from + 1,
+        to
+    );
+    if has_quality(record) {
+        let q = record.qual()[pos];
+        let phred = q.saturating_sub(PHRED_OFFSET);
+        text.push_str(&format!(
+            "Quality:  '{}' (ASCII {})\nPhred:    {} (offset {}), error probability {:.1e}",
+            q as char,
+            q,
+            phred,
+            PHRED_OFFSET,
+            10f64.powf(-(phred as f64) / 10.0)
+        ));
+    } else {
+        text.push_str("Quality:  none, the record has no quality scores");
+    }
+    Ok(text)
+}
+
+#[test]
+fn test_base_quality_report() {
+    let record = fastq::Record::with_attrs("read", None, b"ACGTACGT", b"II5+IIII");
+    assert_eq!(
+        base_quality_report(&record, 2).unwrap(),
+        "Base:     G\nContext:  AC[G]TACGT  (bases 1-8)\n\
+         Quality:  '5' (ASCII 53)\nPhred:    20 (offset 33), error probability 1.0e-2"
+    );
+    assert!(base_quality_report(&record, 8).is_err());
+    let record = fastq::Record::with_attrs("read", None, b"ACGT", b"");
+    assert!(base_quality_report(&record, 0)
+        .unwrap()
+        .ends_with("no quality scores"));
+}
+
+/// Runs of lowercase bases, which some assemblers and maskers use for low confidence
+fn lowercase_intervals(seq: &[u8]) -> IntervalSet<usize> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (i, base) in seq.iter().enumerate() {
+        if !base.is_ascii_lowercase() {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == i => *end = i,
+            _ => runs.push((i, i)),
+        }
+    }
+    runs.to_interval_set()
+}
+
+#[test]
+fn test_lowercase_intervals() {
+    assert_eq!(
+        lowercase_intervals(b"acGTTnnA"),
+        vec![(0, 1), (5, 6)].to_interval_set()
+    );
+    assert_eq!(
+        lowercase_intervals(b"ACGT"),
+        Vec::<(usize, usize)>::new().to_interval_set()
+    );
+}
+
+/// Compact length, GC and N content of a read for the ID line, N only shown if present.
+/// Ambiguity codes count by the fraction of their bases that are G or C, and N is left out
+/// of the GC content; protein sequences only show the length
+fn base_composition(seq: &[u8], protein: bool) -> String {
+    if protein {
+        return format!("[len={}]", seq.len());
+    }
+    let (mut gc, mut called, mut n) = (0.0, 0, 0);
+    for base in seq {
+        gc += match base.to_ascii_uppercase() {
+            b'G' | b'C' | b'S' => 1.0,
+            b'R' | b'Y' | b'K' | b'M' => 0.5,
+            b'B' | b'V' => 2.0 / 3.0,
+            b'D' | b'H' => 1.0 / 3.0,
+            b'N' => {
+                n += 1;
+                continue;
+            }
+            _ => 0.0,
+        };
+        called += 1;
+    }
+    let gc = match called {
+        0 => String::from("-"),
+        _ => format!("{:.1}%", 100.0 * gc / called as f64),
+    };
+    if n == 0 {
+        format!("[len={} GC={}]", seq.len(), gc)
+    } else {
+        let n = 100.0 * n as f64 / seq.len() as f64;
+        format!("[len={} GC={} N={:.1}%]", seq.len(), gc, n)
+    }
+}
+
+#[test]
+fn test_base_composition() {
+    assert_eq!(base_composition(b"ACGTACGT", false), "[len=8 GC=50.0%]");
+    assert_eq!(
+        base_composition(b"gcGCAN", false),
+        "[len=6 GC=80.0% N=16.7%]"
+    );
+    assert_eq!(base_composition(b"SWRA", false), "[len=4 GC=37.5%]");
+    assert_eq!(base_composition(b"NN", false), "[len=2 GC=- N=100.0%]");
+    assert_eq!(base_composition(b"", false), "[len=0 GC=-]");
+    assert_eq!(base_composition(b"MKVLA", true), "[len=5]");
+}
+
+#[test]
+fn test_display_sequence() {
+    let seq = "AC\u{e9}G\tT".as_bytes();
+    assert_eq!(display_sequence(seq), "AC??G?T");
+    assert_eq!(display_sequence(seq).len(), seq.len());
+}
+
+/// Delay after the last pattern edit before autosaving, so bursts of edits are written once
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+
+// color of the unmatched regions (e.g. the insert) when inverting matches
+const INVERTED_MATCH_COLOR: Color = Color::LightYellow;
+
+#[derive(Debug)]
+pub struct App<'a> {
+    pub mode: UIMode,
+    pub quit: bool,
+    pub search_panel: SearchPanel<'a>,
+    pub search_patterns: Vec<SearchPattern>,
+    pub styling_config: StylingConfig,
+    pub keymap: Keymap,
+    pub scroll_config: ScrollConfig,
+    pub default_algorithm: MatchAlgorithm, // for patterns added in the search panel
+    pub alphabet: String,                  // symbols allowed in patterns
+    pub color_count: u16,                  // colors of the terminal, 256 or fewer are approximated
+    pub bookmarks: Vec<Bookmark>,          // sorted by index
+    pub selected_bookmark: usize,
+    pub selection: Option<(usize, usize)>, // first and last position of the selected records
+    pub report: Report,                    // shown in UIMode::Report
+    pub prompt: TextArea<'a>,              // input of UIMode::Prompt
+    pub file: PathBuf,
+    // 0-based start of each read (by ID) within a reference such as an amplicon, the
+    // coordinates the read covers are shown after its ID
+    pub coordinate_offsets: HashMap<String, usize>,
+    // CSV file the patterns are written to after edits, None if autosave is off
+    pub autosave_path: Option<PathBuf>,
+    autosave_due: Option<Instant>,
+    pub rendered_records: RenderedRecords<'a>,
+    // file indexes of the records in viewing order, e.g. grouped by matches; None for file order.
+    // Positions in the viewer (scroll_status.0, bookmarks, match cache) index into this
+    record_order: Option<Vec<usize>>,
+    // number of identical reads each position of the viewer stands for, see collapse_records
+    multiplicity: Option<Vec<usize>>,
+    // offset of the rendered lines to the file
+    // number of records rendered from scroll_status.0 on
+    render_buf_size: usize,
+    // scroll within the viewed lines -- reset to 0 on resize
+    pub scroll_status: (usize, usize),
+    // first base rendered of the record at scroll_status.0, a multiple of MAX_RENDERED_BASES
+    // for reads longer than that, other records are rendered from their start
+    window_start: usize,
+    // horizontal offset in columns, only used when lines are not wrapped
+    pub horizontal_scroll: usize,
+    // whether the last scroll hit the top / bottom, to only notify on arrival
+    at_edge: bool,
+    // (min, max) read length of the length gradient, None if disabled
+    length_scale: Option<(usize, usize)>,
+    // matches of records by index, so styling changes don't search again;
+    // valid for the patterns, junction search and RNA settings in match_cache_key, by pattern
+    match_cache: HashMap<usize, RecordMatches>,
+    match_cache_key: (Vec<SearchPattern>, bool, Molecule),
+    // whether the user was told about sequence bytes shown as UNDISPLAYABLE_BASE
+    undisplayable_warned: bool,
+    reader: FastqReader<File>,
+    message: TransientMessage,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct SearchPattern {
+    pub search_string: String,
+    #[serde(with = "crate::session::color")]
+    pub color: Color,
+    pub edit_distance: u8,
+    pub comment: String,
+    pub algorithm: MatchAlgorithm,
+    // also match the reverse complement, styled and counted as matches of this pattern
+    #[serde(default)]
+    pub search_rev_comp: bool,
+    // disabled patterns are kept in the list but not searched
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+impl SearchPattern {
+    pub fn new(search_string: String, color: Color, edit_distance: u8, comment: &str) -> Self {
+        Self {
+            search_string,
+            color,
+            edit_distance,
+            comment: comment.to_string(),
+            algorithm: MatchAlgorithm::default(),
+            search_rev_comp: false,
+            enabled: true,
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: MatchAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn with_rev_comp(mut self, search_rev_comp: bool) -> Self {
+        self.search_rev_comp = search_rev_comp;
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// The pattern on the forward strand only, and its reverse complement if
+    /// `search_rev_comp`, with the strand (`+` or `-`); regex and protein patterns are forward
+    /// only
+    pub fn strands(&self, molecule: Molecule) -> Vec<(SearchPattern, char)> {
+        let forward = self.clone().with_rev_comp(false);
+        if !self.search_rev_comp
+            || self.algorithm == MatchAlgorithm::Regex
+            || molecule == Molecule::Protein
+        {
+            return vec![(forward, '+')];
+        }
+        let search_string = forward
+            .members()
+            .map(|member| {
+                String::from_utf8_lossy(&revcomp(member.as_bytes(), molecule)).to_string()
+            })
+            .collect::<Vec<String>>()
+            .join(&PATTERN_MEMBER_SEPARATOR.to_string());
+        let reverse = SearchPattern {
+            search_string,
+            ..forward.clone()
+        };
+        vec![(forward, '+'), (reverse, '-')]
+    }
+
+    /// Sequences matched by the pattern: a search string like `ACGT|ACCT` is a group of
+    /// variants sharing the color and comment, matched by any of its members. A regex
+    /// pattern is a single member, `|` being regex alternation
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        let regex = self.algorithm == MatchAlgorithm::Regex;
+        self.search_string
+            .split(move |c: char| !regex && c == PATTERN_MEMBER_SEPARATOR)
+    }
+
+    /// Check the search string against the alphabet, upper-casing it if that makes it valid.
+    /// Returns a warning if the search string was upper-cased, or an error naming the
+    /// symbols outside the alphabet, as such a pattern would silently never match.
+    /// Regex patterns are only checked to compile.
+    pub fn validate(&mut self, alphabet: &str) -> Result<Option<String>, String> {
+        if self.algorithm == MatchAlgorithm::Regex {
+            return compile_regex(&self.search_string)
+                .map(|_| None)
+                .map_err(|e| format!("Invalid regex {}: {}", self.search_string, e));
+        }
+        if self.members().any(str::is_empty) {
+            return Err(format!(
+                "Pattern {} has an empty group member",
+                self.search_string
+            ));
+        }
+        let invalid = |search_string: &str| {
+            let mut symbols: Vec<char> = search_string
+                .chars()
+                .filter(|c| *c != PATTERN_MEMBER_SEPARATOR && !alphabet.contains(*c))
+                .collect();
+            symbols.sort_unstable();
+            symbols.dedup();
+            symbols
+        };
+        if invalid(&self.search_string).is_empty() {
+            return Ok(None);
+        }
+        let upper = self.search_string.to_uppercase();
+        let symbols = invalid(&upper);
+        if symbols.is_empty() {
+            let msg = format!("Pattern {} converted to upper case", self.search_string);
+            self.search_string = upper;
+            Ok(Some(msg))
+        } else {
+            Err(format!(
+                "Pattern {} contains symbols outside the alphabet: {}",
+                self.search_string,
+                symbols
+                    .iter()
+                    .map(|c| format!("'{}'", c))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ))
+        }
+    }
+}
+
+/// How a pattern is matched against reads
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchAlgorithm {
+    /// Myers bit-parallel edit distance, every inserted / deleted base costs 1
+    #[default]
+    Myers,
+    /// Alignment with affine gap penalties, a long indel costs little more than a short one
+    #[serde(rename = "affine")]
+    AffineGap,
+    /// Regular expression over the read sequence, e.g. `A{10,}$` for a poly-A tail at the
+    /// read end; the edit distance is not used
+    Regex,
+}
+
+impl std::str::FromStr for MatchAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" | "myers" => Ok(MatchAlgorithm::Myers),
+            "affine" => Ok(MatchAlgorithm::AffineGap),
+            "regex" => Ok(MatchAlgorithm::Regex),
+            _ => Err(format!(
+                "Unknown match algorithm {}, expected myers, affine or regex",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for MatchAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchAlgorithm::Myers => write!(f, "myers"),
+            MatchAlgorithm::AffineGap => write!(f, "affine"),
+            MatchAlgorithm::Regex => write!(f, "regex"),
+        }
+    }
+}
+
+/// Viewer-wide styling options applied when rendering records
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StylingConfig {
+    pub underline_core: bool, // underline the longest exact run within each match
+    pub no_wrap: bool,        // truncate long lines instead of wrapping, scroll horizontally
+    pub chimera_junctions: bool, // also search across the read / reverse complement junction
+    pub invert_match: bool,   // color the regions not matched by any pattern instead
+    pub quality_styling: bool, // style bases by quality, see quality_mode
+    pub quality_mode: QualityStyleMode,
+    pub quality_threshold: u8,                // Phred score
+    pub length_gradient: bool,                // color IDs by read length
+    pub length_scale: Option<(usize, usize)>, // fixed (min, max) gradient, None to fit the window
+    pub edit_markers: bool, // mark substitutions, extra and missing bases within matches
+    pub trim_preview: bool, // strike through terminal matches and poly-A tails, see trim_bounds
+    pub trim_window: usize, // distance to the read ends within which matches are trimmed
+    pub record_separator: RecordSeparator, // line after each record
+    pub match_style: MatchStyle, // emphasis of matched bases besides their color
+    pub complement_track: bool, // line of the complement bases under the sequence
+    pub rna: bool,          // match U as T, complement A to U
+    pub protein: bool,      // amino acid sequences, no complement
+    pub match_confidence: bool, // dim matches by edit distance
+    pub match_markers: bool, // column marking the patterns matched in each record
+    pub lowercase_italic: bool, // italicize lowercase bases, e.g. low confidence in assemblies
+    pub base_composition: bool, // length, GC% (and N% if any) of each read after the ID
+    pub base_colors: bool,  // color bases by identity under the pattern colors
+    pub show_quality_line: bool, // raw quality string under the sequence, FASTQ only
+}
+
+impl Default for StylingConfig {
+    fn default() -> Self {
+        Self {
+            underline_core: false,
+            no_wrap: false,
+            chimera_junctions: false,
+            invert_match: false,
+            quality_styling: false,
+            quality_mode: QualityStyleMode::default(),
+            quality_threshold: 20,
+            length_gradient: false,
+            length_scale: None,
+            edit_markers: false,
+            trim_preview: false,
+            trim_window: TRIM_END_WINDOW,
+            record_separator: RecordSeparator::default(),
+            match_style: MatchStyle::default(),
+            complement_track: false,
+            rna: false,
+            protein: false,
+            match_confidence: false,
+            match_markers: false,
+            lowercase_italic: false,
+            base_composition: false,
+            base_colors: false,
+            show_quality_line: false,
+        }
+    }
+}
+
+impl StylingConfig {
+    pub fn molecule(&self) -> Molecule {
+        Molecule::from_flags(self.rna, self.protein)
+    }
+}
+
+/// Line rendered after each record to tell densely packed records apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordSeparator {
+    #[default]
+    None,
+    Blank,
+    Rule, // a short dim horizontal rule
+}
+
+/// Emphasis of matched bases besides the pattern color, for terminals whose colors are hard to
+/// tell apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStyle {
+    #[default]
+    Color, // the pattern color only
+    Bold,
+    Underline,
+    Reversed, // the pattern color as background
+}
+
+impl MatchStyle {
+    pub fn modifier(self) -> Modifier {
+        match self {
+            MatchStyle::Color => Modifier::empty(),
+            MatchStyle::Bold => Modifier::BOLD,
+            MatchStyle::Underline => Modifier::UNDERLINED,
+            MatchStyle::Reversed => Modifier::REVERSED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StylingToggle {
+    CoreUnderline,
+    Wrap,
+    InvertMatch,
+    Quality,
+    QualityMode,
+    LengthGradient,
+    EditMarkers,
+    TrimPreview,
+    ComplementTrack,
+    MatchConfidence,
+    MatchMarkers,
+    LowercaseItalic,
+    BaseComposition,
+    BaseColors,
+    QualityLine,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UIMode {
+    Viewer,
+    SearchPanel(bool),  // bool: save file popup
+    Bookmarks,          // bookmarks popup over the viewer
+    Report,             // text popup over the viewer, e.g. a summary of the selection
+    MatchStatistics,    // edit distance histograms popup over the viewer
+    Prompt(PromptKind), // one line input popup over the viewer
+    Help,               // keybindings popup over the viewer, closed by any key
+}
+
+/// What the one line prompt asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    BasePosition,          // a base position of the record at the top, see show_base_quality
+    FindRead,              // `[N] MOTIF`, see find_read
+    GoToRecord,            // a record number (1-based)
+    ExportView,            // a file path, see export_view
+    ExportSelection(bool), // a file path, see export_selection, for the IDs only if true
+}
+
+/// (start, end (inclusive), edit distance, alignment operations) of a match
+type Hit = (usize, usize, usize, Vec<AlignmentOperation>);
+
+/// Matches of each pattern in a record: hits from `App::search_with_alignment` and
+/// junction hits from `App::search_junction` (empty unless searching junctions)
+#[derive(Debug, Clone, PartialEq)]
+struct RecordMatches {
+    hits: Vec<Vec<Hit>>,
+    junction_hits: Vec<Vec<(usize, usize)>>,
+    read_len: usize, // to move the rendered window of long reads without fetching them again
+}
+
+/// Text shown in a popup over the viewer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub title: String,
+    pub text: String,
+}
+
+/// Edit distances of the matches of each pattern in the records searched so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchStatistics {
+    pub records: usize,
+    // per pattern, the number of matches at each edit distance up to the pattern's
+    pub histograms: Vec<Vec<usize>>,
+}
+
+impl MatchStatistics {
+    /// Indexes of the fuzzy patterns with most matches at their largest edit distance,
+    /// likely to match more than intended
+    pub fn over_permissive(&self) -> Vec<usize> {
+        self.histograms
+            .iter()
+            .enumerate()
+            .filter(|(_, histogram)| {
+                let total: usize = histogram.iter().sum();
+                histogram.len() > 1 && 2 * histogram.last().unwrap_or(&0) > total
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Bases of a read rendered from `window_start` on, the whole read unless longer than
+/// MAX_RENDERED_BASES
+fn rendered_window(read_len: usize, window_start: usize) -> std::ops::Range<usize> {
+    let start = window_start.min(last_window_start(read_len));
+    start..(start + MAX_RENDERED_BASES).min(read_len)
+}
+
+/// Start of the last window of a read, 0 unless longer than MAX_RENDERED_BASES
+fn last_window_start(read_len: usize) -> usize {
+    read_len.saturating_sub(1) / MAX_RENDERED_BASES * MAX_RENDERED_BASES
+}
+
+#[test]
+fn test_rendered_window() {
+    assert_eq!(rendered_window(0, 0), 0..0);
+    assert_eq!(rendered_window(10, 0), 0..10);
+    let len = 2 * MAX_RENDERED_BASES + 10;
+    assert_eq!(rendered_window(len, 0), 0..MAX_RENDERED_BASES);
+    assert_eq!(last_window_start(len), 2 * MAX_RENDERED_BASES);
+    assert_eq!(
+        rendered_window(len, 5 * MAX_RENDERED_BASES),
+        2 * MAX_RENDERED_BASES..len
+    );
+    assert_eq!(last_window_start(MAX_RENDERED_BASES), 0);
+}
+
+/// Height in terminal rows of lines wrapped to the width, or not wrapped
+fn lines_height(lines: &[Line], width: usize, no_wrap: bool) -> usize {
+    if no_wrap {
+        return lines.len();
+    }
+    // empty lines, e.g. blank separators, still take a row
+    lines.iter().map(|x| x.width().div_ceil(width).max(1)).sum()
+}
+
+/// Lines of the rendered records, each record may render to any number of lines
+/// scrolling pushes and pops whole records
+#[derive(Debug, Default)]
+pub struct RenderedRecords<'a> {
+    records: VecDeque<Vec<Line<'a>>>,
+}
+
+impl<'a> RenderedRecords<'a> {
+    /// number of records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn push_front(&mut self, lines: Vec<Line<'a>>) {
+        self.records.push_front(lines);
+    }
+
+    pub fn push_back(&mut self, lines: Vec<Line<'a>>) {
+        self.records.push_back(lines);
+    }
+
+    pub fn pop_front(&mut self) -> Option<Vec<Line<'a>>> {
+        self.records.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<Vec<Line<'a>>> {
+        self.records.pop_back()
+    }
+
+    /// all lines, record after record
+    pub fn lines(&self) -> impl Iterator<Item = &Line<'a>> {
+        self.records.iter().flatten()
+    }
+
+    /// height of the first record in terminal rows
+    pub fn first_height(&self, width: usize, no_wrap: bool) -> usize {
+        self.records
+            .front()
+            .map_or(0, |lines| lines_height(lines, width, no_wrap))
+    }
+
+    /// height of each record in terminal rows
+    pub fn heights(&self, width: usize, no_wrap: bool) -> impl Iterator<Item = usize> + '_ {
+        self.records
+            .iter()
+            .map(move |lines| lines_height(lines, width, no_wrap))
+    }
+
+    /// height of all records in terminal rows
+    pub fn height(&self, width: usize, no_wrap: bool) -> usize {
+        self.records
+            .iter()
+            .map(|lines| lines_height(lines, width, no_wrap))
+            .sum()
+    }
+}
+
+impl<'a> FromIterator<Vec<Line<'a>>> for RenderedRecords<'a> {
+    fn from_iter<T: IntoIterator<Item = Vec<Line<'a>>>>(iter: T) -> Self {
+        Self {
+            records: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[test]
+fn test_rendered_records() {
+    let record = |n: usize, width: usize| -> Vec<Line> {
+        (0..n)
+            .map(|i| Line::from(format!("{}", i).repeat(width)))
+            .collect()
+    };
+    let mut records: RenderedRecords = vec![record(1, 4), record(2, 4), record(3, 4)]
+        .into_iter()
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records.lines().count(), 6);
+    assert_eq!(records.first_height(10, false), 1);
+    // 4 characters wrap to 2 rows at width 3
+    assert_eq!(records.height(3, false), 12);
+    assert_eq!(records.height(3, true), 6);
+    assert_eq!(lines_height(&[Line::default()], 3, false), 1);
+
+    // scrolling down pops the whole first record
+    assert_eq!(records.pop_front().map(|x| x.len()), Some(1));
+    assert_eq!(records.first_height(10, false), 2);
+    records.push_back(record(1, 4));
+    assert_eq!(records.pop_back().map(|x| x.len()), Some(1));
+    // scrolling up pushes a record in front of the rest
+    records.push_front(record(3, 12));
+    assert_eq!(records.first_height(10, false), 6);
+    assert_eq!(
+        records.lines().map(|x| x.width()).collect::<Vec<usize>>(),
+        vec![12, 12, 12, 4, 4, 4, 4, 4]
+    );
+}
+
+#[derive(Default, Debug)]
+pub struct TransientMessage {
+    message: String,
+    timer: u8, // ticks to live
+}
+
+impl TransientMessage {
+    pub fn new(message: String) -> Self {
+        Self { message, timer: 1 }
+    }
+    pub fn get(&mut self) -> Option<String> {
+        if self.timer > 0 {
+            self.timer -= 1;
+            Some(self.message.clone())
+        } else {
+            None
+        }
+    }
+    pub fn dismiss(&mut self) {
+        self.timer = 0;
+    }
+}
+
+impl App<'_> {
+    pub fn new(
+        file: &Path,
+        search_patterns: Vec<SearchPattern>,
+        keymap: Keymap,
+        decompress_options: &DecompressOptions,
+    ) -> Result<Self, std::io::Error> {
+        let reader = FastqReader::from_path(file, decompress_options)?;
+        let mut instance = App {
+            quit: false,
+            search_patterns: search_patterns.clone(),
+            styling_config: StylingConfig::default(),
+            keymap,
+            scroll_config: ScrollConfig::default(),
+            default_algorithm: MatchAlgorithm::default(),
+            alphabet: DNA_ALPHABET.to_string(),
+            color_count: 256,
+            bookmarks: Vec::new(),
+            selected_bookmark: 0,
+            selection: None,
+            report: Report::default(),
+            prompt: TextArea::default(),
+            message: TransientMessage::default(),
+            mode: UIMode::Viewer,
+            search_panel: SearchPanel::new(&search_patterns),
+            file: Path::new(&file).to_path_buf(),
+            autosave_path: None,
+            autosave_due: None,
+            reader,
+            rendered_records: RenderedRecords::default(),
+            record_order: None,
+            multiplicity: None,
+            coordinate_offsets: HashMap::new(),
+            render_buf_size: DEFAULT_RENDER_BUF_SIZE,
+            scroll_status: (0, 0),
+            window_start: 0,
+            horizontal_scroll: 0,
+            at_edge: false,
+            length_scale: None,
+            match_cache: HashMap::new(),
+            undisplayable_warned: false,
+            match_cache_key: (Vec::new(), false, Molecule::Dna),
+        };
+        instance.update();
+        Ok(instance)
+    }
+
+    /// Set running to false to quit the application.
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    pub fn set_styling_config(&mut self, styling_config: StylingConfig) {
+        if styling_config != self.styling_config {
+            self.styling_config = styling_config;
+            self.update();
+        }
+    }
+
+    pub fn set_search_patterns(&mut self, search_patterns: Vec<SearchPattern>) {
+        self.search_patterns = search_patterns;
+        self.schedule_autosave();
+        self.update();
+    }
+
+    /// Render `size` records from the top of the view on, e.g. more for tall terminals
+    pub fn set_render_buf_size(&mut self, size: usize) {
+        self.render_buf_size = size.max(1);
+        self.update();
+    }
+
+    pub fn append_search_pattern(&mut self, pattern: SearchPattern) {
+        self.search_patterns.push(pattern);
+        self.search_panel.clear_inputs();
+        self.search_panel.update(&self.search_patterns);
+        self.schedule_autosave();
+        self.update();
+    }
+
+    pub fn delete_search_pattern(&mut self, index: usize) -> SearchPattern {
+        let pattern = self.search_patterns.remove(index);
+        self.search_panel.update(&self.search_patterns);
+        self.schedule_autosave();
+        self.update();
+        pattern
+    }
+
+    /// Swap a pattern with the one above (or below) it, the selection following it. The order
+    /// is kept in saved patterns and orders the match markers
+    pub fn move_search_pattern(&mut self, index: usize, up: bool) {
+        let target = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|x| *x < self.search_patterns.len())
+        };
+        let Some(target) = target.filter(|_| index < self.search_patterns.len()) else {
+            return;
+        };
+        self.search_patterns.swap(index, target);
+        self.search_panel.update(&self.search_patterns);
+        self.search_panel.select_pattern(target);
+        self.schedule_autosave();
+        self.update();
+    }
+
+    /// Disable a pattern without losing its settings, or enable it again
+    pub fn toggle_pattern(&mut self, index: usize) {
+        let Some(pattern) = self.search_patterns.get_mut(index) else {
+            return;
+        };
+        pattern.enabled = !pattern.enabled;
+        let msg = format!(
+            "Pattern {} {}",
+            pattern.search_string,
+            if pattern.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        self.search_panel.update(&self.search_patterns);
+        self.schedule_autosave();
+        self.update();
+        self.set_message(msg);
+    }
+
+    /// Widen or narrow the edit distance of a pattern by one, at most the length of its
+    /// shortest member
+    pub fn adjust_edit_distance(&mut self, index: usize, widen: bool) {
+        let Some(pattern) = self.search_patterns.get_mut(index) else {
+            return;
+        };
+        if pattern.algorithm == MatchAlgorithm::Regex {
+            let msg = format!(
+                "Regex pattern {} has no edit distance",
+                pattern.search_string
+            );
+            self.set_message(msg);
+            return;
+        }
+        let max = pattern
+            .members()
+            .map(str::len)
+            .min()
+            .unwrap_or(0)
+            .min(u8::MAX as usize) as u8;
+        let distance = if widen {
+            pattern.edit_distance.saturating_add(1).min(max)
+        } else {
+            pattern.edit_distance.saturating_sub(1)
+        };
+        let msg = format!("Edit distance of {}: {}", pattern.search_string, distance);
+        if distance != pattern.edit_distance {
+            pattern.edit_distance = distance;
+            self.search_panel.update(&self.search_patterns);
+            self.schedule_autosave();
+            self.update();
+        }
+        self.set_message(msg);
+    }
+
+    /// Autosave the patterns once no further edit happens within `AUTOSAVE_DELAY`
+    fn schedule_autosave(&mut self) {
+        if self.autosave_path.is_some() {
+            self.autosave_due = Some(Instant::now() + AUTOSAVE_DELAY);
+        }
+    }
+
+    /// Write the patterns to the autosave file if an edit is due to be saved, called on
+    /// every event including ticks
+    pub fn autosave_if_due(&mut self) {
+        let (Some(path), Some(due)) = (&self.autosave_path, self.autosave_due) else {
+            return;
+        };
+        if Instant::now() < due {
+            return;
+        }
+        self.autosave_due = None;
+        let result = write_replacing(path, |writer| {
+            let mut writer = csv::Writer::from_writer(writer);
+            write_patterns_csv(&self.search_patterns, &mut writer)?;
+            writer.flush()
+        });
+        if let Err(e) = result {
+            self.set_message(format!("Autosave failed: {}", e));
+        }
+    }
+
+    pub fn edit_search_pattern(&mut self, index: usize) {
+        let pattern: SearchPattern = self.delete_search_pattern(index);
+        self.search_panel.edit_pattern(pattern);
+    }
+
+    pub fn toggle_ui_mode(&mut self) {
+        match &self.mode {
+            UIMode::Viewer
+            | UIMode::Bookmarks
+            | UIMode::Report
+            | UIMode::MatchStatistics
+            | UIMode::Help
+            | UIMode::Prompt(_) => self.mode = UIMode::SearchPanel(false),
+            UIMode::SearchPanel(_) => self.mode = UIMode::Viewer,
+        };
+    }
+
+    pub fn show_report(&mut self, title: String, text: String) {
+        self.report = Report { title, text };
+        self.mode = UIMode::Report;
+    }
+
+    pub fn show_help(&mut self) {
+        self.mode = UIMode::Help;
+    }
+
+    pub fn close_report(&mut self) {
+        self.mode = UIMode::Viewer;
+    }
+
+    /// Ask for a base position of the record at the top, see `show_base_quality`
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        let (title, hint) = match kind {
+            PromptKind::BasePosition => (
+                "Base position (1-based) in the record at the top",
+                "Esc to cancel; Enter to show its quality",
+            ),
+            PromptKind::FindRead => (
+                "[N] MOTIF or pattern (search string or comment)",
+                "Esc to cancel; Enter to jump to the Nth read containing it",
+            ),
+            PromptKind::GoToRecord => ("Record number (1-based)", "Esc to cancel; Enter to jump"),
+            PromptKind::ExportView => (
+                "File to write the rendered records to, .html for HTML, ANSI colors otherwise",
+                "Esc to cancel; Enter to write",
+            ),
+            PromptKind::ExportSelection(_) if self.selection.is_none() => {
+                return self.set_message(String::from("No records selected, select with [ and ]"));
+            }
+            PromptKind::ExportSelection(false) => (
+                "File to write the selected records to as FASTQ",
+                "Esc to cancel; Enter to write, replacing the file if it exists",
+            ),
+            PromptKind::ExportSelection(true) => (
+                "File to write the IDs of the selected records to",
+                "Esc to cancel; Enter to write, replacing the file if it exists",
+            ),
+        };
+        self.prompt = TextArea::default();
+        match kind {
+            PromptKind::ExportView => self.prompt.insert_str(VIEW_EXPORT_FILE),
+            PromptKind::ExportSelection(false) => self.prompt.insert_str(SELECTION_FASTQ_FILE),
+            PromptKind::ExportSelection(true) => self.prompt.insert_str(SELECTION_IDS_FILE),
+            _ => false,
+        };
+        self.prompt.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(Line::from(hint).right_aligned()),
+        );
+        self.mode = UIMode::Prompt(kind);
+    }
+
+    pub fn submit_prompt(&mut self) {
+        let UIMode::Prompt(kind) = self.mode else {
+            return;
+        };
+        self.mode = UIMode::Viewer;
+        let input = self.prompt.lines().join("");
+        match kind {
+            PromptKind::BasePosition => self.show_base_quality(&input),
+            PromptKind::FindRead => self.find_read(&input),
+            PromptKind::GoToRecord => match input.trim().parse::<usize>() {
+                Ok(n) if n > 0 => self.go_to_record(n - 1),
+                _ => self.set_message(format!(
+                    "Invalid record number {}, expected a number from 1",
+                    input
+                )),
+            },
+            PromptKind::ExportView => self.export_view(Path::new(input.trim())),
+            PromptKind::ExportSelection(ids_only) => {
+                self.export_selection(Path::new(input.trim()), ids_only)
+            }
+        }
+    }
+
+    /// Show the base and quality score at the position entered in the prompt
+    fn show_base_quality(&mut self, input: &str) {
+        let Some(pos) = input.trim().parse::<usize>().ok().filter(|x| *x > 0) else {
+            self.set_message(format!(
+                "Invalid base position {}, expected a number from 1",
+                input
+            ));
+            return;
+        };
+        let record = match self.record_at(self.scroll_status.0) {
+            Ok(Some(record)) => record,
+            Ok(None) => return,
+            Err(e) => return self.set_message(format!("Error reading the record: {}", e)),
+        };
+        match base_quality_report(&record, pos - 1) {
+            Ok(text) => self.show_report(format!("Base {} of {}", pos, record.id()), text),
+            Err(msg) => self.set_message(msg),
+        }
+    }
+
+    /// Jump to the Nth read below the top record containing a motif, `[N] MOTIF` where
+    /// MOTIF is the search string or comment of a pattern or an exact sequence
+    fn find_read(&mut self, input: &str) {
+        let input = input.trim();
+        let (count, motif) = input
+            .split_once(' ')
+            .and_then(|(n, motif)| Some((n.parse::<usize>().ok()?, motif.trim())))
+            .unwrap_or((1, input));
+        if count == 0 || motif.is_empty() {
+            return self.set_message(format!("Invalid search {}, expected [N] MOTIF", input));
+        }
+        let pattern = match self
+            .search_patterns
+            .iter()
+            .find(|x| x.search_string == motif || x.comment == motif)
+        {
+            Some(pattern) => pattern.clone(),
+            None => {
+                let mut pattern = SearchPattern::new(motif.to_string(), Color::Reset, 0, "");
+                if let Err(msg) = pattern.validate(&self.alphabet) {
+                    return self.set_message(msg);
+                }
+                pattern
+            }
+        };
+        let mut found = 0;
+        let mut position = self.scroll_status.0;
+        loop {
+            position += 1;
+            let record = match self.record_at(position) {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(e) => {
+                    return self.set_message(format!(
+                        "Error reading record {}: {}",
+                        position + 1,
+                        e
+                    ))
+                }
+            };
+            if App::search(&record, &pattern, self.styling_config.molecule()).is_empty() {
+                continue;
+            }
+            found += 1;
+            if found == count {
+                self.go_to_record(position);
+                return self.set_message(format!(
+                    "Read {} below the top containing {}: record {}",
+                    count,
+                    motif,
+                    position + 1
+                ));
+            }
+        }
+        self.set_message(format!(
+            "End of file reached, only {} of {} reads containing {} found",
+            found, count, motif
+        ));
+    }
+
+    /// Move the next (or previous) record matching any pattern to the top. Matches already
+    /// found for the records around the screen are reused, other records are searched one
+    /// at a time until a match, so nothing beyond it is read
+    pub fn jump_to_match(&mut self, forward: bool) {
+        if !self.search_patterns.iter().any(|x| x.enabled) {
+            return self.set_message(String::from("No patterns to jump to"));
+        }
+        let cache_valid = self.match_cache_key
+            == (
+                self.search_patterns.clone(),
+                self.styling_config.chimera_junctions,
+                self.styling_config.molecule(),
+            );
+        let mut position = self.scroll_status.0;
+        loop {
+            position = match (forward, position.checked_sub(1)) {
+                (true, _) => position + 1,
+                (false, Some(previous)) => previous,
+                (false, None) => break,
+            };
+            let matched = match self.match_cache.get(&position).filter(|_| cache_valid) {
+                Some(matches) => matches.hits.iter().any(|hits| !hits.is_empty()),
+                None => match self.record_at(position) {
+                    Ok(Some(record)) => {
+                        let molecule = self.styling_config.molecule();
+                        self.search_patterns.iter().any(|pattern| {
+                            pattern.enabled && !App::search(&record, pattern, molecule).is_empty()
+                        })
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        return self.set_message(format!(
+                            "Error reading record {}: {}",
+                            position + 1,
+                            e
+                        ))
+                    }
+                },
+            };
+            if matched {
+                self.go_to_record(position);
+                return self.set_message(format!("Match in record {}", position + 1));
+            }
+        }
+        self.set_message(String::from(if forward {
+            "No further matches"
+        } else {
+            "No previous matches"
+        }));
+    }
+
+    /// Index the records of the file for direct seeking, see `FastqReader::build_index`
+    pub fn build_index(&mut self) -> Result<usize, std::io::Error> {
+        let file = self.file.clone();
+        let records = self.reader.build_index(&file)?;
+        self.update();
+        Ok(records)
+    }
+
+    /// Path to read the records again from, e.g. to group them, the spooled copy of
+    /// standard input
+    pub fn input_path(&self) -> &Path {
+        self.reader.input_path(&self.file)
+    }
+
+    pub fn toggle_match_statistics(&mut self) {
+        self.mode = match self.mode {
+            UIMode::MatchStatistics => UIMode::Viewer,
+            _ => UIMode::MatchStatistics,
+        };
+    }
+
+    /// Edit distance histograms of the non-overlapping matches in the records searched so
+    /// far, i.e. those scrolled through since the patterns last changed
+    pub fn match_statistics(&self) -> MatchStatistics {
+        let mut histograms: Vec<Vec<usize>> = self
+            .search_patterns
+            .iter()
+            .map(|pattern| vec![0; pattern.edit_distance as usize + 1])
+            .collect();
+        for matches in self.match_cache.values() {
+            for (histogram, hits) in histograms.iter_mut().zip(&matches.hits) {
+                for (_, _, dist, _) in Self::best_hits(hits.clone()) {
+                    let last = histogram.len() - 1;
+                    histogram[dist.min(last)] += 1;
+                }
+            }
+        }
+        MatchStatistics {
+            records: self.match_cache.len(),
+            histograms,
+        }
+    }
+
+    pub fn toggle_styling(&mut self, toggle: StylingToggle) {
+        if toggle == StylingToggle::ComplementTrack && self.styling_config.protein {
+            return self.set_message(String::from("Protein sequences have no complement"));
+        }
+        if matches!(toggle, StylingToggle::Quality | StylingToggle::QualityMode)
+            && !self.styling_config.quality_styling
+        {
+            let top = match self.record_at(self.scroll_status.0) {
+                Ok(top) => top,
+                Err(e) => return self.set_message(format!("Error reading the record: {}", e)),
+            };
+            if top.is_some_and(|record| !has_quality(&record)) {
+                self.set_message(String::from(
+                    "No quality scores in this record, quality styling is unavailable",
+                ));
+                return;
+            }
+        }
+        let (name, enabled) = match toggle {
+            StylingToggle::CoreUnderline => {
+                self.styling_config.underline_core = !self.styling_config.underline_core;
+                ("Exact core underline", self.styling_config.underline_core)
+            }
+            StylingToggle::Wrap => {
+                self.styling_config.no_wrap = !self.styling_config.no_wrap;
+                // vertical offset is counted in wrapped rows or lines depending on the mode
+                self.scroll_status.1 = 0;
+                self.horizontal_scroll = 0;
+                ("Line wrapping", !self.styling_config.no_wrap)
+            }
+            StylingToggle::InvertMatch => {
+                self.styling_config.invert_match = !self.styling_config.invert_match;
+                (
+                    "Inverted match highlighting",
+                    self.styling_config.invert_match,
+                )
+            }
+            StylingToggle::Quality => {
+                self.styling_config.quality_styling = !self.styling_config.quality_styling;
+                ("Quality styling", self.styling_config.quality_styling)
+            }
+            StylingToggle::QualityMode => {
+                self.styling_config.quality_mode = self.styling_config.quality_mode.toggled();
+                // switching the mode implies wanting to see it
+                self.styling_config.quality_styling = true;
+                (
+                    "High quality emphasis",
+                    self.styling_config.quality_mode == QualityStyleMode::Emphasis,
+                )
+            }
+            StylingToggle::LengthGradient => {
+                self.styling_config.length_gradient = !self.styling_config.length_gradient;
+                ("Read length gradient", self.styling_config.length_gradient)
+            }
+            StylingToggle::EditMarkers => {
+                self.styling_config.edit_markers = !self.styling_config.edit_markers;
+                ("Edit markers", self.styling_config.edit_markers)
+            }
+            StylingToggle::TrimPreview => {
+                self.styling_config.trim_preview = !self.styling_config.trim_preview;
+                ("Trim preview", self.styling_config.trim_preview)
+            }
+            StylingToggle::ComplementTrack => {
+                self.styling_config.complement_track = !self.styling_config.complement_track;
+                ("Complement track", self.styling_config.complement_track)
+            }
+            StylingToggle::MatchConfidence => {
+                self.styling_config.match_confidence = !self.styling_config.match_confidence;
+                (
+                    "Match confidence dimming",
+                    self.styling_config.match_confidence,
+                )
+            }
+            StylingToggle::MatchMarkers => {
+                self.styling_config.match_markers = !self.styling_config.match_markers;
+                // the column narrows the viewer, so wrapped rows change
+                self.scroll_status.1 = 0;
+                ("Match markers", self.styling_config.match_markers)
+            }
+            StylingToggle::LowercaseItalic => {
+                self.styling_config.lowercase_italic = !self.styling_config.lowercase_italic;
+                ("Lowercase italic", self.styling_config.lowercase_italic)
+            }
+            StylingToggle::BaseComposition => {
+                self.styling_config.base_composition = !self.styling_config.base_composition;
+                ("Base composition", self.styling_config.base_composition)
+            }
+            StylingToggle::BaseColors => {
+                self.styling_config.base_colors = !self.styling_config.base_colors;
+                ("Base coloring", self.styling_config.base_colors)
+            }
+            StylingToggle::QualityLine => {
+                self.styling_config.show_quality_line = !self.styling_config.show_quality_line;
+                ("Quality line", self.styling_config.show_quality_line)
+            }
+        };
+        self.update();
+        self.set_message(format!(
+            "{} {}",
+            name,
+            if enabled { "enabled" } else { "disabled" }
+        ));
+    }
+
+    pub fn save_patterns(&self) -> Option<String> {
+        let path = self.search_panel.file_save_popup_lines();
+        if path.len() != 1 {
+            return Some(String::from("Malformed file path"));
+        }
+        let path = Path::new(&path[0]);
+        let file = match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                return Some(match e.kind() {
+                    std::io::ErrorKind::NotFound => String::from("File path not found"),
+                    std::io::ErrorKind::PermissionDenied => String::from("Permission denied"),
+                    std::io::ErrorKind::AlreadyExists => String::from("File already exists"),
+                    _ => format!("Error creating {}: {}", path.display(), e),
+                })
+            }
+        };
+        let mut writer = csv::Writer::from_writer(OutputWriter::new(file, path));
+        if let Err(e) = write_patterns_csv(&self.search_patterns, &mut writer) {
+            return Some(format!("Error writing pattern CSV file: {}", e));
+        }
+        match writer.into_inner().map(|x| x.finish()) {
+            Ok(Ok(())) => None,
+            _ => Some(String::from("Error flushing pattern CSV file")),
+        }
+    }
+
+    /// scroll the rendered lines by num
+    /// rendered_records push / pop whole records if scrolling beyond a record
+    pub fn scroll(&mut self, num: isize, tui_size: Size) {
+        let width = self.viewer_width(tui_size.width);
+        let no_wrap = self.styling_config.no_wrap;
+
+        let was_at_edge = self.at_edge;
+        if num != 0 {
+            self.at_edge = false;
+        }
+
+        if num == 0 {
+            return;
+        } else if num <= isize::MIN + 1 {
+            self.back_to_top();
+            return;
+        } else if num < 0 {
+            if self.scroll_status.1 > 0 {
+                // scroll within the first record
+                let remaining = self.scroll_status.1 as isize + num;
+                self.scroll_status.1 = remaining.max(0) as usize;
+                return self.scroll(remaining.min(0), tui_size);
+            } else if self.move_window(false) {
+                // into the bottom of the previous window of a long read
+                self.scroll_status.1 = self.rendered_records.first_height(width, no_wrap);
+                return self.scroll(num, tui_size);
+            } else {
+                let mut remaining = num;
+                while remaining < 0 && self.scroll_status.0 > 0 {
+                    let index = self.scroll_status.0 - 1;
+                    let record = match self.record_at(index) {
+                        Ok(Some(record)) => record,
+                        Ok(None) => break,
+                        Err(e) => {
+                            return self.set_message(format!(
+                                "Error reading record {}: {}",
+                                index + 1,
+                                e
+                            ))
+                        }
+                    };
+                    self.scroll_status.0 -= 1;
+                    // long reads are entered from the bottom, so from their last window
+                    self.window_start = last_window_start(record.seq().len());
+                    let lines = self.record_lines(index, record);
+                    remaining += lines_height(&lines, width, no_wrap) as isize;
+                    self.rendered_records.push_front(lines);
+                    if self.rendered_records.len() > self.render_buf_size {
+                        self.rendered_records.pop_back();
+                    }
+                }
+                self.scroll_status.1 = remaining.max(0) as usize;
+                if remaining < 0 {
+                    self.at_edge = true;
+                    if !was_at_edge {
+                        self.set_message("Hit top".to_string());
+                    }
+                }
+                return;
+            }
+        }
+
+        let mut remaining: isize = num + self.scroll_status.1 as isize; // remaining lines to scroll
+        let mut current_record_height = self.rendered_records.first_height(width, no_wrap);
+        self.scroll_status.1 = 0;
+
+        while remaining >= current_record_height as isize {
+            if self.move_window(true) {
+                // the next window of a long read at the top
+                remaining -= current_record_height as isize;
+                current_record_height = self.rendered_records.first_height(width, no_wrap);
+                continue;
+            }
+            // the record after the rendered ones, fewer than render_buf_size if a read error
+            // stopped `update` early
+            let index = self.scroll_status.0 + self.rendered_records.len();
+            let rec = match self.record_at(index) {
+                Ok(Some(rec)) => rec,
+                Ok(None) => {
+                    // EOF reached, scroll the rendered lines within their total height
+                    let max_scroll = self.max_scroll_at_eof(width, no_wrap, tui_size.height);
+                    self.scroll_status.1 =
+                        (self.scroll_status.1 + remaining as usize).min(max_scroll);
+                    if self.scroll_status.1 == max_scroll {
+                        self.at_edge = true;
+                        if !was_at_edge {
+                            self.set_message("Hit bottom".to_string());
+                        }
+                    }
+                    return;
+                }
+                Err(e) => {
+                    return self.set_message(format!("Error reading record {}: {}", index + 1, e))
+                }
+            };
+            // otherwise append new record and pop current record, only appending to
+            // refill a buffer left short
+            let full = self.rendered_records.len() >= self.render_buf_size;
+            if full {
+                self.rendered_records.pop_front();
+                self.scroll_status.0 += 1;
+                self.window_start = 0;
+            }
+            let lines = self.record_lines(index, rec);
+            self.rendered_records.push_back(lines);
+            if full {
+                remaining -= current_record_height as isize;
+            }
+            current_record_height = self.rendered_records.first_height(width, no_wrap);
+        }
+        self.scroll_status.1 = remaining as usize;
+    }
+
+    /// Render the next (or previous) window of the record at the top if it is a read longer
+    /// than MAX_RENDERED_BASES, false if the window is already the last (or first)
+    fn move_window(&mut self, forward: bool) -> bool {
+        let index = self.scroll_status.0;
+        let Some(read_len) = self.match_cache.get(&index).map(|x| x.read_len) else {
+            return false;
+        };
+        let window_start = if forward {
+            self.window_start + MAX_RENDERED_BASES
+        } else {
+            match self.window_start.checked_sub(MAX_RENDERED_BASES) {
+                Some(start) => start,
+                None => return false,
+            }
+        };
+        if window_start >= read_len {
+            return false;
+        }
+        let record = match self.record_at(index) {
+            Ok(Some(record)) => record,
+            Ok(None) => return false,
+            Err(e) => {
+                self.set_message(format!("Error reading record {}: {}", index + 1, e));
+                return false;
+            }
+        };
+        self.window_start = window_start;
+        let lines = self.record_lines(index, record);
+        self.rendered_records.pop_front();
+        self.rendered_records.push_front(lines);
+        true
+    }
+
+    /// scroll the unwrapped lines horizontally by num columns
+    pub fn scroll_horizontal(&mut self, num: isize, tui_size: Size) {
+        if !self.styling_config.no_wrap {
+            self.set_message("Horizontal scrolling requires line wrapping off (w)".to_string());
+            return;
+        }
+        let max_scroll = self
+            .rendered_records
+            .lines()
+            .map(|x| x.width())
+            .max()
+            .unwrap_or(0)
+            .saturating_sub(self.viewer_width(tui_size.width));
+        self.horizontal_scroll = self
+            .horizontal_scroll
+            .saturating_add_signed(num)
+            .min(max_scroll);
+    }
+
+    /// Columns available to the rendered lines in a terminal `tui_width` columns wide
+    pub fn viewer_width(&self, tui_width: u16) -> usize {
+        // 2 boarders 1 char wide
+        (tui_width as usize).saturating_sub(2 + self.marker_column_width())
+    }
+
+    /// Number of records in the viewer, None until the reader reached the end of the file
+    pub fn total_records(&self) -> Option<usize> {
+        match &self.record_order {
+            Some(order) => Some(order.len()),
+            None => self.reader.total_records,
+        }
+    }
+
+    /// Position of the record at the top, e.g. "record 1234 / 98765 (1.2%)", or
+    /// "record 1234 / ?" while the total is unknown
+    pub fn position_indicator(&self) -> String {
+        let record = self.scroll_status.0 + 1;
+        match self.total_records() {
+            Some(total) => format!(
+                "record {} / {} ({:.1}%)",
+                record,
+                total,
+                100.0 * record as f64 / total.max(1) as f64
+            ),
+            None => format!("record {} / ?", record),
+        }
+    }
+
+    pub fn back_to_top(&mut self) {
+        if let Err(e) = self.reader.rewind() {
+            return self.set_message(format!("Error reading the file: {}", e));
+        }
+        self.scroll_status = (0, 0);
+        self.window_start = 0;
+        self.update();
+    }
+
+    /// Scroll so that the record at the given index (0-based) is at the top
+    /// indexes beyond the last record are clamped to the last record
+    pub fn go_to_record(&mut self, index: usize) {
+        let (index, msg) = match self.record_at(index) {
+            Ok(Some(_)) => (index, None),
+            Err(e) => {
+                return self.set_message(format!("Error reading record {}: {}", index + 1, e))
+            }
+            Ok(None) => {
+                let last = self.total_records().unwrap_or(1).saturating_sub(1);
+                (
+                    last,
+                    Some(format!(
+                        "Record {} out of range, jumped to the last record ({})",
+                        index + 1,
+                        last + 1
+                    )),
+                )
+            }
+        };
+        self.scroll_status = (index, 0);
+        self.window_start = 0;
+        self.update();
+        if let Some(msg) = msg {
+            self.set_message(msg);
+        }
+    }
+
+    /// Record at a position of the viewer, the position is the file index unless the records
+    /// are reordered with `set_record_order`. Malformed records the reader skipped on the way
+    /// are reported in the message
+    fn record_at(&mut self, position: usize) -> Result<Option<fastq::Record>, std::io::Error> {
+        let record = match &self.record_order {
+            Some(order) => match order.get(position) {
+                Some(index) => self.reader.get_index(*index),
+                None => Ok(None),
+            },
+            None => self.reader.get_index(position),
+        };
+        let malformed = self.reader.take_malformed();
+        if let Some(last) = malformed.last() {
+            self.set_message(match malformed.len() {
+                1 => last.clone(),
+                n => format!("{} (and {} more malformed records skipped)", last, n - 1),
+            });
+        }
+        record
+    }
+
+    /// View the records in the given order of file indexes, e.g. grouped by matches, from the
+    /// top. Bookmarks refer to positions in the viewer so they are cleared
+    pub fn set_record_order(&mut self, order: Vec<usize>) {
+        self.record_order = Some(order);
+        self.bookmarks.clear();
+        self.match_cache.clear();
+        self.scroll_status = (0, 0);
+        self.window_start = 0;
+        self.update();
+    }
+
+    /// View one read of each group of identical reads, given as (file index of the shown read,
+    /// number of reads) from `collapsing::collapse_records`, with the number after its ID
+    pub fn collapse_records(&mut self, collapsed: Vec<(usize, usize)>) {
+        let reads: usize = collapsed.iter().map(|(_, count)| count).sum();
+        let distinct = collapsed.len();
+        let (order, multiplicity) = collapsed.into_iter().unzip();
+        self.multiplicity = Some(multiplicity);
+        self.set_record_order(order);
+        self.set_message(format!(
+            "Collapsed {} reads into {} groups of identical sequences",
+            reads, distinct
+        ));
+    }
+
+    /// View the file from the first record at or after a byte offset, which becomes record 1
+    pub fn start_at_offset(&mut self, offset: u64) -> Result<(), std::io::Error> {
+        let start = self.reader.start_at_offset(offset)?;
+        self.bookmarks.clear();
+        self.match_cache.clear();
+        self.scroll_status = (0, 0);
+        self.window_start = 0;
+        self.update();
+        self.set_message(format!(
+            "Opened at the record starting at byte {}, records are numbered from there",
+            start
+        ));
+        Ok(())
+    }
+
+    /// Index of the first record whose ID (or its first word) equals the given ID
+    pub fn find_record_by_id(&mut self, id: &str) -> Result<Option<usize>, std::io::Error> {
+        let mut index = 0;
+        while let Some(record) = self.record_at(index)? {
+            if record.id() == id || record.id().split_whitespace().next() == Some(id) {
+                return Ok(Some(index));
+            }
+            index += 1;
+        }
+        Ok(None)
+    }
+
+    pub fn cycle_patterns_list(&mut self, reverse: bool) {
+        self.search_panel.cycle_patterns_list(reverse);
+    }
+
+    pub fn set_message(&mut self, msg: String) {
+        self.message = TransientMessage::new(msg);
+        // eprint!("\x07"); // sending BEL to terminal result in delayed rendering?
+    }
+
+    pub fn get_message(&mut self) -> Option<String> {
+        self.message.get()
+    }
+
+    /// Render the records again after the terminal was resized, keeping the record at the top;
+    /// the scroll within it is clamped as its lines wrap to a different height
+    pub fn resized_update(&mut self, tui_size: Size) {
+        let width = self.viewer_width(tui_size.width);
+        let no_wrap = self.styling_config.no_wrap;
+        self.update();
+        let next = self.record_at(self.scroll_status.0 + self.render_buf_size);
+        let max_scroll = if matches!(next, Ok(None)) {
+            // the last record is rendered, scrolled like in `scroll` at EOF
+            self.max_scroll_at_eof(width, no_wrap, tui_size.height)
+        } else {
+            self.rendered_records
+                .first_height(width, no_wrap)
+                .saturating_sub(1)
+        };
+        self.scroll_status.1 = self.scroll_status.1.min(max_scroll);
+    }
+
+    /// Rows the rendered lines scroll by at most once the last record is rendered
+    fn max_scroll_at_eof(&self, width: usize, no_wrap: bool, tui_height: u16) -> usize {
+        // 2 x boarders 1 char high, plus 1 empty line to indicate EOF
+        3 + self
+            .rendered_records
+            .height(width, no_wrap)
+            .saturating_sub(tui_height as usize)
+    }
+
+    /// full update
+    /// get lines from reader and render
+    pub fn update(&mut self) {
+        let mut positions: Vec<usize> =
+            (self.scroll_status.0..self.scroll_status.0 + self.render_buf_size).collect();
+        // fetched in file order, so grouped records don't rewind the reader for each record
+        if let Some(order) = &self.record_order {
+            positions.sort_by_key(|i| order.get(*i).copied().unwrap_or(usize::MAX));
+        }
+        let mut records: Vec<(usize, fastq::Record)> = Vec::with_capacity(positions.len());
+        let mut error: Option<String> = None;
+        for i in positions {
+            match self.record_at(i) {
+                Ok(Some(record)) => records.push((i, record)),
+                Ok(None) => {}
+                Err(e) => {
+                    error = Some(format!("Error reading record {}: {}", i + 1, e));
+                    break;
+                }
+            }
+        }
+        records.sort_by_key(|(i, _)| *i);
+        // render the records up to the first one missing, e.g. not fetched after a read error
+        // as grouped records are fetched in file order, so rendered_records[k] stays the
+        // record at scroll_status.0 + k
+        let first = self.scroll_status.0;
+        let run = records
+            .iter()
+            .enumerate()
+            .take_while(|(k, (i, _))| *i == first + k)
+            .count();
+        records.truncate(run);
+
+        if let Some(error) = error {
+            self.set_message(error);
+        } else if records.len() < self.render_buf_size && self.scroll_status.0 > 0 {
+            // no need to notify if the whole file fits in the buffer
+            self.set_message(format!(
+                "EOF reached during app.update, {} records rendered",
+                records.len()
+            ));
+        }
+        // fit the length gradient to the rendered records, kept while scrolling
+        self.length_scale = if self.styling_config.length_gradient {
+            self.styling_config.length_scale.or_else(|| {
+                let lengths = records.iter().map(|(_, record)| record.seq().len());
+                Some((lengths.clone().min()?, lengths.max()?))
+            })
+        } else {
+            None
+        };
+        self.rendered_records = self.records_to_lines(&records).into_iter().collect();
+        let counts = self.match_counts(records.iter().map(|(index, _)| *index));
+        self.search_panel
+            .set_match_counts(counts, &self.search_patterns);
+    }
+
+    /// Non-overlapping matches of each pattern in the cached records at the given positions
+    fn match_counts(&self, positions: impl Iterator<Item = usize>) -> Vec<usize> {
+        let mut counts = vec![0; self.search_patterns.len()];
+        for matches in positions.filter_map(|index| self.match_cache.get(&index)) {
+            for (count, hits) in counts.iter_mut().zip(&matches.hits) {
+                *count += Self::best_hits(hits.clone()).len();
+            }
+        }
+        counts
+    }
+
+    /// Search all enabled patterns in the records not in the match cache, e.g. after scrolling,
+    /// and edited patterns in the others, then style all records
+    fn records_to_lines<'a>(&mut self, records: &[(usize, fastq::Record)]) -> Vec<Vec<Line<'a>>> {
+        let cache_key = (
+            self.search_patterns.clone(),
+            self.styling_config.chimera_junctions,
+            self.styling_config.molecule(),
+        );
+        if (self.match_cache_key.1, self.match_cache_key.2) != (cache_key.1, cache_key.2) {
+            self.match_cache.clear();
+        } else if self.match_cache_key.0 != cache_key.0 {
+            // e.g. a pattern added or deleted: only search the new or edited patterns, in the
+            // records at hand, reusing the matches of the other patterns
+            let previous: Vec<Option<usize>> = cache_key
+                .0
+                .iter()
+                .map(|pattern| self.match_cache_key.0.iter().position(|x| x == pattern))
+                .collect();
+            let cache = std::mem::take(&mut self.match_cache);
+            self.match_cache = records
+                .par_iter()
+                .filter_map(|(index, record)| Some((*index, cache.get(index)?, record)))
+                .map(|(index, matches, record)| {
+                    let matches = Self::update_record_matches(
+                        matches,
+                        &previous,
+                        record,
+                        &self.search_patterns,
+                        self.styling_config.chimera_junctions,
+                        self.styling_config.molecule(),
+                    );
+                    (index, matches)
+                })
+                .collect();
+        }
+        self.match_cache_key = cache_key;
+        // only keep matches of records around the rendered window
+        let (first, size) = (self.scroll_status.0, self.render_buf_size);
+        self.match_cache
+            .retain(|index, _| *index + size >= first && *index < first + 2 * size);
+        // parallel by record
+        let searched: Vec<(usize, RecordMatches)> = records
+            .par_iter()
+            .filter(|(index, _)| !self.match_cache.contains_key(index))
+            .map(|(index, record)| {
+                (
+                    *index,
+                    Self::search_record(
+                        record,
+                        &self.search_patterns,
+                        self.styling_config.chimera_junctions,
+                        self.styling_config.molecule(),
+                    ),
+                )
+            })
+            .collect();
+        self.match_cache.extend(searched);
+        if !self.undisplayable_warned
+            && records.iter().any(|(_, record)| {
+                // the reader has already replaced non-ASCII bytes with '?'
+                record
+                    .seq()
+                    .iter()
+                    .any(|&base| !base.is_ascii_graphic() || base == UNDISPLAYABLE_BASE as u8)
+            })
+        {
+            self.undisplayable_warned = true;
+            self.set_message(format!(
+                "Warning: {} has sequences with non-ASCII or control bytes, shown as {}",
+                self.file.display(),
+                UNDISPLAYABLE_BASE
+            ));
+        }
+        records
+            .par_iter()
+            .map(|(index, record)| {
+                let mut lines = Self::record_to_lines(
+                    record,
+                    &self.search_patterns,
+                    &self.match_cache[index],
+                    &self.styling_config,
+                    self.length_scale,
+                    if *index == self.scroll_status.0 {
+                        self.window_start
+                    } else {
+                        0
+                    },
+                );
+                let count = self.multiplicity.as_ref().and_then(|x| x.get(*index));
+                if let Some(count) = count.filter(|x| **x > 1) {
+                    lines[0].spans.push(Span::styled(
+                        format!("  ×{}", count),
+                        Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                // IDs hold the whole header line, the table only its first word
+                let id = record.id().split_whitespace().next().unwrap_or_default();
+                if let Some(offset) = self.coordinate_offsets.get(id) {
+                    lines[0].spans.push(Span::styled(
+                        format!(
+                            "  [reference {}-{}]",
+                            offset + 1,
+                            offset + record.seq().len()
+                        ),
+                        Style::new().fg(Color::DarkGray),
+                    ));
+                }
+                lines
+            })
+            .collect()
+    }
+
+    /// Lines of a single record, e.g. scrolled into view
+    fn record_lines<'a>(&mut self, index: usize, record: fastq::Record) -> Vec<Line<'a>> {
+        self.records_to_lines(&[(index, record)])
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Matches of all patterns in a record, the part of rendering independent of styling
+    fn search_record(
+        record: &fastq::Record,
+        search_patterns: &[SearchPattern],
+        chimera_junctions: bool,
+        molecule: Molecule,
+    ) -> RecordMatches {
+        // parallel by pattern too, for many patterns on long reads; rayon balances this with the
+        // records searched in parallel
+        let (hits, junction_hits) = search_patterns
+            .par_iter()
+            .map(|pattern| Self::search_pattern(record, pattern, chimera_junctions, molecule))
+            .unzip();
+        RecordMatches {
+            hits,
+            junction_hits,
+            read_len: record.seq().len(),
+        }
+    }
+
+    /// Matches of a record after editing the patterns, `previous` holding the index of each
+    /// pattern in the patterns `matches` were searched for, if it was among them
+    fn update_record_matches(
+        matches: &RecordMatches,
+        previous: &[Option<usize>],
+        record: &fastq::Record,
+        search_patterns: &[SearchPattern],
+        chimera_junctions: bool,
+        molecule: Molecule,
+    ) -> RecordMatches {
+        let (hits, junction_hits) = search_patterns
+            .par_iter()
+            .zip(previous)
+            .map(|(pattern, previous)| match previous {
+                Some(i) => (matches.hits[*i].clone(), matches.junction_hits[*i].clone()),
+                None => Self::search_pattern(record, pattern, chimera_junctions, molecule),
+            })
+            .unzip();
+        RecordMatches {
+            hits,
+            junction_hits,
+            read_len: matches.read_len,
+        }
+    }
+
+    /// Matches and junction matches of one pattern in a record, none if it is disabled
+    fn search_pattern(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        chimera_junctions: bool,
+        molecule: Molecule,
+    ) -> (Vec<Hit>, Vec<(usize, usize)>) {
+        if !pattern.enabled {
+            return (Vec::new(), Vec::new());
+        }
+        let junction_hits = if chimera_junctions {
+            Self::search_junction(record, pattern, molecule)
+        } else {
+            Vec::new()
+        };
+        (
+            Self::search_with_alignment(record, pattern, molecule),
+            junction_hits,
+        )
+    }
+
+    fn record_to_lines<'a>(
+        record: &fastq::Record,
+        search_patterns: &[SearchPattern],
+        matches: &RecordMatches,
+        styling_config: &StylingConfig,
+        length_scale: Option<(usize, usize)>, // color the ID by length if given
+        window_start: usize, // first base rendered of reads longer than MAX_RENDERED_BASES
+    ) -> Vec<Line<'a>> {
+        let read_len = record.seq().len();
+        let window = rendered_window(read_len, window_start);
+        let mut style_input = StyleInput::new(Color::Gray);
+        style_input.match_modifier = styling_config.match_style.modifier();
+        let mut core_intervals: Vec<(usize, usize)> = Vec::new();
+        let mut id_line: Vec<Span> = vec![match length_scale {
+            Some((min, max)) => Span::styled(
+                record.id().to_string(),
+                Style::new().fg(length_color(record.seq().len(), min, max)),
+            ),
+            None => record.id().to_string().into(),
+        }];
+        for ((pattern, hits), junction_hits) in search_patterns
+            .iter()
+            .zip(&matches.hits)
+            .zip(&matches.junction_hits)
+            .filter(|((pattern, _), _)| pattern.enabled)
+        {
+            let mut intervals: Vec<(usize, usize)> = if styling_config.match_confidence {
+                style_input.fg_color_intervals.extend(confidence_layers(
+                    hits,
+                    pattern.color,
+                    pattern.edit_distance,
+                ));
+                Vec::new()
+            } else {
+                hits.iter()
+                    .map(|(start, end, _, _)| (*start, *end))
+                    .collect()
+            };
+            if !junction_hits.is_empty() {
+                id_line.push(Span::styled(
+                    format!(
+                        "  [possible chimera junction: {}]",
+                        if pattern.comment.is_empty() {
+                            &pattern.search_string
+                        } else {
+                            &pattern.comment
+                        }
+                    ),
+                    Style::new().fg(pattern.color),
+                ));
+                intervals.extend(junction_hits);
+            }
+            if styling_config.underline_core || styling_config.edit_markers {
+                for (start, _, _, ops) in Self::best_hits(hits.clone()) {
+                    if styling_config.underline_core {
+                        core_intervals.extend(exact_core(start, &ops));
+                    }
+                    if styling_config.edit_markers {
+                        style_input
+                            .edit_positions
+                            .extend(alignment_edits(start, &ops));
+                    }
+                }
+            }
+            let mut set = intervals.to_interval_set();
+            if styling_config.match_confidence {
+                // junction hits only where no hit is already in a confidence layer
+                let hit_set = hits
+                    .iter()
+                    .map(|(start, end, _, _)| (*start, *end))
+                    .collect::<Vec<(usize, usize)>>()
+                    .to_interval_set();
+                set = set.difference(&hit_set);
+            }
+            style_input.fg_color_intervals.push((set, pattern.color));
+        }
+        style_input.underline_intervals = core_intervals.to_interval_set();
+        if styling_config.trim_preview {
+            let hits: Vec<(usize, usize)> = matches
+                .hits
+                .iter()
+                .flat_map(|hits| hits.iter().map(|(start, end, _, _)| (*start, *end)))
+                .collect();
+            let (start, end) = trim_bounds(record.seq(), &hits, styling_config.trim_window);
+            let mut trimmed: Vec<(usize, usize)> = Vec::new();
+            if start > 0 {
+                trimmed.push((0, start - 1));
+            }
+            if end < read_len {
+                trimmed.push((end, read_len - 1));
+            }
+            style_input.crossed_out_intervals = trimmed.to_interval_set();
+            id_line.push(Span::styled(
+                format!("  [trimmed length: {}]", end - start),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        if styling_config.base_composition {
+            id_line.push(Span::styled(
+                format!(
+                    "  {}",
+                    base_composition(record.seq(), styling_config.protein)
+                ),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        if styling_config.quality_styling && has_quality(record) {
+            style_input.quality_intervals = get_quality_styling(
+                record.qual(),
+                styling_config.quality_threshold,
+                styling_config.quality_mode,
+            );
+            style_input.quality_modifier = styling_config.quality_mode.modifier();
+        } else if styling_config.quality_styling {
+            id_line.push(Span::styled(
+                "  [no quality]",
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        if styling_config.lowercase_italic {
+            style_input.italic_intervals = lowercase_intervals(record.seq());
+        }
+        if styling_config.base_colors {
+            let seq = &record.seq()[window.clone()];
+            style_input.base_color_intervals =
+                base_color_intervals(seq, window.start, styling_config.protein);
+        }
+        if styling_config.invert_match && read_len > 0 {
+            let matched: Vec<IntervalSet<usize>> = style_input
+                .fg_color_intervals
+                .drain(..)
+                .map(|(set, _)| set)
+                .collect();
+            let unmatched =
+                subtract_intervals(&vec![(0, read_len - 1)].to_interval_set(), &matched);
+            style_input
+                .fg_color_intervals
+                .push((unmatched, INVERTED_MATCH_COLOR));
+        }
+        if window.len() < read_len {
+            id_line.push(Span::styled(
+                format!(
+                    "  [bases {}-{} of {}]",
+                    window.start + 1,
+                    window.end,
+                    read_len
+                ),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        let seq = display_sequence(&record.seq()[window.clone()]);
+        let mut lines = vec![
+            Line::from(id_line),
+            highlight_window(&style_input, seq, window.start),
+        ];
+        if styling_config.show_quality_line && has_quality(record) {
+            // one character per base, so it wraps and scrolls in step with the sequence
+            lines.push(Line::styled(
+                String::from_utf8_lossy(&record.qual()[window.clone()]).into_owned(),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        if window.end < read_len {
+            lines.push(Line::styled(
+                format!("... {} more bases, scroll on", read_len - window.end),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        if styling_config.complement_track && !styling_config.protein {
+            // complement only, not reversed, so bases pair column by column
+            let complemented: Vec<u8> = record.seq()[window]
+                .iter()
+                .map(|x| complement(*x, styling_config.molecule()))
+                .collect();
+            lines.push(Line::styled(
+                display_sequence(&complemented),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        match styling_config.record_separator {
+            RecordSeparator::None => {}
+            RecordSeparator::Blank => lines.push(Line::default()),
+            RecordSeparator::Rule => lines.push(Line::styled(
+                "─".repeat(SEPARATOR_RULE_WIDTH),
+                Style::new().fg(Color::DarkGray),
+            )),
+        }
+        lines
+    }
+}
+
+#[test]
+fn test_validate_pattern() {
+    let mut pattern = SearchPattern::new(String::from("ACGTN"), Color::Red, 0, "");
+    assert_eq!(pattern.validate(DNA_ALPHABET), Ok(None));
+    let mut pattern = SearchPattern::new(String::from("acgtn"), Color::Red, 0, "");
+    assert!(pattern.validate(DNA_ALPHABET).unwrap().is_some());
+    assert_eq!(pattern.search_string, "ACGTN");
+    let mut pattern = SearchPattern::new(String::from("AC GT1 "), Color::Red, 0, "");
+    assert_eq!(
+        pattern.validate(DNA_ALPHABET),
+        Err(String::from(
+            "Pattern AC GT1  contains symbols outside the alphabet: ' ' '1'"
+        ))
+    );
+    // e.g. for protein patterns
+    let mut pattern = SearchPattern::new(String::from("MEEPQ"), Color::Red, 0, "");
+    assert!(pattern.validate(DNA_ALPHABET).is_err());
+    assert_eq!(pattern.validate("ACDEFGHIKLMNPQRSTVWY"), Ok(None));
+    // U would never match T in DNA reads, it is only allowed for RNA
+    let mut pattern = SearchPattern::new(String::from("AUCG"), Color::Red, 0, "");
+    assert_eq!(
+        pattern.validate(Molecule::Dna.alphabet()),
+        Err(String::from(
+            "Pattern AUCG contains symbols outside the alphabet: 'U'"
+        ))
+    );
+    assert_eq!(pattern.validate(Molecule::Rna.alphabet()), Ok(None));
+}
+
+#[test]
+fn test_pattern_group() {
+    let record = fastq::Record::with_attrs("group", None, b"AACCGGTTAATTGGCC", &[b'I'; 16]);
+    let pattern = SearchPattern::new(String::from("CCGG|TTGG"), Color::Red, 0, "variants");
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Dna),
+        vec![(2, 5), (10, 13)]
+    );
+    let mut pattern = SearchPattern::new(String::from("ccgg|ttgg"), Color::Red, 0, "");
+    assert!(pattern.validate(DNA_ALPHABET).unwrap().is_some());
+    assert_eq!(pattern.search_string, "CCGG|TTGG");
+    let mut pattern = SearchPattern::new(String::from("CCGG||TTGG"), Color::Red, 0, "");
+    assert!(pattern.validate(DNA_ALPHABET).is_err());
+}
+
+/// Write patterns as CSV with the header pattern,color,editdistance,comment, plus an
+/// algorithm column if any pattern is not matched with the default algorithm and a revcomp
+/// column if any pattern also matches its reverse complement
+pub fn write_patterns_csv<W: std::io::Write>(
+    search_patterns: &[SearchPattern],
+    writer: &mut csv::Writer<W>,
+) -> csv::Result<()> {
+    let with_algorithm = search_patterns
+        .iter()
+        .any(|x| x.algorithm != MatchAlgorithm::default());
+    let with_rev_comp = search_patterns.iter().any(|x| x.search_rev_comp);
+    let with_enabled = search_patterns.iter().any(|x| !x.enabled);
+    let mut header = vec!["pattern", "color", "editdistance", "comment"];
+    if with_algorithm {
+        header.push("algorithm");
+    }
+    if with_rev_comp {
+        header.push("revcomp");
+    }
+    if with_enabled {
+        header.push("enabled");
+    }
+    writer.write_record(header)?;
+    for pattern in search_patterns {
+        let mut record = vec![
+            pattern.search_string.clone(),
+            pattern.color.to_string(),
+            pattern.edit_distance.to_string(),
+            pattern.comment.to_string(),
+        ];
+        if with_algorithm {
+            record.push(pattern.algorithm.to_string());
+        }
+        if with_rev_comp {
+            record.push(pattern.search_rev_comp.to_string());
+        }
+        if with_enabled {
+            record.push(pattern.enabled.to_string());
+        }
+        writer.write_record(record)?;
+    }
+    Ok(())
+}
+
+/// Write patterns to a CSV file, gzip-compressed if the path ends with `.gz`
+pub fn save_patterns_csv(search_patterns: &[SearchPattern], path: &Path) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(OutputWriter::create(path)?);
+    write_patterns_csv(search_patterns, &mut writer)?;
+    writer.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[test]
+fn test_autosave() {
+    let (mut app, file) = fixture_app("@read\nACGT\n+\nIIII\n", Vec::new());
+    let autosave = TempFileGuard(file.with_extension("csv"));
+    app.autosave_path = Some(autosave.to_path_buf());
+    app.append_search_pattern(SearchPattern::new(
+        String::from("ACGT"),
+        Color::Red,
+        1,
+        "R1",
+    ));
+    // debounced, nothing written right after the edit
+    app.autosave_if_due();
+    assert!(!autosave.exists());
+    app.autosave_due = Some(Instant::now());
+    app.autosave_if_due();
+    assert_eq!(
+        std::fs::read_to_string(&autosave).unwrap(),
+        "pattern,color,editdistance,comment\nACGT,Red,1,R1\n"
+    );
+    assert_eq!(app.autosave_due, None);
+}
+
+#[test]
+fn test_save_patterns() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    let patterns = vec![SearchPattern::new(
+        String::from("ACGT"),
+        Color::Red,
+        1,
+        "R1",
+    )];
+    let (mut app, file) = fixture_app("@read\nACGT\n+\nIIII\n", patterns);
+    let type_path = |app: &mut App, path: &Path| {
+        app.search_panel.clear_file_save_popup();
+        for c in path.to_string_lossy().chars() {
+            app.search_panel
+                .file_popup_input(KeyEvent::from(KeyCode::Char(c)));
+        }
+    };
+    let saved = TempFileGuard(file.with_extension("csv"));
+    type_path(&mut app, &saved);
+    assert_eq!(app.save_patterns(), None);
+    assert_eq!(
+        std::fs::read_to_string(&saved).unwrap(),
+        "pattern,color,editdistance,comment\nACGT,Red,1,R1\n"
+    );
+    assert_eq!(app.save_patterns().as_deref(), Some("File already exists"));
+    // errors other than the common ones are reported too, e.g. a file as the directory
+    let path = file.join("patterns.csv");
+    type_path(&mut app, &path);
+    let message = app.save_patterns().unwrap();
+    assert!(message.starts_with(&format!("Error creating {}: ", path.display())));
+}
+
+#[test]
+fn test_adjust_edit_distance() {
+    let patterns = vec![SearchPattern::new(
+        String::from("ACG|TT"),
+        Color::Red,
+        1,
+        "",
+    )];
+    let (mut app, _file) = fixture_app("@read\nACGT\n+\nIIII\n", patterns);
+    // clamped to the length of the shortest member
+    for _ in 0..3 {
+        app.adjust_edit_distance(0, true);
+    }
+    assert_eq!(app.search_patterns[0].edit_distance, 2);
+    assert_eq!(app.match_cache_key.0, app.search_patterns);
+    for _ in 0..3 {
+        app.adjust_edit_distance(0, false);
+    }
+    assert_eq!(app.search_patterns[0].edit_distance, 0);
+}
+
+#[test]
+fn test_toggle_pattern() {
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let content = "@read0\nTTTT\n+\nIIII\n@read1\nACGT\n+\nIIII\n";
+    let (mut app, _file) = fixture_app(content, patterns);
+    app.toggle_pattern(0);
+    assert!(!app.search_patterns[0].enabled);
+    assert!(app.match_cache[&1].hits[0].is_empty());
+    app.jump_to_match(true);
+    assert_eq!(app.scroll_status.0, 0);
+    app.toggle_pattern(0);
+    assert_eq!(app.match_cache[&1].hits[0].len(), 1);
+    app.jump_to_match(true);
+    assert_eq!(app.scroll_status.0, 1);
+}
+
+#[test]
+fn test_move_search_pattern() {
+    let patterns: Vec<SearchPattern> = ["AC", "CG", "GT"]
+        .iter()
+        .map(|x| SearchPattern::new(x.to_string(), Color::Red, 0, ""))
+        .collect();
+    let (mut app, _file) = fixture_app("@read\nACGT\n+\nIIII\n", patterns);
+    let order = |app: &App| {
+        app.search_patterns
+            .iter()
+            .map(|x| x.search_string.clone())
+            .collect::<Vec<String>>()
+    };
+    app.move_search_pattern(0, false);
+    assert_eq!(order(&app), ["CG", "AC", "GT"]);
+    assert_eq!(app.search_panel.selected_pattern(), Some(1));
+    app.move_search_pattern(1, false);
+    app.move_search_pattern(2, false);
+    assert_eq!(order(&app), ["CG", "GT", "AC"]);
+    assert_eq!(app.search_panel.selected_pattern(), Some(2));
+    app.move_search_pattern(0, true);
+    assert_eq!(order(&app), ["CG", "GT", "AC"]);
+    assert_eq!(app.match_cache_key.0, app.search_patterns);
+}
+
+#[test]
+fn test_match_counts() {
+    let patterns = vec![
+        SearchPattern::new(String::from("ACGT"), Color::Red, 0, ""),
+        SearchPattern::new(String::from("GGGG"), Color::Blue, 0, ""),
+    ];
+    let content = "@read0\nACGTTACGT\n+\nIIIIIIIII\n@read1\nACGT\n+\nIIII\n";
+    let (mut app, _file) = fixture_app(content, patterns);
+    assert_eq!(app.search_panel.match_counts(), [3, 0]);
+    app.append_search_pattern(SearchPattern::new(String::from("TTA"), Color::Green, 0, ""));
+    assert_eq!(app.search_panel.match_counts(), [3, 0, 1]);
+    app.delete_search_pattern(0);
+    assert_eq!(app.search_panel.match_counts(), [0, 1]);
+    app.go_to_record(1);
+    assert_eq!(app.search_panel.match_counts(), [0, 0]);
+}
+
+#[test]
+fn test_find_read() {
+    let content: String = ["ACGT", "AAAA", "GAAAAG", "CCCC", "AAAAT"]
+        .iter()
+        .enumerate()
+        .map(|(i, seq)| format!("@read{}\n{}\n+\n{}\n", i, seq, "I".repeat(seq.len())))
+        .collect();
+    let patterns = vec![SearchPattern::new(
+        String::from("AAAA"),
+        Color::Red,
+        0,
+        "polyA",
+    )];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    app.find_read("2 polyA");
+    assert_eq!(app.scroll_status.0, 2);
+    // counted from below the top record, motifs need not be patterns
+    app.find_read("cccc");
+    assert_eq!(app.scroll_status.0, 3);
+    app.find_read("3 AAAA");
+    assert_eq!(app.scroll_status.0, 3);
+    assert!(app.get_message().unwrap().contains("only 1 of 3"));
+}
+
+#[test]
+fn test_go_to_record_prompt() {
+    let content: String = (0..5)
+        .map(|i| format!("@read{}\nACGT\n+\nIIII\n", i))
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    let go_to = |app: &mut App, input: &str| {
+        app.open_prompt(PromptKind::GoToRecord);
+        app.prompt.insert_str(input);
+        app.submit_prompt();
+    };
+    go_to(&mut app, "4");
+    assert_eq!((app.scroll_status.0, &app.mode), (3, &UIMode::Viewer));
+    assert_eq!(app.position_indicator(), "record 4 / 5 (80.0%)");
+    go_to(&mut app, "0");
+    assert_eq!(app.scroll_status.0, 3);
+    assert!(app
+        .get_message()
+        .unwrap()
+        .starts_with("Invalid record number"));
+    go_to(&mut app, "100");
+    assert_eq!(app.scroll_status.0, 4);
+}
+
+#[test]
+fn test_jump_to_match() {
+    let content: String = (0..8)
+        .map(|i| {
+            let seq = if i == 2 || i == 6 {
+                "TTACGTTT"
+            } else {
+                "TTTTTTTT"
+            };
+            format!("@read{}\n{}\n+\nIIIIIIII\n", i, seq)
+        })
+        .collect();
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    app.jump_to_match(true);
+    assert_eq!(app.scroll_status.0, 2);
+    app.jump_to_match(true);
+    assert_eq!(app.scroll_status.0, 6);
+    app.jump_to_match(true);
+    assert_eq!(app.scroll_status.0, 6);
+    assert_eq!(app.get_message().as_deref(), Some("No further matches"));
+    app.jump_to_match(false);
+    assert_eq!(app.scroll_status.0, 2);
+    app.jump_to_match(false);
+    assert_eq!(app.scroll_status.0, 2);
+    assert_eq!(app.get_message().as_deref(), Some("No previous matches"));
+}
+
+#[test]
+fn test_match_cache() {
+    let content: String = (0..5)
+        .map(|i| {
+            let seq = format!("ACGTTT{}", "GA".repeat(i));
+            format!("@read{}\n{}\n+\n{}\n", i, seq, "I".repeat(seq.len()))
+        })
+        .collect();
+    let mut patterns = vec![
+        SearchPattern::new(String::from("ACGT"), Color::Red, 0, ""),
+        SearchPattern::new(String::from("GAGA"), Color::Blue, 0, ""),
+    ];
+    let options = DecompressOptions::default();
+    let (mut app, file) = fixture_app(&content, patterns.clone());
+    // editing one pattern searches again instead of reusing the cached hits
+    patterns[1] = SearchPattern::new(String::from("TGA"), Color::Blue, 1, "");
+    app.set_search_patterns(patterns.clone());
+    assert_eq!(app.match_cache.len(), 5);
+    let mut reader = FastqReader::from_path(&file, &options).unwrap();
+    for (index, matches) in &app.match_cache {
+        let record = reader.get_index(*index).unwrap().unwrap();
+        assert_eq!(
+            *matches,
+            App::search_record(&record, &patterns, false, Molecule::Dna)
+        );
+    }
+    // adding or deleting a pattern keeps the cached hits of the others
+    app.match_cache.get_mut(&0).unwrap().hits[0].clear();
+    app.append_search_pattern(SearchPattern::new(String::from("TTT"), Color::Green, 0, ""));
+    assert!(app.match_cache[&0].hits[0].is_empty());
+    assert_eq!(app.match_cache[&0].hits[2].len(), 1);
+    assert_eq!(app.match_cache[&1].hits[0].len(), 1);
+    app.delete_search_pattern(1);
+    assert!(app.match_cache[&0].hits[0].is_empty());
+    assert_eq!(app.match_cache[&0].hits.len(), 2);
+    assert_eq!(app.match_cache[&0].hits[1][0].0, 3);
+}
+
+#[test]
+fn test_search_record_many_patterns() {
+    // 20 patterns searched in parallel on a long read, in pattern order
+    let mut state: u64 = 1;
+    let seq: Vec<u8> = (0..10000)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            b"ACGT"[(state >> 62) as usize]
+        })
+        .collect();
+    let record = fastq::Record::with_attrs("long", None, &seq, &vec![b'I'; seq.len()]);
+    let patterns: Vec<SearchPattern> = (0..20)
+        .map(|i| {
+            let search_string = String::from_utf8(seq[i * 37..i * 37 + 12 + i].to_vec()).unwrap();
+            SearchPattern::new(search_string, Color::Red, (i % 3) as u8, "")
+        })
+        .collect();
+    let matches = App::search_record(&record, &patterns, true, Molecule::Dna);
+    assert_eq!(matches.hits.len(), 20);
+    for (i, pattern) in patterns.iter().enumerate() {
+        let (hits, junction_hits) = App::search_pattern(&record, pattern, true, Molecule::Dna);
+        assert!(hits.iter().any(|hit| hit.0 == i * 37));
+        assert_eq!(matches.hits[i], hits);
+        assert_eq!(matches.junction_hits[i], junction_hits);
+    }
+}
+
+#[test]
+fn test_render_buf_size() {
+    let content: String = (0..6)
+        .map(|i| format!("@read{}\nACGT\n+\nIIII\n", i))
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    assert_eq!(app.rendered_records.len(), 6);
+    app.set_render_buf_size(3);
+    assert_eq!(app.rendered_records.len(), 3);
+    // scrolling past the first record renders the fourth
+    app.scroll(2, Size::new(80, 10));
+    assert_eq!(app.scroll_status, (1, 0));
+    assert_eq!(app.rendered_records.len(), 3);
+    assert_eq!(app.rendered_records.records[2][0].spans[0].content, "read3");
+}
+
+#[test]
+fn test_resized_update() {
+    let content: String = (0..4)
+        .map(|i| {
+            format!(
+                "@read{}\n{}\n+\n{}\n",
+                i,
+                "ACGT".repeat(25),
+                "I".repeat(100)
+            )
+        })
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    app.set_render_buf_size(2);
+    // 22 columns wrap the 100 bases to 5 rows, 3 rows into the second record
+    let narrow = Size::new(22, 10);
+    app.scroll(9, narrow);
+    assert_eq!(app.scroll_status, (1, 3));
+    app.resized_update(narrow);
+    assert_eq!(app.scroll_status, (1, 3));
+    // unwrapped at 102 columns the second record is 2 rows high, its last row at the top
+    app.resized_update(Size::new(102, 10));
+    assert_eq!(app.scroll_status, (1, 1));
+    assert_eq!(app.rendered_records.len(), 2);
+    // with all records rendered, scrolled within their height at EOF
+    app.set_render_buf_size(4);
+    app.back_to_top();
+    app.scroll(15, narrow);
+    assert_eq!(app.scroll_status, (0, 15));
+    app.resized_update(Size::new(102, 10));
+    assert_eq!(app.scroll_status, (0, 3));
+}
+
+#[test]
+fn test_scroll_pages() {
+    let content: String = (0..10)
+        .map(|i| format!("@read{}\nACGT\n+\nIIII\n", i))
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    app.set_render_buf_size(4);
+    app.scroll_config = ScrollConfig {
+        step: 1,
+        half_page: 0.5,
+        full_page: 1.0,
+    };
+    // records are 2 rows high, 8 rows in the viewer between the borders
+    let size = Size::new(40, 10);
+    let half_page = ScrollConfig::page_lines(app.scroll_config.half_page, size.height - 2);
+    let page = ScrollConfig::page_lines(app.scroll_config.full_page, size.height - 2);
+    assert_eq!((half_page, page), (4, 8));
+    app.scroll(-half_page, size);
+    assert_eq!(app.scroll_status, (0, 0));
+    assert_eq!(app.get_message().as_deref(), Some("Hit top"));
+    app.scroll(half_page, size);
+    assert_eq!(app.scroll_status, (2, 0));
+    app.scroll(page, size);
+    assert_eq!(app.scroll_status, (6, 0));
+    // the last 4 records are rendered, scrolled within them up to the end of the file
+    app.scroll(page, size);
+    assert_eq!(app.scroll_status, (6, 3));
+    assert_eq!(app.get_message().as_deref(), Some("Hit bottom"));
+    app.scroll(-half_page, size);
+    assert_eq!(app.scroll_status, (5, 1));
+    app.scroll(-page, size);
+    assert_eq!(app.scroll_status, (1, 1));
+    app.scroll(-page, size);
+    assert_eq!(app.scroll_status, (0, 0));
+}
+
+#[test]
+fn test_records_up_to_gap() {
+    let content: String = (0..5)
+        .map(|i| format!("@read{}\nACGT\n+\nIIII\n", i))
+        .collect();
+    let (mut app, _file) = fixture_app(&content, Vec::new());
+    let ids = |app: &App| -> Vec<String> {
+        app.rendered_records
+            .lines()
+            .step_by(2)
+            .map(|x| x.to_string())
+            .collect()
+    };
+    // position 1 is missing although position 2 was fetched before it in file order
+    app.set_record_order(vec![3, 99, 0]);
+    assert_eq!(ids(&app), vec!["read3"]);
+    app.scroll(1, Size::new(40, 10));
+    assert_eq!(app.scroll_status.0, 0);
+    // a buffer left short is refilled before scrolling past its records
+    app.set_record_order(vec![4, 3, 2, 1, 0]);
+    app.set_render_buf_size(3);
+    app.rendered_records.pop_back();
+    app.rendered_records.pop_back();
+    app.scroll(2, Size::new(40, 10));
+    assert_eq!(app.scroll_status, (1, 0));
+    assert_eq!(ids(&app), vec!["read3", "read2", "read1"]);
+}
+
+#[test]
+fn test_long_read_windows() {
+    let long = "ACGT".repeat((2 * MAX_RENDERED_BASES + 12) / 4);
+    let content: String = ["AC", long.as_str(), "GT"]
+        .iter()
+        .enumerate()
+        .map(|(i, seq)| format!("@read{}\n{}\n+\n{}\n", i, seq, "I".repeat(seq.len())))
+        .collect();
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    let size = Size::new(MAX_RENDERED_BASES as u16 / 10 + 2, 20);
+    let seq_width = |app: &App| app.rendered_records.lines().nth(1).unwrap().width();
+
+    // ID line, 10 rows of the first window and a line telling more bases follow
+    app.go_to_record(1);
+    assert_eq!(seq_width(&app), MAX_RENDERED_BASES);
+    assert_eq!(
+        app.rendered_records
+            .first_height(size.width as usize - 2, false),
+        12
+    );
+    // scrolling past the window moves to the next one instead of the next record
+    app.scroll(12, size);
+    assert_eq!(
+        (app.scroll_status, app.window_start),
+        ((1, 0), MAX_RENDERED_BASES)
+    );
+    app.scroll(-1, size);
+    assert_eq!((app.scroll_status, app.window_start), ((1, 11), 0));
+    app.scroll(-13, size);
+    assert_eq!((app.scroll_status, app.window_start), ((0, 0), 0));
+    // scrolling up into a long read starts from its last window
+    app.go_to_record(2);
+    app.scroll(-1, size);
+    assert_eq!(app.scroll_status.0, 1);
+    assert_eq!(app.window_start, 2 * MAX_RENDERED_BASES);
+    assert_eq!(seq_width(&app), 12);
+}
+
+#[test]
+fn test_non_ascii_sequence() {
+    let mut content = b"@read\nAC\xc3\xa9ACGT\xffA\n+\nIIIIIIIIII\n".to_vec();
+    content.extend(b"@next\nACGT\n+\nIIII\n");
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    // one character per byte, so the match stays on the bytes it matched
+    let line = app.rendered_records.lines().nth(1).unwrap().clone();
+    assert_eq!(
+        line,
+        Line::from(vec![
+            Span::raw("AC??"),
+            Span::styled("ACGT", Style::new().fg(Color::Red)),
+            Span::raw("?A"),
+        ])
+    );
+    assert!(app.get_message().unwrap().contains("non-ASCII"));
+    assert_eq!(
+        app.rendered_records.lines().nth(2).unwrap().to_string(),
+        "next"
+    );
+}
+
+#[test]
+fn test_match_statistics() {
+    let records = [
+        "GGACGTACGTGG",
+        "GGACGTTCGTGG",
+        "GGACCTTCGTGG",
+        "GGGGGGGGGGGG",
+    ];
+    let content: String = records
+        .iter()
+        .enumerate()
+        .map(|(i, seq)| format!("@read{}\n{}\n+\n{}\n", i, seq, "I".repeat(seq.len())))
+        .collect();
+    let patterns = vec![
+        SearchPattern::new(String::from("ACGTACGT"), Color::Red, 2, ""),
+        SearchPattern::new(String::from("GGGG"), Color::Blue, 0, ""),
+        SearchPattern::new(String::from("GGCGG"), Color::Green, 1, ""),
+    ];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    let stats = app.match_statistics();
+    assert_eq!(stats.records, 4);
+    assert_eq!(stats.histograms[..2], [vec![1, 1, 1], vec![3]]);
+    // never matching exactly, so likely too permissive
+    assert_eq!(stats.histograms[2][0], 0);
+    assert_eq!(stats.over_permissive(), vec![2]);
+    app.toggle_match_statistics();
+    assert_eq!(app.mode, UIMode::MatchStatistics);
+    app.toggle_match_statistics();
+    assert_eq!(app.mode, UIMode::Viewer);
+}
+
+/// A viewer of a temporary FASTQ file of `content`, removed with the returned guard
+#[cfg(test)]
+fn fixture_app(
+    content: impl AsRef<[u8]>,
+    patterns: Vec<SearchPattern>,
+) -> (App<'static>, TempFileGuard) {
+    let file = TempFileGuard::with_content("fastq", content);
+    let app = App::new(
+        &file,
+        patterns,
+        Keymap::default(),
+        &DecompressOptions::default(),
+    )
+    .unwrap();
+    (app, file)
+}
+
+/// A record with quality `I` (40) except '#' (2) at the `low_quality` positions
+#[cfg(test)]
+fn fixture_record(id: &str, seq: &str, low_quality: &[usize]) -> fastq::Record {
+    let qual: Vec<u8> = (0..seq.len())
+        .map(|i| if low_quality.contains(&i) { b'#' } else { b'I' })
+        .collect();
+    fastq::Record::with_attrs(id, None, seq.as_bytes(), &qual)
+}
+
+/// Lines of a record as rendered in the viewer, searched and styled end to end
+#[cfg(test)]
+fn render_fixture(
+    record: &fastq::Record,
+    patterns: &[SearchPattern],
+    styling_config: &StylingConfig,
+) -> Vec<Line<'static>> {
+    let matches = App::search_record(
+        record,
+        patterns,
+        styling_config.chimera_junctions,
+        styling_config.molecule(),
+    );
+    App::record_to_lines(record, patterns, &matches, styling_config, None, 0)
+}
+
+#[test]
+fn test_golden_highlighting() {
+    let record = fixture_record("golden", "TTACGTACGTTTGGCCGTAAtt", &[10, 11]);
+    let patterns = vec![
+        SearchPattern::new(String::from("ACGTACGT"), Color::Red, 0, ""),
+        SearchPattern::new(String::from("GTACGTTT"), Color::Blue, 0, ""),
+        SearchPattern::new(String::from("CCGGAA"), Color::Green, 1, ""),
+    ];
+    let styling_config = StylingConfig {
+        edit_markers: true,
+        quality_styling: true,
+        lowercase_italic: true,
+        ..StylingConfig::default()
+    };
+    let style = |color: Color| Style::new().fg(color);
+    // red and blue overlap in gray, the substitution in the fuzzy green match is bold,
+    // low quality bases dim and lowercase bases italic, each on top of the match color
+    assert_eq!(
+        render_fixture(&record, &patterns, &styling_config),
+        vec![
+            Line::from("golden"),
+            Line::from(vec![
+                Span::raw("TT"),
+                Span::styled("AC", style(Color::Red)),
+                Span::styled("GTACGT", style(Color::Gray)),
+                Span::styled("TT", style(Color::Blue).add_modifier(Modifier::DIM)),
+                Span::raw("GG"),
+                Span::styled("CCG", style(Color::Green)),
+                Span::styled("T", style(Color::Green).add_modifier(Modifier::BOLD)),
+                Span::styled("AA", style(Color::Green)),
+                Span::styled("tt", Style::new().add_modifier(Modifier::ITALIC)),
+            ]),
+        ]
+    );
+    // the same record with only the match colors
+    let plain = render_fixture(&record, &patterns, &StylingConfig::default());
+    assert_eq!(
+        plain[1],
+        Line::from(vec![
+            Span::raw("TT"),
+            Span::styled("AC", style(Color::Red)),
+            Span::styled("GTACGT", style(Color::Gray)),
+            Span::styled("TT", style(Color::Blue)),
+            Span::raw("GG"),
+            Span::styled("CCGTAA", style(Color::Green)),
+            Span::raw("tt"),
+        ])
+    );
+}
+
+#[test]
+fn test_quality_line() {
+    let styling_config = StylingConfig {
+        show_quality_line: true,
+        ..StylingConfig::default()
+    };
+    let record = fixture_record("qual", "ACGTAC", &[1, 4]);
+    let lines: Vec<String> = render_fixture(&record, &[], &styling_config)
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(lines, vec!["qual", "ACGTAC", "I#II#I"]);
+    // records without quality scores, e.g. from FASTA, are left as they are
+    let record = fastq::Record::with_attrs("noqual", None, b"ACGT", b"");
+    assert_eq!(render_fixture(&record, &[], &styling_config).len(), 2);
+}
+
+#[test]
+fn test_collapse_records() {
+    let content: String = ["ACGT", "ACGT", "TTTT", "ACGT"]
+        .iter()
+        .enumerate()
+        .map(|(i, seq)| format!("@read{}\n{}\n+\nIIII\n", i, seq))
+        .collect();
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let (mut app, _file) = fixture_app(&content, patterns);
+    app.collapse_records(vec![(0, 3), (2, 1)]);
+    let lines: Vec<String> = app
+        .rendered_records
+        .lines()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(lines, vec!["read0  ×3", "ACGT", "read2", "TTTT"]);
+}
+
+#[test]
+fn test_coordinate_offsets() {
+    let content = "@read0 runid=abc ch=7\nACGT\n+\nIIII\n@read1\nACGT\n+\nIIII\n";
+    let (mut app, _file) = fixture_app(content, Vec::new());
+    app.coordinate_offsets = HashMap::from([(String::from("read0"), 100)]);
+    app.update();
+    let lines: Vec<String> = app
+        .rendered_records
+        .lines()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(
+        lines[..3],
+        ["read0 runid=abc ch=7  [reference 101-104]", "ACGT", "read1"]
+    );
+}
+
+#[test]
+fn test_malformed_records() {
+    let content = "@read0\nACGT\n+\nIIII\n\
+                   @read1\nACGT\n+\nII\n\
+                   @read2\nACGT\n+\nIIII\n\
+                   @read3\nACGT\nIIII\n\
+                   @read4\nACGT\n+\nIIII\n";
+    let (mut app, _file) = fixture_app(content, Vec::new());
+    let message = app.get_message().unwrap();
+    // the last one skipped, the third record if it were valid
+    assert_eq!(
+        message,
+        "Skipped malformed record 3 (byte 55): no '+' line after the sequence of @read3 \
+         (and 1 more malformed records skipped)"
+    );
+    let ids: Vec<String> = app
+        .rendered_records
+        .lines()
+        .step_by(2)
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(ids, vec!["read0", "read2", "read4"]);
+    assert_eq!(app.total_records(), Some(3));
+}
+
+#[test]
+fn test_missing_quality() {
+    let (mut app, _file) = fixture_app("@noqual\nACGT\n+\n\n@qual\nACGT\n+\n++II\n", Vec::new());
+    // not enabled when the record at the top has no quality scores
+    app.toggle_styling(StylingToggle::Quality);
+    assert!(!app.styling_config.quality_styling);
+    assert!(app.get_message().unwrap().starts_with("No quality scores"));
+    app.go_to_record(1);
+    app.toggle_styling(StylingToggle::Quality);
+    assert!(app.styling_config.quality_styling);
+    app.go_to_record(0);
+    let lines: Vec<Line> = app.rendered_records.lines().cloned().collect();
+    assert_eq!(lines[0].to_string(), "noqual  [no quality]");
+    assert_eq!(lines[1], Line::from("ACGT"));
+    assert_eq!(
+        lines[3],
+        Line::from(vec![
+            Span::styled("AC", Style::new().add_modifier(Modifier::DIM)),
+            Span::raw("GT"),
+        ])
+    );
+}
@@ -0,0 +1,644 @@
+use super::{revcomp, App, MatchAlgorithm, Molecule, SearchPattern, PATTERN_MEMBER_SEPARATOR};
+use bio::alignment::pairwise::Aligner;
+use bio::alignment::AlignmentOperation;
+use bio::io::fastq;
+use bio::pattern_matching::myers::long::Myers as MyersLong;
+use bio::pattern_matching::myers::{BitVec, Myers, MyersBuilder};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+#[cfg(test)]
+use super::{DNA_ALPHABET, RNA_ALPHABET};
+#[cfg(test)]
+use ratatui::prelude::Color;
+
+/// Compiled patterns kept by each thread, the most recently used ones so that edited
+/// patterns and jump motifs don't pile up over a session
+const PATTERN_CACHE_SIZE: usize = 64;
+
+/// Least recently used cache of compiled patterns, small enough to be searched linearly
+struct PatternCache<K, V> {
+    entries: VecDeque<(K, V)>, // least recently used first
+}
+
+impl<K: PartialEq, V> PatternCache<K, V> {
+    const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// The value of a key, built and cached if missing, evicting the least recently used
+    /// value if the cache is full
+    fn get_or_try_insert<E>(
+        &mut self,
+        key: K,
+        build: impl FnOnce() -> Result<V, E>,
+    ) -> Result<&mut V, E> {
+        match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(i) => {
+                let entry = self.entries.remove(i).unwrap();
+                self.entries.push_back(entry);
+            }
+            None => {
+                let value = build()?;
+                if self.entries.len() >= PATTERN_CACHE_SIZE {
+                    self.entries.pop_front();
+                }
+                self.entries.push_back((key, value));
+            }
+        }
+        Ok(&mut self.entries.back_mut().unwrap().1)
+    }
+}
+
+thread_local! {
+    // compiled regex patterns by search string, as records are searched one at a time
+    static REGEX_CACHE: RefCell<PatternCache<String, regex::bytes::Regex>> =
+        const { RefCell::new(PatternCache::new()) };
+    // built Myers automata by search string and RNA mode, one bit vector type per string
+    static MYERS_CACHE: RefCell<PatternCache<(String, Molecule), Box<dyn Any>>> =
+        const { RefCell::new(PatternCache::new()) };
+}
+
+/// Compile a regex pattern, matching case insensitively like the fuzzy patterns do
+pub fn compile_regex(search_string: &str) -> Result<regex::bytes::Regex, regex::Error> {
+    REGEX_CACHE.with_borrow_mut(|cache| {
+        let regex = cache.get_or_try_insert(search_string.to_string(), || {
+            regex::bytes::RegexBuilder::new(search_string)
+                .case_insensitive(true)
+                .build()
+        })?;
+        Ok(regex.clone())
+    })
+}
+
+/// IUPAC ambiguity codes allowed in patterns and the symbols they match
+const IUPAC_CODES: [(u8, &[u8]); 11] = [
+    (b'M', b"AC"),
+    (b'R', b"AG"),
+    (b'W', b"AT"),
+    (b'S', b"CG"),
+    (b'Y', b"CT"),
+    (b'K', b"GT"),
+    (b'V', b"ACGMRS"),
+    (b'H', b"ACTMWY"),
+    (b'D', b"AGTRWK"),
+    (b'B', b"CGTSYK"),
+    (b'N', b"ACGTMRWSYKVHDB"),
+];
+
+/// Amino acid ambiguity codes and the amino acids they match
+const PROTEIN_CODES: [(u8, &[u8]); 4] = [
+    (b'B', b"DN"),
+    (b'Z', b"EQ"),
+    (b'J', b"IL"),
+    (b'X', b"ACDEFGHIKLMNPQRSTVWYUOBZJ"),
+];
+
+/// Symbols each pattern symbol matches besides itself: the IUPAC codes, plus T and U matching
+/// each other for RNA reads, codes matching T then also match U; for proteins the amino acid
+/// codes only. Read symbols match case insensitively (soft-masked bases), for pattern symbols
+/// upper-cased before matching
+fn ambiguity_codes(molecule: Molecule) -> Vec<(u8, Vec<u8>)> {
+    let table: &[(u8, &[u8])] = match molecule {
+        Molecule::Protein => &PROTEIN_CODES,
+        _ => &IUPAC_CODES,
+    };
+    let mut codes: Vec<(u8, Vec<u8>)> = table
+        .iter()
+        .map(|(code, bases)| (*code, bases.to_vec()))
+        .collect();
+    if molecule == Molecule::Rna {
+        for (_, bases) in codes.iter_mut() {
+            if bases.contains(&b'T') {
+                bases.push(b'U');
+            }
+        }
+        codes.push((b'T', b"U".to_vec()));
+        codes.push((b'U', b"T".to_vec()));
+    }
+    for (code, bases) in codes.iter_mut() {
+        let lower: Vec<u8> = bases.iter().map(u8::to_ascii_lowercase).collect();
+        bases.extend(lower);
+        bases.push(code.to_ascii_lowercase());
+    }
+    for symbol in b'A'..=b'Z' {
+        if !codes.iter().any(|(code, _)| *code == symbol) {
+            codes.push((symbol, vec![symbol.to_ascii_lowercase()]));
+        }
+    }
+    codes
+}
+
+/// Myers matcher builder with the pattern symbols' `ambiguity_codes`
+fn myers_builder(molecule: Molecule) -> MyersBuilder {
+    let mut builder = MyersBuilder::new();
+    for (base, equivalents) in ambiguity_codes(molecule) {
+        builder.ambig(base, equivalents);
+    }
+    builder
+}
+
+/// Run `search` with the Myers automaton of a search string, built by `build` unless it was
+/// searched for recently, as the automaton only depends on the pattern and not on the read
+fn with_myers<M: 'static, R>(
+    search_string: &str,
+    molecule: Molecule,
+    build: impl FnOnce(&MyersBuilder, Vec<u8>) -> M,
+    search: impl FnOnce(&mut M) -> R,
+) -> R {
+    MYERS_CACHE.with_borrow_mut(|cache| {
+        let myers = cache
+            .get_or_try_insert((search_string.to_string(), molecule), || {
+                let pattern = search_string.to_ascii_uppercase().into_bytes();
+                Ok::<Box<dyn Any>, ()>(Box::new(build(&myers_builder(molecule), pattern)))
+            })
+            .unwrap()
+            .downcast_mut::<M>()
+            .expect("Myers automaton of another bit vector type cached");
+        search(myers)
+    })
+}
+
+/// Remove greedy fuzzy matches that extend better matches with mismatches only, keeping
+/// the rest ordered by edit distance
+fn remove_greedy_extensions(
+    mut matches: Vec<(usize, usize, usize, Vec<AlignmentOperation>)>,
+) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+    matches.sort_by_key(|(_, _, dist, _)| *dist);
+    let mut filtered_matches: Vec<(usize, usize, usize, Vec<AlignmentOperation>)> = Vec::new();
+    for m in matches {
+        if !filtered_matches.iter().any(|(_, end, dist, _)| {
+            // m.1 - end == m.2 - dist
+            m.1 + dist == m.2 + end && m.2 != 0
+        }) {
+            filtered_matches.push(m);
+        }
+    }
+    filtered_matches
+}
+
+// affine gap scoring of MatchAlgorithm::AffineGap in quarter edits, a gap of length k
+// costs AFFINE_GAP_OPEN + k * AFFINE_GAP_EXTEND, e.g. a 4bp deletion costs 2 edits
+const AFFINE_SCALE: i32 = 4;
+const AFFINE_MISMATCH: i32 = -4;
+const AFFINE_GAP_OPEN: i32 = -4;
+const AFFINE_GAP_EXTEND: i32 = -1;
+
+impl App<'_> {
+    pub fn search(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize)> {
+        Self::search_with_alignment(record, pattern, molecule)
+            .into_iter()
+            .map(|(start, end, _, _)| (start, end))
+            .collect()
+    }
+
+    /// Search the read followed by its reverse complement for matches spanning the junction,
+    /// i.e. a potential fold-back chimera where the read continues into its own reverse
+    /// complement. Returns (start, end (inclusive)) of the covered read tail for each match.
+    pub fn search_junction(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize)> {
+        let len = record.seq().len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let mut seq = record.seq().to_vec();
+        seq.extend(revcomp(record.seq(), molecule));
+        let mut qual = record.qual().to_vec();
+        qual.extend(record.qual().iter().rev());
+        let concatenated = fastq::Record::with_attrs(record.id(), None, &seq, &qual);
+        Self::best_hits(Self::search_with_alignment(
+            &concatenated,
+            pattern,
+            molecule,
+        ))
+        .into_iter()
+        .filter(|(start, end, _, _)| *start < len && *end >= len)
+        // position len + k of the reverse complement is the complement of len - 1 - k
+        .map(|(start, end, _, _)| (start.min(2 * len - 1 - end), len - 1))
+        .collect()
+    }
+
+    /// Search with alignment path, U and T matching each other for RNA
+    /// returns (start, end (inclusive), edit distance, alignment operations) for each match
+    pub fn search_with_alignment(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+        if pattern.algorithm == MatchAlgorithm::Regex {
+            return Self::search_regex(record, pattern);
+        }
+        if pattern.search_rev_comp {
+            let mut hits: Vec<(usize, usize, usize, Vec<AlignmentOperation>)> = pattern
+                .strands(molecule)
+                .iter()
+                .flat_map(|(strand, _)| Self::search_with_alignment(record, strand, molecule))
+                .collect();
+            // palindromes match on both strands
+            hits.sort_by_key(|(start, end, _, _)| (*start, *end));
+            hits.dedup_by_key(|(start, end, _, _)| (*start, *end));
+            return hits;
+        }
+        if pattern.search_string.contains(PATTERN_MEMBER_SEPARATOR) {
+            let mut hits: Vec<(usize, usize, usize, Vec<AlignmentOperation>)> = pattern
+                .members()
+                .flat_map(|member| {
+                    let member = SearchPattern {
+                        search_string: member.to_string(),
+                        ..pattern.clone()
+                    };
+                    Self::search_with_alignment(record, &member, molecule)
+                })
+                .collect();
+            hits.sort_by_key(|(start, _, _, _)| *start);
+            return hits;
+        }
+        if pattern.algorithm == MatchAlgorithm::AffineGap {
+            return Self::search_affine(record, pattern, molecule);
+        }
+        // the smallest bit vector holding one bit per pattern symbol
+        match pattern.search_string.len() {
+            0..=8 => Self::search_generic::<u8>(record, pattern, molecule),
+            9..=16 => Self::search_generic::<u16>(record, pattern, molecule),
+            17..=32 => Self::search_generic::<u32>(record, pattern, molecule),
+            33..=64 => Self::search_generic::<u64>(record, pattern, molecule),
+            _ => Self::search_long(record, pattern, molecule),
+        }
+    }
+
+    /// Non-overlapping regex matches as exact matches, empty matches skipped; an invalid
+    /// regex matches nothing (patterns are validated when added)
+    fn search_regex(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+        let Ok(regex) = compile_regex(&pattern.search_string) else {
+            return Vec::new();
+        };
+        regex
+            .find_iter(record.seq())
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                (
+                    m.start(),
+                    m.end() - 1,
+                    0,
+                    vec![AlignmentOperation::Match; m.len()],
+                )
+            })
+            .collect()
+    }
+
+    /// Search with affine gap penalties: the best alignment of the pattern in the read, then
+    /// in the parts of the read left and right of it, until none is within the edit distance
+    fn search_affine(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+        let pattern_seq = pattern.search_string.to_ascii_uppercase().into_bytes();
+        let pattern_seq = pattern_seq.as_slice();
+        let codes = ambiguity_codes(molecule);
+        let score = |a: u8, b: u8| {
+            if a == b
+                || codes
+                    .iter()
+                    .any(|(code, bases)| *code == a && bases.contains(&b))
+            {
+                0
+            } else {
+                AFFINE_MISMATCH
+            }
+        };
+        let mut aligner = Aligner::with_capacity(
+            pattern_seq.len(),
+            record.seq().len(),
+            AFFINE_GAP_OPEN,
+            AFFINE_GAP_EXTEND,
+            score,
+        );
+        let mut hits: Vec<(usize, usize, usize, Vec<AlignmentOperation>)> = Vec::new();
+        let mut segments: Vec<(usize, usize)> = vec![(0, record.seq().len())];
+        while let Some((from, to)) = segments.pop() {
+            if from >= to {
+                continue;
+            }
+            let alignment = aligner.semiglobal(pattern_seq, &record.seq()[from..to]);
+            let dist = ((-alignment.score) as usize).div_ceil(AFFINE_SCALE as usize);
+            if dist > pattern.edit_distance.into() || alignment.yend == alignment.ystart {
+                continue;
+            }
+            let (start, end) = (from + alignment.ystart, from + alignment.yend - 1);
+            let ops = alignment
+                .operations
+                .into_iter()
+                .filter(|op| {
+                    !matches!(
+                        op,
+                        AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_)
+                    )
+                })
+                .collect();
+            hits.push((start, end, dist, ops));
+            segments.push((from, start));
+            segments.push((end + 1, to));
+        }
+        hits.sort_by_key(|(start, _, _, _)| *start);
+        hits
+    }
+
+    fn search_generic<T: BitVec>(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)>
+    where
+        <T as BitVec>::DistType: From<u8> + Into<usize>,
+    {
+        let build =
+            |builder: &MyersBuilder, pattern: Vec<u8>| -> Myers<T> { builder.build(pattern) };
+        let matches = with_myers(&pattern.search_string, molecule, build, |myers| {
+            let mut lazy_matches = myers.find_all_lazy(record.seq(), pattern.edit_distance.into());
+            let ends = lazy_matches
+                .by_ref()
+                .collect::<Vec<(usize, <T as BitVec>::DistType)>>();
+            ends.into_iter()
+                .map(|(end, _)| {
+                    let mut ops: Vec<AlignmentOperation> = Vec::new();
+                    let (start, dist) = lazy_matches
+                        .path_at(end, &mut ops)
+                        .expect("Failed to trace back match");
+                    (start, end, dist.into(), ops)
+                })
+                .collect::<Vec<(usize, usize, usize, Vec<AlignmentOperation>)>>()
+        });
+        remove_greedy_extensions(matches)
+    }
+
+    /// Search patterns longer than 64 symbols, which don't fit one bit vector, with the
+    /// block-based Myers algorithm
+    fn search_long(
+        record: &fastq::Record,
+        pattern: &SearchPattern,
+        molecule: Molecule,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+        let build = |builder: &MyersBuilder, pattern: Vec<u8>| -> MyersLong<u64> {
+            builder.build_long(pattern)
+        };
+        let matches = with_myers(&pattern.search_string, molecule, build, |myers| {
+            let mut lazy_matches = myers.find_all_lazy(record.seq(), pattern.edit_distance.into());
+            let ends = lazy_matches.by_ref().collect::<Vec<(usize, usize)>>();
+            ends.into_iter()
+                .map(|(end, _)| {
+                    let mut ops: Vec<AlignmentOperation> = Vec::new();
+                    let (start, dist) = lazy_matches
+                        .path_at(end, &mut ops)
+                        .expect("Failed to trace back match");
+                    (start, end, dist, ops)
+                })
+                .collect::<Vec<(usize, usize, usize, Vec<AlignmentOperation>)>>()
+        });
+        remove_greedy_extensions(matches)
+    }
+
+    /// Reduce hits from `search_with_alignment` to non-overlapping ones
+    /// preferring lower edit distance, then longer hits; sorted by start position
+    pub fn best_hits(
+        mut hits: Vec<(usize, usize, usize, Vec<AlignmentOperation>)>,
+    ) -> Vec<(usize, usize, usize, Vec<AlignmentOperation>)> {
+        hits.sort_by_key(|(start, end, dist, _)| (*dist, usize::MAX - (end - start)));
+        let mut ret: Vec<(usize, usize, usize, Vec<AlignmentOperation>)> = Vec::new();
+        for hit in hits {
+            if ret
+                .iter()
+                .all(|(start, end, _, _)| hit.1 < *start || hit.0 > *end)
+            {
+                ret.push(hit);
+            }
+        }
+        ret.sort_by_key(|(start, _, _, _)| *start);
+        ret
+    }
+}
+
+#[test]
+fn test_pattern_cache() {
+    let mut cache: PatternCache<usize, usize> = PatternCache::new();
+    let mut builds = 0;
+    let mut get = |cache: &mut PatternCache<usize, usize>, key: usize| {
+        *cache
+            .get_or_try_insert(key, || {
+                builds += 1;
+                Ok::<_, ()>(key * 2)
+            })
+            .unwrap()
+    };
+    for key in 0..PATTERN_CACHE_SIZE {
+        get(&mut cache, key);
+    }
+    assert_eq!(get(&mut cache, 0), 0); // cached, now the most recently used
+    get(&mut cache, PATTERN_CACHE_SIZE); // evicts 1
+    assert_eq!(cache.entries.len(), PATTERN_CACHE_SIZE);
+    assert_eq!(get(&mut cache, 0), 0);
+    assert_eq!(get(&mut cache, 1), 2);
+    assert_eq!(builds, PATTERN_CACHE_SIZE + 2);
+}
+
+#[test]
+fn test_myers_bit_width_boundaries() {
+    for len in [7, 8, 15, 16, 31, 32, 63, 64] {
+        let pattern_seq: Vec<u8> = (0..len).map(|i| b"ACGT"[i * 7 % 4]).collect();
+        let mut seq = b"TTTTT".to_vec();
+        seq.extend(&pattern_seq);
+        seq.extend(b"TTTTT");
+        let record = fastq::Record::with_attrs("len", None, &seq, &vec![b'I'; seq.len()]);
+        let pattern =
+            SearchPattern::new(String::from_utf8(pattern_seq).unwrap(), Color::Red, 0, "");
+        assert!(
+            App::search(&record, &pattern, Molecule::Dna).contains(&(5, 4 + len)),
+            "pattern length {}",
+            len
+        );
+    }
+}
+
+#[test]
+fn test_search_affine() {
+    // two adaptors with a 4bp deletion, the second one also with a substitution
+    let adaptor = "CTACACGACGCTCTTCCGATCT";
+    let record = fastq::Record::with_attrs(
+        "deletion",
+        None,
+        b"GGGGGGCTACAGCTCTTCCGATCTGGGGGGGGCTACAGCTCTTCCAATCTGGGG",
+        &[b'I'; 54],
+    );
+    let pattern = SearchPattern::new(String::from(adaptor), Color::Red, 3, "");
+    // each deletion costs 4 edits with Myers
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+    let pattern = pattern.with_algorithm(MatchAlgorithm::AffineGap);
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Dna),
+        vec![(6, 23), (32, 49)]
+    );
+}
+
+#[test]
+fn test_search_junction() {
+    // read tail ACTTGC followed by its reverse complement GCAAGT forms ACTTGCGCAA
+    let record = fastq::Record::with_attrs("chimera", None, b"GGGGGGGGACTTGC", b"IIIIIIIIIIIIII");
+    let pattern = SearchPattern::new(String::from("ACTTGCGCAA"), Color::Red, 0, "");
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+    assert_eq!(
+        App::search_junction(&record, &pattern, Molecule::Dna),
+        vec![(8, 13)]
+    );
+    // matches within the read are not junction matches
+    let pattern = SearchPattern::new(String::from("GGGGACTT"), Color::Red, 0, "");
+    assert!(App::search_junction(&record, &pattern, Molecule::Dna).is_empty());
+}
+
+#[test]
+fn test_search_rna() {
+    let record = fastq::Record::with_attrs("rna", None, b"GGAUCGGG", b"IIIIIIII");
+    let pattern = SearchPattern::new(String::from("ATCG"), Color::Red, 0, "");
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+    assert_eq!(App::search(&record, &pattern, Molecule::Rna), vec![(2, 5)]);
+    let pattern = pattern.with_algorithm(MatchAlgorithm::AffineGap);
+    assert_eq!(App::search(&record, &pattern, Molecule::Rna), vec![(2, 5)]);
+    // U patterns match T, IUPAC codes matching T also match U
+    let record = fastq::Record::with_attrs("dna", None, b"GGATCGGG", b"IIIIIIII");
+    let pattern = SearchPattern::new(String::from("AUCG"), Color::Red, 0, "");
+    assert_eq!(App::search(&record, &pattern, Molecule::Rna), vec![(2, 5)]);
+    let pattern = SearchPattern::new(String::from("AYCG"), Color::Red, 0, "");
+    let record = fastq::Record::with_attrs("rna", None, b"AUCG", b"IIII");
+    assert_eq!(App::search(&record, &pattern, Molecule::Rna), vec![(0, 3)]);
+    assert_eq!(revcomp(b"AUCG", Molecule::Rna), b"CGAU");
+    assert_eq!(revcomp(b"ATCG", Molecule::Dna), b"CGAT");
+}
+
+#[test]
+fn test_search_protein() {
+    // R, N and B are nucleotide ambiguity codes for DNA but amino acids for proteins
+    let record = fastq::Record::with_attrs("protein", None, b"MKRGNDLLEQ", b"");
+    let pattern = SearchPattern::new(String::from("GRN"), Color::Red, 0, "");
+    assert!(App::search(&record, &pattern, Molecule::Protein).is_empty());
+    let pattern = SearchPattern::new(String::from("RGN"), Color::Red, 0, "");
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Protein),
+        vec![(2, 4)]
+    );
+    let pattern = SearchPattern::new(String::from("GBDJXZ"), Color::Red, 0, "");
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Protein),
+        vec![(3, 8)]
+    );
+    let pattern = pattern.with_algorithm(MatchAlgorithm::AffineGap);
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Protein),
+        vec![(3, 8)]
+    );
+    // nothing is reverse complemented
+    let pattern = SearchPattern::new(String::from("MK"), Color::Red, 0, "").with_rev_comp(true);
+    assert_eq!(pattern.strands(Molecule::Protein).len(), 1);
+    assert_eq!(Molecule::from_flags(true, true), Molecule::Protein);
+    assert_eq!(Molecule::from_flags(true, false).alphabet(), RNA_ALPHABET);
+}
+
+#[test]
+fn test_search_long_pattern() {
+    // a 100 bp pattern in a read with 2 substitutions, and as soft-masked bases
+    let pattern_seq: String = "ACGTTGCAAG".repeat(10);
+    let mut seq = format!("TTTTT{}GGGGG", pattern_seq).into_bytes();
+    seq[20] = b'A';
+    seq[70] = b'C';
+    let record = fastq::Record::with_attrs("long", None, &seq, &vec![b'I'; seq.len()]);
+    let pattern = SearchPattern::new(pattern_seq.clone(), Color::Red, 5, "");
+    let hits = App::best_hits(App::search_with_alignment(&record, &pattern, Molecule::Dna));
+    assert_eq!(
+        hits.iter().map(|x| (x.0, x.1, x.2)).collect::<Vec<_>>(),
+        vec![(5, 104, 2)]
+    );
+    let pattern = SearchPattern::new(pattern_seq.clone(), Color::Red, 1, "");
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+    let seq = format!("TT{}", pattern_seq.to_lowercase()).into_bytes();
+    let record = fastq::Record::with_attrs("masked", None, &seq, &vec![b'I'; seq.len()]);
+    assert!(App::search(&record, &pattern, Molecule::Dna).contains(&(2, 101)));
+}
+
+#[test]
+fn test_search_soft_masked() {
+    // soft-masked lowercase bases match upper case patterns with both algorithms
+    let record = fastq::Record::with_attrs("masked", None, b"GGatCGnnACGTgg", b"IIIIIIIIIIIIII");
+    for algorithm in [MatchAlgorithm::Myers, MatchAlgorithm::AffineGap] {
+        let pattern =
+            SearchPattern::new(String::from("ATCG"), Color::Red, 0, "").with_algorithm(algorithm);
+        assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(2, 5)]);
+        let pattern =
+            SearchPattern::new(String::from("acgtGG"), Color::Red, 0, "").with_algorithm(algorithm);
+        assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(8, 13)]);
+        let pattern =
+            SearchPattern::new(String::from("CGNNA"), Color::Red, 0, "").with_algorithm(algorithm);
+        assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(4, 8)]);
+    }
+    // soft-masked U matches T for RNA reads
+    let record = fastq::Record::with_attrs("rna", None, b"GGaucGGG", b"IIIIIIII");
+    let pattern = SearchPattern::new(String::from("ATCG"), Color::Red, 0, "");
+    assert_eq!(App::search(&record, &pattern, Molecule::Rna), vec![(2, 5)]);
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+}
+
+#[test]
+fn test_search_regex() {
+    let seq = b"ACGTAAGTAAAAaaaaaa";
+    let record = fastq::Record::with_attrs("polya", None, seq, &vec![b'I'; seq.len()]);
+    let pattern = SearchPattern::new(String::from("A{10,}$"), Color::Red, 3, "polyA")
+        .with_algorithm(MatchAlgorithm::Regex)
+        .with_rev_comp(true);
+    // one member despite the |, not reverse complemented
+    assert_eq!(pattern.members().count(), 1);
+    assert_eq!(pattern.strands(Molecule::Dna).len(), 1);
+    assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(8, 17)]);
+    let pattern = SearchPattern::new(String::from("GT|TAAG"), Color::Red, 0, "")
+        .with_algorithm(MatchAlgorithm::Regex);
+    assert_eq!(pattern.members().collect::<Vec<_>>(), vec!["GT|TAAG"]);
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Dna),
+        vec![(2, 3), (6, 7)]
+    );
+    let mut pattern = SearchPattern::new(String::from("A{2,"), Color::Red, 0, "")
+        .with_algorithm(MatchAlgorithm::Regex);
+    assert!(pattern.validate(DNA_ALPHABET).is_err());
+    assert!(App::search(&record, &pattern, Molecule::Dna).is_empty());
+    assert_eq!("regex".parse::<MatchAlgorithm>(), Ok(MatchAlgorithm::Regex));
+}
+
+#[test]
+fn test_search_rev_comp() {
+    // ACGGA forward at 0 and reverse complemented (TCCGT) at 7
+    let record = fastq::Record::with_attrs("read", None, b"ACGGATTTCCGTT", b"IIIIIIIIIIIII");
+    let pattern = SearchPattern::new(String::from("ACGGA|GGG"), Color::Red, 0, "");
+    assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(0, 4)]);
+    let pattern = pattern.with_rev_comp(true);
+    let strands = pattern.strands(Molecule::Dna);
+    assert_eq!(strands[1].0.search_string, "TCCGT|CCC");
+    assert_eq!(strands[1].1, '-');
+    assert!(!strands[1].0.search_rev_comp);
+    assert_eq!(
+        App::search(&record, &pattern, Molecule::Dna),
+        vec![(0, 4), (7, 11)]
+    );
+    // palindromes are found once
+    let pattern = SearchPattern::new(String::from("AATT"), Color::Red, 0, "").with_rev_comp(true);
+    let record = fastq::Record::with_attrs("read", None, b"GAATTC", b"IIIIII");
+    assert_eq!(App::search(&record, &pattern, Molecule::Dna), vec![(1, 4)]);
+}
@@ -0,0 +1,283 @@
+use super::{has_quality, App, SearchPattern};
+use crate::io::clipboard::{copy_to_clipboard, Copied};
+use crate::io::output::OutputWriter;
+use crate::io::view_export::{write_lines, ViewFormat};
+use crate::match_summarizing::{fmt_summarised_reads, summarise_reads};
+use bio::io::fastq;
+use ratatui::prelude::Line;
+use std::io::Write;
+use std::path::Path;
+
+#[cfg(test)]
+use super::{fixture_app, PromptKind, UIMode};
+#[cfg(test)]
+use crate::io::fastq::{DecompressOptions, FastqReader};
+#[cfg(test)]
+use crate::test_utils::TempFileGuard;
+#[cfg(test)]
+use ratatui::prelude::Color;
+#[cfg(test)]
+use tui_textarea::TextArea;
+
+/// Default files the selected records and their IDs are written to, see
+/// `App::export_selection`
+pub const SELECTION_FASTQ_FILE: &str = "seqsizzle-selection.fastq";
+pub const SELECTION_IDS_FILE: &str = "seqsizzle-selection-ids.txt";
+/// Default file the rendered view is exported to, see `App::export_view`
+pub const VIEW_EXPORT_FILE: &str = "seqsizzle-view.html";
+
+/// The sequence of a record, or the whole record as FASTQ (FASTA without quality scores)
+fn record_text(record: &fastq::Record, full: bool) -> String {
+    let seq = String::from_utf8_lossy(record.seq());
+    if !full {
+        return seq.to_string();
+    }
+    let header = match record.desc() {
+        Some(desc) => format!("{} {}", record.id(), desc),
+        None => record.id().to_string(),
+    };
+    if has_quality(record) {
+        let qual = String::from_utf8_lossy(record.qual());
+        format!("@{}\n{}\n+\n{}\n", header, seq, qual)
+    } else {
+        format!(">{}\n{}\n", header, seq)
+    }
+}
+
+impl App<'_> {
+    /// Start (or end) the selection at the record at the top of the viewer, the other end
+    /// is kept or also set to that record if nothing is selected yet
+    pub fn select_range_edge(&mut self, start: bool) {
+        let index = self.scroll_status.0;
+        let (first, last) = match (self.selection, start) {
+            (None, _) => (index, index),
+            (Some((_, last)), true) => (index.min(last), index.max(last)),
+            (Some((first, _)), false) => (first.min(index), first.max(index)),
+        };
+        self.selection = Some((first, last));
+        self.set_message(format!(
+            "Selected records {}-{} ({} records)",
+            first + 1,
+            last + 1,
+            last - first + 1
+        ));
+    }
+
+    pub fn clear_selection(&mut self) {
+        if self.selection.take().is_some() {
+            self.set_message(String::from("Selection cleared"));
+        }
+    }
+
+    /// Records of the selection read one at a time, empty if nothing is selected
+    fn selected_records(
+        &mut self,
+    ) -> Box<dyn Iterator<Item = Result<fastq::Record, std::io::Error>> + '_> {
+        let positions = match self.selection {
+            Some((first, last)) => first..last + 1,
+            None => 0..0,
+        };
+        Box::new(positions.map_while(|index| self.record_at(index).transpose()))
+    }
+
+    /// Write the selected records to a FASTQ file, or their IDs one per line, streamed from
+    /// the reader
+    pub fn export_selection(&mut self, path: &Path, ids_only: bool) {
+        if self.selection.is_none() {
+            return self.set_message(String::from("No records selected, select with [ and ]"));
+        }
+        let result = OutputWriter::create(path).and_then(|mut writer| {
+            let mut written = 0;
+            if ids_only {
+                for record in self.selected_records() {
+                    writeln!(writer, "{}", record?.id())?;
+                    written += 1;
+                }
+            } else {
+                let mut fastq_writer = fastq::Writer::new(&mut writer);
+                for record in self.selected_records() {
+                    fastq_writer.write_record(&record?)?;
+                    written += 1;
+                }
+                fastq_writer.flush()?;
+            }
+            writer.finish()?;
+            Ok(written)
+        });
+        self.set_message(match result {
+            Ok(n) if ids_only => format!("Wrote the IDs of {} records to {}", n, path.display()),
+            Ok(n) => format!("Wrote {} records to {}", n, path.display()),
+            Err(e) => format!("Error writing {}: {}", path.display(), e),
+        });
+    }
+
+    /// Write the selected records, or the records rendered around the screen, with their
+    /// colors and styles as HTML (`.html` files) or ANSI escape codes (otherwise)
+    pub fn export_view(&mut self, path: &Path) {
+        let positions = match self.selection {
+            Some((first, last)) => first..last + 1,
+            None => self.scroll_status.0..self.scroll_status.0 + self.rendered_records.len(),
+        };
+        let mut records: Vec<(usize, fastq::Record)> = Vec::with_capacity(positions.len());
+        for index in positions {
+            match self.record_at(index) {
+                Ok(record) => records.extend(record.map(|record| (index, record))),
+                Err(e) => return self.set_message(format!("Error reading the records: {}", e)),
+            }
+        }
+        let lines: Vec<Line> = self
+            .records_to_lines(&records)
+            .into_iter()
+            .flatten()
+            .collect();
+        let result = OutputWriter::create(path).and_then(|mut writer| {
+            write_lines(&mut writer, &lines, ViewFormat::from_path(path))?;
+            writer.finish()
+        });
+        self.set_message(match result {
+            Ok(()) => format!(
+                "Wrote {} rendered records to {}",
+                records.len(),
+                path.display()
+            ),
+            Err(e) => format!("Error writing {}: {}", path.display(), e),
+        });
+    }
+
+    /// Copy the sequence of the record at the top to the clipboard, or the whole record as
+    /// FASTQ (FASTA without quality scores) if `full`
+    pub fn copy_record(&mut self, full: bool) {
+        let record = match self.record_at(self.scroll_status.0) {
+            Ok(Some(record)) => record,
+            Ok(None) => return self.set_message(String::from("No record to copy")),
+            Err(e) => return self.set_message(format!("Error reading the record: {}", e)),
+        };
+        let text = record_text(&record, full);
+        let what = if full {
+            format!("record {}", record.id())
+        } else {
+            format!(
+                "the sequence of {} ({} bases)",
+                record.id(),
+                record.seq().len()
+            )
+        };
+        self.set_message(match copy_to_clipboard(&text) {
+            Ok(Copied::Clipboard) => format!("Copied {} to the clipboard", what),
+            Ok(Copied::Terminal { truncated: false }) => {
+                format!("Sent {} to the terminal (OSC 52) to copy", what)
+            }
+            Ok(Copied::Terminal { truncated: true }) => format!(
+                "Sent {} to the terminal (OSC 52) to copy, likely too long to be copied whole",
+                what
+            ),
+            Err(e) => format!("Error copying to the clipboard: {}", e),
+        });
+    }
+
+    /// Summarize the pattern combinations of the selected records in a popup, as the
+    /// summarize subcommand does for the whole file
+    pub fn summarize_selection(&mut self) {
+        let Some((first, last)) = self.selection else {
+            return self.set_message(String::from("No records selected, select with [ and ]"));
+        };
+        let patterns: Vec<SearchPattern> = self
+            .search_patterns
+            .iter()
+            .filter(|x| x.enabled)
+            .cloned()
+            .collect();
+        let molecule = self.styling_config.molecule();
+        match summarise_reads(self.selected_records(), &patterns, true, molecule) {
+            Ok(summary) => self.show_report(
+                format!("Records {}-{}", first + 1, last + 1),
+                fmt_summarised_reads(&summary, true, &[]),
+            ),
+            Err(e) => self.set_message(format!("Error reading the selected records: {}", e)),
+        }
+    }
+}
+
+#[test]
+fn test_selection() {
+    let content: String = (0..6)
+        .map(|i| {
+            format!(
+                "@read{}\n{}\n+\nIIII\n",
+                i,
+                if i % 2 == 0 { "ACGT" } else { "TTTT" }
+            )
+        })
+        .collect();
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let options = DecompressOptions::default();
+    let (mut app, file) = fixture_app(&content, patterns);
+    app.summarize_selection();
+    assert_eq!(app.mode, UIMode::Viewer);
+    app.open_prompt(PromptKind::ExportSelection(false));
+    assert_eq!(app.mode, UIMode::Viewer);
+    // the end is marked before the start, either order selects the records in between
+    app.go_to_record(3);
+    app.select_range_edge(false);
+    app.go_to_record(1);
+    app.select_range_edge(true);
+    assert_eq!(app.selection, Some((1, 3)));
+
+    let out = TempFileGuard(file.with_extension("ids.txt"));
+    app.open_prompt(PromptKind::ExportSelection(true));
+    assert_eq!(app.prompt.lines(), [SELECTION_IDS_FILE]);
+    app.prompt = TextArea::from([out.to_string_lossy()]);
+    app.submit_prompt();
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        "read1\nread2\nread3\n"
+    );
+    app.export_selection(&out, false);
+    let mut reader = FastqReader::from_path(&out, &options).unwrap();
+    assert_eq!(reader.get_index(1).unwrap().unwrap().id(), "read2");
+    assert!(reader.get_index(3).unwrap().is_none());
+
+    app.summarize_selection();
+    assert_eq!(app.mode, UIMode::Report);
+    assert_eq!(app.report.title, "Records 2-4");
+    assert_eq!(app.report.text, "1\tACGT\n2\t..\n");
+    app.close_report();
+    app.clear_selection();
+    assert_eq!(app.selection, None);
+}
+
+#[test]
+fn test_export_view() {
+    let content: String = (0..3)
+        .map(|i| format!("@read{}\nTTACGT\n+\nIIIIII\n", i))
+        .collect();
+    let patterns = vec![SearchPattern::new(String::from("ACGT"), Color::Red, 0, "")];
+    let (mut app, file) = fixture_app(&content, patterns);
+    let out = TempFileGuard(file.with_extension("ansi"));
+    app.export_view(&out);
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        "read0\nTT\x1b[31mACGT\x1b[0m\n".to_owned()
+            + "read1\nTT\x1b[31mACGT\x1b[0m\n"
+            + "read2\nTT\x1b[31mACGT\x1b[0m\n"
+    );
+    // only the selected records, as HTML
+    app.selection = Some((1, 1));
+    let html = TempFileGuard(file.with_extension("html"));
+    app.export_view(&html);
+    let written = std::fs::read_to_string(&html).unwrap();
+    assert!(written.contains("read1\nTT<span style=\"color: #cd0000\">ACGT</span>\n</pre>"));
+    assert!(!written.contains("read0"));
+}
+
+#[test]
+fn test_record_text() {
+    let record = fastq::Record::with_attrs("read", Some("barcode=AC"), b"ACGT", b"II5+");
+    assert_eq!(record_text(&record, false), "ACGT");
+    assert_eq!(
+        record_text(&record, true),
+        "@read barcode=AC\nACGT\n+\nII5+\n"
+    );
+    let record = fastq::Record::with_attrs("read", None, b"ACGT", b"");
+    assert_eq!(record_text(&record, true), ">read\nACGT\n");
+}
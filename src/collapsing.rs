@@ -0,0 +1,84 @@
+use bio::io::fastq;
+use std::collections::HashMap;
+
+/// Which identical reads are collapsed into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CollapseMode {
+    /// runs of identical sequences in a row
+    Consecutive,
+    /// identical sequences anywhere in the file
+    All,
+}
+
+/// Index of the first read of each run (or set) of identical sequences and the number of reads
+/// in it, in order of first occurrence. `All` keeps every distinct sequence in memory.
+/// The reads should be read as the viewer reads them, see `FastqReader::into_records`
+pub fn collapse_records(
+    reads: impl Iterator<Item = Result<fastq::Record, std::io::Error>>,
+    mode: CollapseMode,
+) -> Result<Vec<(usize, usize)>, String> {
+    let mut collapsed: Vec<(usize, usize)> = Vec::new();
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new(); // sequence -> index in collapsed
+    let mut previous: Option<Vec<u8>> = None;
+    for (index, read) in reads.enumerate() {
+        let read = read.map_err(|e| format!("Invalid FASTQ record {}: {}", index + 1, e))?;
+        match mode {
+            CollapseMode::Consecutive => {
+                if previous.as_deref() == Some(read.seq()) {
+                    collapsed.last_mut().unwrap().1 += 1;
+                } else {
+                    collapsed.push((index, 1));
+                    previous = Some(read.seq().to_vec());
+                }
+            }
+            CollapseMode::All => match seen.get(read.seq()) {
+                Some(&i) => collapsed[i].1 += 1,
+                None => {
+                    seen.insert(read.seq().to_vec(), collapsed.len());
+                    collapsed.push((index, 1));
+                }
+            },
+        }
+    }
+    Ok(collapsed)
+}
+
+#[test]
+fn test_collapse_records() {
+    let record = |seq: &str| {
+        Ok(fastq::Record::with_attrs(
+            "id",
+            None,
+            seq.as_bytes(),
+            &vec![b'I'; seq.len()],
+        ))
+    };
+    let reads = || ["ACGT", "ACGT", "TTTT", "ACGT", "ACGT", "ACGT", "GG"].map(record);
+    assert_eq!(
+        collapse_records(reads().into_iter(), CollapseMode::Consecutive).unwrap(),
+        vec![(0, 2), (2, 1), (3, 3), (6, 1)]
+    );
+    assert_eq!(
+        collapse_records(reads().into_iter(), CollapseMode::All).unwrap(),
+        vec![(0, 5), (2, 1), (6, 1)]
+    );
+}
+
+#[test]
+fn test_collapse_records_malformed() {
+    use crate::io::fastq::FastqReader;
+    use crate::test_utils::TempFileGuard;
+    // the malformed record is skipped, so the two ACGT reads are consecutive at 0 and 1
+    let file = TempFileGuard::with_content(
+        "fastq",
+        "@a\nACGT\n+\nIIII\n@bad\nTT\n+\nI\n@b\nACGT\n+\nIIII\n@c\nGG\n+\nII\n",
+    );
+    let reads = FastqReader::new(std::fs::File::open(&file).unwrap())
+        .unwrap()
+        .into_records()
+        .unwrap();
+    let collapsed = collapse_records(reads, CollapseMode::Consecutive).unwrap();
+    assert_eq!(collapsed, vec![(0, 2), (2, 1)]);
+    let mut reader = FastqReader::new(std::fs::File::open(&file).unwrap()).unwrap();
+    assert_eq!(reader.get_index(2).unwrap().unwrap().id(), "c");
+}
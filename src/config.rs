@@ -0,0 +1,136 @@
+use crate::keymap::Keymap;
+use crate::presets::DEFAULT_PALETTE;
+use anyhow::Context;
+use ratatui::prelude::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Settings read from the TOML config file, e.g.
+/// ```toml
+/// alphabet = "ACGTN"
+/// palette = ["blue", "#ff8800", "green"]
+///
+/// [scroll]
+/// step = 2
+/// half_page = 0.5
+///
+/// [keymap.viewer]
+/// scroll_down = ["n", "down"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// symbols allowed in patterns, defaults to DNA bases and IUPAC codes
+    pub alphabet: Option<String>,
+    /// colors assigned in turn to preset adaptors and FASTA patterns
+    pub palette: Option<Vec<String>>,
+    /// scroll amounts of the viewer
+    pub scroll: ScrollConfig,
+    /// keybinding overrides: context -> action name -> keys
+    pub keymap: HashMap<String, HashMap<String, Vec<String>>>,
+}
+
+/// Lines scrolled per key press, pages as fractions of the viewer height
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrollConfig {
+    pub step: usize,
+    pub half_page: f32,
+    pub full_page: f32,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        ScrollConfig {
+            step: 1,
+            half_page: 0.4,
+            full_page: 1.0,
+        }
+    }
+}
+
+impl ScrollConfig {
+    /// Lines scrolled by a page of `fraction`, at least one line
+    pub fn page_lines(fraction: f32, viewer_height: u16) -> isize {
+        ((viewer_height as f32 * fraction).floor() as isize).max(1)
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/seqsizzle/config.toml`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|x| !x.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("seqsizzle").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Error reading config file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Error parsing config file {}", path.display()))
+    }
+
+    /// The configured palette, or the default one
+    pub fn palette(&self) -> anyhow::Result<Vec<Color>> {
+        let Some(names) = &self.palette else {
+            return Ok(DEFAULT_PALETTE.to_vec());
+        };
+        if names.is_empty() {
+            anyhow::bail!("Invalid palette: no colors");
+        }
+        names
+            .iter()
+            .map(|x| Color::from_str(x).map_err(|_| anyhow::anyhow!("Invalid palette color {}", x)))
+            .collect()
+    }
+
+    /// The scroll amounts, checked to scroll at least a line and at most a screen
+    pub fn scroll(&self) -> anyhow::Result<ScrollConfig> {
+        let scroll = self.scroll;
+        if scroll.step == 0 {
+            anyhow::bail!("Invalid scroll step: 0");
+        }
+        for (name, fraction) in [
+            ("half_page", scroll.half_page),
+            ("full_page", scroll.full_page),
+        ] {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                anyhow::bail!(
+                    "Invalid scroll {}: {}, expected a fraction of the screen in (0, 1]",
+                    name,
+                    fraction
+                );
+            }
+        }
+        Ok(scroll)
+    }
+
+    pub fn keymap(&self) -> anyhow::Result<Keymap> {
+        Keymap::from_config(&self.keymap).map_err(|e| anyhow::anyhow!("Invalid keymap: {}", e))
+    }
+}
+
+#[test]
+fn test_scroll_config() {
+    let config: Config = toml::from_str("[scroll]\nstep = 3\nfull_page = 0.9").unwrap();
+    let scroll = config.scroll().unwrap();
+    assert_eq!(
+        scroll,
+        ScrollConfig {
+            step: 3,
+            half_page: 0.4,
+            full_page: 0.9
+        }
+    );
+    assert_eq!(ScrollConfig::page_lines(scroll.full_page, 40), 36);
+    assert_eq!(ScrollConfig::page_lines(scroll.half_page, 1), 1);
+    let config: Config = toml::from_str("[scroll]\nhalf_page = 1.5").unwrap();
+    assert!(config.scroll().is_err());
+    let config: Config = toml::from_str("[scroll]\nstep = 0").unwrap();
+    assert!(config.scroll().is_err());
+}
@@ -1,10 +1,14 @@
-use crate::app::{App, SearchPattern, UIMode};
-use crate::search_panel::{PanelElement, PanelElementName};
+use crate::app::{App, MatchAlgorithm, PromptKind, SearchPattern, StylingToggle, UIMode};
+use crate::config::ScrollConfig;
+use crate::keymap::{Action, KeyBinding, KeyContext, KeyLookup};
+use crate::search_panel::PanelElementName;
 use crate::{Event, Tui};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::prelude::{Color, Size};
 use std::str::FromStr;
 
+const HORIZONTAL_SCROLL_STEP: isize = 10;
+
 pub enum Update {
     SearchPanelFocusNext(bool),
     SearchPanelInput(KeyEvent),
@@ -12,8 +16,24 @@ pub enum Update {
     ToggleFilePopup,
     EditSearchPattern(SearchPatternEdit),
     CycleSearchPattern(bool),
+    EditBookmarks(BookmarkEdit),
+    ToggleBookmarksPopup,
+    CycleBookmarks(bool),
+    SaveState,
+    EditSelection(SelectionEdit),
+    CloseReport,
+    ExplainMatches,
+    ToggleMatchStatistics,
+    ShowHelp,
+    CopyRecord(bool),  // whole record, not only the sequence?
+    JumpToMatch(bool), // forward?
+    OpenPrompt(PromptKind),
+    PromptInput(KeyEvent),
+    SubmitPrompt,
     ToggleUIMode,
+    ToggleStyling(StylingToggle),
     ScrollViewer(isize),
+    ScrollViewerHorizontal(isize),
     WindowResize(Size),
     Msg(String),
     Quit,
@@ -21,39 +41,85 @@ pub enum Update {
 }
 
 pub enum SearchPatternEdit {
-    Delete(usize, bool), // (index, pop into edit boxes?)
+    Delete(usize, bool),         // (index, pop into edit boxes?)
+    AdjustDistance(usize, bool), // (index, widen?)
+    Toggle(usize),               // enable or disable
+    Move(usize, bool),           // (index, up?)
     Append(SearchPattern),
 }
 
+pub enum BookmarkEdit {
+    Toggle, // bookmark the current record or remove its bookmark
+    Delete, // delete the selected bookmark
+    Jump,   // jump to the selected bookmark
+}
+
+pub enum SelectionEdit {
+    Start, // start the selection at the top record
+    End,   // end the selection at the top record
+    Clear,
+    Export(bool), // ask where to write the selected records, or only their IDs if true
+    Summarize,
+}
+
 pub fn handle_input(app: &App, tui: &Tui, input: Event) -> Update {
     match input {
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        }) => Update::Quit,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('/'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        }) => Update::ToggleUIMode,
-        Event::Key(KeyEvent { 
-            code: KeyCode::Char('f'),
-            modifiers: KeyModifiers::CONTROL,
-            .. 
-        }) => Update::ToggleUIMode,
         Event::Key(keyevent) => match app.mode {
             UIMode::Viewer => handle_input_viewer(app, tui, keyevent),
             UIMode::SearchPanel(false) => handle_input_search_panel(app, tui, keyevent),
             UIMode::SearchPanel(true) => handle_input_file_save(app, tui, keyevent),
+            UIMode::Bookmarks => handle_input_bookmarks(app, tui, keyevent),
+            UIMode::Report | UIMode::MatchStatistics => handle_input_report(app, tui, keyevent),
+            UIMode::Prompt(_) => handle_input_prompt(app, tui, keyevent),
+            UIMode::Help => Update::CloseReport, // any key
         },
         Event::Resize(_, _) => Update::WindowResize(tui.size()),
         _ => Update::None,
     }
 }
 
+/// Resolve the key press to an action bound in the contexts, reading further key presses
+/// while they form the start of a longer binding (e.g. `g g`)
+fn lookup_action(
+    app: &App,
+    tui: &Tui,
+    contexts: &[KeyContext],
+    keyevent: KeyEvent,
+) -> Option<Action> {
+    let mut keys = vec![KeyBinding::from(keyevent)];
+    loop {
+        match app.keymap.lookup(contexts, &keys) {
+            KeyLookup::Action(action) => return Some(action),
+            KeyLookup::None => return None,
+            KeyLookup::Prefix => {
+                let mut next: Event = tui.events.next().unwrap();
+                while let Event::Tick = next {
+                    next = tui.events.next().unwrap();
+                }
+                match next {
+                    Event::Key(keyevent) => keys.push(KeyBinding::from(keyevent)),
+                    _ => return None,
+                }
+            }
+        }
+    }
+}
+
+/// Updates of actions available everywhere, `None` for the rest
+fn global_update(action: Action) -> Option<Update> {
+    match action {
+        Action::Quit => Some(Update::Quit),
+        Action::ToggleSearchPanel => Some(Update::ToggleUIMode),
+        _ => None,
+    }
+}
+
 fn handle_input_file_save(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
-    if keyevent.code == KeyCode::Esc {
+    if let Some(update) =
+        lookup_action(app, tui, &[KeyContext::Global], keyevent).and_then(global_update)
+    {
+        update
+    } else if keyevent.code == KeyCode::Esc {
         Update::ToggleFilePopup
     } else if keyevent.code == KeyCode::Enter {
         if let Some(msg) = app.save_patterns() {
@@ -66,136 +132,213 @@ fn handle_input_file_save(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
     }
 }
 
-pub fn handle_input_viewer(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
-    match keyevent {
-        KeyEvent {
-            code: KeyCode::Char('q'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => Update::Quit,
-        KeyEvent {
-            code: KeyCode::Char('j') | KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => Update::ScrollViewer(1),
-        KeyEvent {
-            code: KeyCode::Char('k') | KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => Update::ScrollViewer(-1),
-        KeyEvent {
-            code: KeyCode::Char('d'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        } => Update::ScrollViewer((tui.size().height as f32 * 0.4).floor() as isize),
-        KeyEvent {
-            code: KeyCode::Char('u'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        } => Update::ScrollViewer(-(tui.size().height as f32 * 0.4).floor() as isize),
-
-        // gg scrolls to top
-        KeyEvent {
-            code: KeyCode::Char('g'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => {
-            let mut next: Event = tui.events.next().unwrap();
-            while let Event::Tick = next {
-                next = tui.events.next().unwrap();
-            }
-            if let Event::Key(KeyEvent {
-                code: KeyCode::Char('g'),
-                modifiers: KeyModifiers::NONE,
-                ..
-            }) = next
-            {
-                Update::ScrollViewer(isize::MIN + 1)// negating isize::MIN cause overflow
-            } else {
-                Update::None
-            }
-        },
-        _ => Update::None,
+/// Raw key presses edit the input, like the file save popup
+fn handle_input_prompt(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
+    if let Some(update) =
+        lookup_action(app, tui, &[KeyContext::Global], keyevent).and_then(global_update)
+    {
+        update
+    } else if keyevent.code == KeyCode::Esc {
+        Update::CloseReport
+    } else if keyevent.code == KeyCode::Enter {
+        Update::SubmitPrompt
+    } else {
+        Update::PromptInput(keyevent)
     }
 }
 
-pub fn handle_input_search_panel(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
-    if keyevent.code == KeyCode::Esc {
-        Update::ToggleUIMode
-
-    // arrow keys switch input focus regardless of current focus
-    } else if keyevent.modifiers == KeyModifiers::NONE
-        && [KeyCode::Right, KeyCode::Left].contains(&keyevent.code)
-    {
-        Update::SearchPanelFocusNext(keyevent.code == KeyCode::Left)
+pub fn handle_input_viewer(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
+    let scroll = app.scroll_config;
+    let viewer_height = tui.size().height.saturating_sub(2); // 2 borders
+    let step = scroll.step as isize;
+    let half_page = ScrollConfig::page_lines(scroll.half_page, viewer_height);
+    let page = ScrollConfig::page_lines(scroll.full_page, viewer_height);
+    match lookup_action(
+        app,
+        tui,
+        &[KeyContext::Global, KeyContext::Viewer],
+        keyevent,
+    ) {
+        Some(Action::ScrollDown) => Update::ScrollViewer(step),
+        Some(Action::ScrollUp) => Update::ScrollViewer(-step),
+        Some(Action::HalfPageDown) => Update::ScrollViewer(half_page),
+        Some(Action::HalfPageUp) => Update::ScrollViewer(-half_page),
+        Some(Action::PageDown) => Update::ScrollViewer(page),
+        Some(Action::PageUp) => Update::ScrollViewer(-page),
+        // negating isize::MIN cause overflow
+        Some(Action::ScrollToTop) => Update::ScrollViewer(isize::MIN + 1),
+        Some(Action::ScrollLeft) => Update::ScrollViewerHorizontal(-HORIZONTAL_SCROLL_STEP),
+        Some(Action::ScrollRight) => Update::ScrollViewerHorizontal(HORIZONTAL_SCROLL_STEP),
+        Some(Action::ToggleCoreUnderline) => Update::ToggleStyling(StylingToggle::CoreUnderline),
+        Some(Action::ToggleWrap) => Update::ToggleStyling(StylingToggle::Wrap),
+        Some(Action::ToggleInvertMatch) => Update::ToggleStyling(StylingToggle::InvertMatch),
+        Some(Action::ToggleQualityStyling) => Update::ToggleStyling(StylingToggle::Quality),
+        Some(Action::ToggleQualityMode) => Update::ToggleStyling(StylingToggle::QualityMode),
+        Some(Action::ToggleLengthGradient) => Update::ToggleStyling(StylingToggle::LengthGradient),
+        Some(Action::ToggleEditMarkers) => Update::ToggleStyling(StylingToggle::EditMarkers),
+        Some(Action::ToggleTrimPreview) => Update::ToggleStyling(StylingToggle::TrimPreview),
+        Some(Action::ToggleComplementTrack) => {
+            Update::ToggleStyling(StylingToggle::ComplementTrack)
+        }
+        Some(Action::ToggleMatchConfidence) => {
+            Update::ToggleStyling(StylingToggle::MatchConfidence)
+        }
+        Some(Action::ToggleMatchMarkers) => Update::ToggleStyling(StylingToggle::MatchMarkers),
+        Some(Action::ToggleLowercaseItalic) => {
+            Update::ToggleStyling(StylingToggle::LowercaseItalic)
+        }
+        Some(Action::ToggleBaseComposition) => {
+            Update::ToggleStyling(StylingToggle::BaseComposition)
+        }
+        Some(Action::ToggleBaseColors) => Update::ToggleStyling(StylingToggle::BaseColors),
+        Some(Action::ToggleQualityLine) => Update::ToggleStyling(StylingToggle::QualityLine),
+        Some(Action::ToggleBookmark) => Update::EditBookmarks(BookmarkEdit::Toggle),
+        Some(Action::ToggleBookmarksPopup) => Update::ToggleBookmarksPopup,
+        Some(Action::SaveState) => Update::SaveState,
+        Some(Action::SelectRangeStart) => Update::EditSelection(SelectionEdit::Start),
+        Some(Action::SelectRangeEnd) => Update::EditSelection(SelectionEdit::End),
+        Some(Action::ClearSelection) => Update::EditSelection(SelectionEdit::Clear),
+        Some(Action::ExportSelection) => Update::EditSelection(SelectionEdit::Export(false)),
+        Some(Action::ExportSelectionIds) => Update::EditSelection(SelectionEdit::Export(true)),
+        Some(Action::SummarizeSelection) => Update::EditSelection(SelectionEdit::Summarize),
+        Some(Action::ExplainMatches) => Update::ExplainMatches,
+        Some(Action::ShowMatchStatistics) => Update::ToggleMatchStatistics,
+        Some(Action::ShowBaseQuality) => Update::OpenPrompt(PromptKind::BasePosition),
+        Some(Action::FindRead) => Update::OpenPrompt(PromptKind::FindRead),
+        Some(Action::GoToRecord) => Update::OpenPrompt(PromptKind::GoToRecord),
+        Some(Action::ExportView) => Update::OpenPrompt(PromptKind::ExportView),
+        Some(Action::CopySequence) => Update::CopyRecord(false),
+        Some(Action::CopyRecord) => Update::CopyRecord(true),
+        Some(Action::NextMatch) => Update::JumpToMatch(true),
+        Some(Action::PreviousMatch) => Update::JumpToMatch(false),
+        Some(Action::ShowHelp) => Update::ShowHelp,
+        Some(action) => global_update(action).unwrap_or(Update::None),
+        None => Update::None,
+    }
+}
 
-    // Tab and S-Tab switch input focus regardless of current focus
-    } else if [KeyModifiers::NONE, KeyModifiers::SHIFT].contains(&keyevent.modifiers)
-        && [KeyCode::Tab, KeyCode::BackTab].contains(&keyevent.code)
-    {
-        Update::SearchPanelFocusNext(keyevent.modifiers == KeyModifiers::SHIFT)
+pub fn handle_input_report(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
+    match lookup_action(
+        app,
+        tui,
+        &[KeyContext::Global, KeyContext::Report],
+        keyevent,
+    ) {
+        Some(Action::CloseReport) => Update::CloseReport,
+        Some(action) => global_update(action).unwrap_or(Update::None),
+        None => Update::None,
+    }
+}
 
-    } else if keyevent.modifiers == KeyModifiers::CONTROL && keyevent.code == KeyCode::Char('s') {
-        Update::ToggleFilePopup
+pub fn handle_input_bookmarks(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
+    match lookup_action(
+        app,
+        tui,
+        &[KeyContext::Global, KeyContext::Bookmarks],
+        keyevent,
+    ) {
+        Some(Action::ToggleBookmarksPopup) => Update::ToggleBookmarksPopup,
+        Some(Action::PreviousBookmark) => Update::CycleBookmarks(true),
+        Some(Action::NextBookmark) => Update::CycleBookmarks(false),
+        Some(Action::JumpToBookmark) => Update::EditBookmarks(BookmarkEdit::Jump),
+        Some(Action::DeleteBookmark) => Update::EditBookmarks(BookmarkEdit::Delete),
+        Some(action) => global_update(action).unwrap_or(Update::None),
+        None => Update::None,
+    }
+}
 
-    // patterns list specific keybindings
-    } else if app.search_panel.focused_element() == PanelElementName::PatternsList {
-        match keyevent {
-            KeyEvent {
-                code: KeyCode::Up | KeyCode::Down,
-                modifiers: KeyModifiers::NONE,
-                ..
-            } => Update::CycleSearchPattern(keyevent.code == KeyCode::Up),
-            KeyEvent {
-                code: KeyCode::Char('d') | KeyCode::Delete | KeyCode::Enter | KeyCode::Backspace,
-                modifiers: KeyModifiers::NONE,
-                ..
-            } => match app.search_panel.selected_pattern() {
+pub fn handle_input_search_panel(app: &App, tui: &Tui, keyevent: KeyEvent) -> Update {
+    let on_patterns_list = app.search_panel.focused_element() == PanelElementName::PatternsList;
+    let contexts = [
+        KeyContext::Global,
+        KeyContext::SearchPanel,
+        if on_patterns_list {
+            KeyContext::PatternsList
+        } else {
+            KeyContext::PatternInputs
+        },
+    ];
+    match lookup_action(app, tui, &contexts, keyevent) {
+        Some(Action::FocusNext) => Update::SearchPanelFocusNext(false),
+        Some(Action::FocusPrevious) => Update::SearchPanelFocusNext(true),
+        Some(Action::SavePatterns) => Update::ToggleFilePopup,
+        Some(Action::PreviousPattern) => Update::CycleSearchPattern(true),
+        Some(Action::NextPattern) => Update::CycleSearchPattern(false),
+        Some(action @ (Action::DeletePattern | Action::EditPattern)) => {
+            match app.search_panel.selected_pattern() {
                 Some(selection) => Update::EditSearchPattern(SearchPatternEdit::Delete(
                     selection,
-                    keyevent.code == KeyCode::Enter,
+                    action == Action::EditPattern,
                 )),
                 None => Update::Msg("No pattern selected".to_string()),
-            },
-            _ => Update::None,
-        }
-
-    // focus != PanelElementName::PatternsList
-    } else if keyevent.modifiers == KeyModifiers::NONE && keyevent.code == KeyCode::Enter {
-        let search_string = match app.search_panel.elements()[&PanelElementName::InputPattern] {
-            PanelElement::TextAreaElement(ref textarea) => textarea.lines().join(""),
-            _ => panic!("Wrong type of element"),
-        };
-        if search_string.is_empty() {
-            return Update::Msg("Search pattern cannot be empty".to_string());
+            }
         }
-        let try_color = Color::from_str(
-            match app.search_panel.elements()[&PanelElementName::InputColor] {
-                PanelElement::TextAreaElement(ref textarea) => textarea.lines().join(""),
-                _ => panic!("Wrong type of element"),
+        Some(Action::TogglePattern) => match app.search_panel.selected_pattern() {
+            Some(selection) => Update::EditSearchPattern(SearchPatternEdit::Toggle(selection)),
+            None => Update::Msg("No pattern selected".to_string()),
+        },
+        Some(action @ (Action::MovePatternUp | Action::MovePatternDown)) => {
+            match app.search_panel.selected_pattern() {
+                Some(selection) => Update::EditSearchPattern(SearchPatternEdit::Move(
+                    selection,
+                    action == Action::MovePatternUp,
+                )),
+                None => Update::Msg("No pattern selected".to_string()),
             }
-            .as_str(),
-        );
-        let try_u8 = u8::from_str(
-            match app.search_panel.elements()[&PanelElementName::InputDistance] {
-                PanelElement::TextAreaElement(ref textarea) => textarea.lines().join(""),
-                _ => panic!("Wrong type of element"),
+        }
+        Some(action @ (Action::WidenEditDistance | Action::NarrowEditDistance)) => {
+            match app.search_panel.selected_pattern() {
+                Some(selection) => Update::EditSearchPattern(SearchPatternEdit::AdjustDistance(
+                    selection,
+                    action == Action::WidenEditDistance,
+                )),
+                None => Update::Msg("No pattern selected".to_string()),
             }
-            .as_str(),
-        );
-        let comment = match app.search_panel.elements()[&PanelElementName::InputComment] {
-            PanelElement::TextAreaElement(ref textarea) => textarea.lines().join(""),
-            _ => panic!("Wrong type of element"),
-        };
-        match (try_color, try_u8) {
-                                       (Ok(color), Ok(distance)) => {Update::EditSearchPattern(SearchPatternEdit::Append(SearchPattern::new(search_string, color, distance, comment.as_str())))},
-                                       (Err(_), Ok(_)) => {Update::Msg("Color needs to be valid hex code".to_string())},
-                                       (Ok(_), Err(_)) => {Update::Msg("Edit distance needs to be valid positive integer".to_string())},
-                                       (Err(_), Err(_)) => {Update::Msg("Color needs to be valid hex code, edit distance needs to be valid positive integer".to_string())},
-                }
+        }
+        Some(Action::AddPattern) => add_pattern_from_inputs(app),
+        Some(action) => global_update(action).unwrap_or(Update::None),
+        // pass to input boxes
+        None if !on_patterns_list => Update::SearchPanelInput(keyevent),
+        None => Update::None,
+    }
+}
 
-    // pass to input boxes
-    } else {
-        Update::SearchPanelInput(keyevent)
+fn add_pattern_from_inputs(app: &App) -> Update {
+    let search_string = app.search_panel.input_text(&PanelElementName::InputPattern);
+    if search_string.is_empty() {
+        return Update::Msg("Search pattern cannot be empty".to_string());
+    }
+    let try_color = Color::from_str(&app.search_panel.input_text(&PanelElementName::InputColor));
+    let regex = app.search_panel.checked(&PanelElementName::InputRegex);
+    // the edit distance of regex patterns is not used
+    let try_u8 = u8::from_str(
+        &app.search_panel
+            .input_text(&PanelElementName::InputDistance),
+    )
+    .or_else(|e| if regex { Ok(0) } else { Err(e) });
+    let comment = app.search_panel.input_text(&PanelElementName::InputComment);
+    match (try_color, try_u8) {
+        (Ok(color), Ok(distance)) => {
+            let algorithm = if regex {
+                MatchAlgorithm::Regex
+            } else {
+                app.default_algorithm
+            };
+            let mut pattern = SearchPattern::new(search_string, color, distance, comment.as_str())
+                .with_algorithm(algorithm)
+                .with_rev_comp(app.search_panel.checked(&PanelElementName::InputRevComp) && !regex);
+            match pattern.validate(&app.alphabet) {
+                Ok(_) => Update::EditSearchPattern(SearchPatternEdit::Append(pattern)),
+                Err(msg) => Update::Msg(msg),
+            }
+        }
+        (Err(_), Ok(_)) => Update::Msg("Color needs to be valid hex code".to_string()),
+        (Ok(_), Err(_)) => {
+            Update::Msg("Edit distance needs to be valid positive integer".to_string())
+        }
+        (Err(_), Err(_)) => Update::Msg(
+            "Color needs to be valid hex code, edit distance needs to be valid positive integer"
+                .to_string(),
+        ),
     }
 }
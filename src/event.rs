@@ -1,4 +1,5 @@
 use std::{
+    cell::Cell,
     sync::mpsc,
     thread,
     time::{Duration, Instant},
@@ -28,6 +29,8 @@ pub struct EventHandler {
     sender: mpsc::Sender<Event>,
     /// Event receiver channel.
     receiver: mpsc::Receiver<Event>,
+    /// Event received by [`EventHandler::key_pending`] but not yet returned by `next`.
+    peeked: Cell<Option<Event>>,
     /// Event handler thread.
     #[allow(dead_code)]
     handler: thread::JoinHandle<()>,
@@ -73,6 +76,7 @@ impl EventHandler {
         Self {
             sender,
             receiver,
+            peeked: Cell::new(None),
             handler,
         }
     }
@@ -82,6 +86,17 @@ impl EventHandler {
     /// This function will always block the current thread if
     /// there is no data available and it's possible for more data to be sent.
     pub fn next(&self) -> Result<Event> {
-        Ok(self.receiver.recv()?)
+        match self.peeked.take() {
+            Some(event) => Ok(event),
+            None => Ok(self.receiver.recv()?),
+        }
+    }
+
+    /// Whether a key press is already waiting, without blocking or consuming it.
+    pub fn key_pending(&self) -> bool {
+        if self.peeked.get().is_none() {
+            self.peeked.set(self.receiver.try_recv().ok());
+        }
+        matches!(self.peeked.get(), Some(Event::Key(_)))
     }
 }
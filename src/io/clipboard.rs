@@ -0,0 +1,67 @@
+use base64::Engine;
+use std::cell::RefCell;
+use std::io::{IsTerminal, Write};
+
+/// Payloads above this many bytes are cut short or dropped by many terminals, e.g. hterm and
+/// older xterm, when sent through OSC 52
+const OSC52_LIMIT: usize = 100_000;
+
+/// Where `copy_to_clipboard` put the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Copied {
+    /// the system clipboard
+    Clipboard,
+    /// sent to the terminal with OSC 52, which may or may not put it on the clipboard
+    Terminal { truncated: bool },
+}
+
+thread_local! {
+    // kept for the whole session, on X11 the copied text is served by its owner
+    static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+}
+
+/// Copy text to the system clipboard, or without one (e.g. over SSH or without a display
+/// server) send it to the terminal with an OSC 52 escape sequence. Terminals without OSC 52
+/// support ignore it, so `Copied::Terminal` doesn't guarantee the text was copied
+pub fn copy_to_clipboard(text: &str) -> Result<Copied, String> {
+    let clipboard_error = match set_clipboard_text(text) {
+        Ok(()) => return Ok(Copied::Clipboard),
+        Err(e) => e,
+    };
+    // the viewer draws to stderr, keeping stdout for the saved patterns
+    let mut terminal = std::io::stderr();
+    if !terminal.is_terminal() {
+        return Err(format!(
+            "{}, and no terminal to copy through",
+            clipboard_error
+        ));
+    }
+    let payload = write_osc52(&mut terminal, text).map_err(|e| e.to_string())?;
+    Ok(Copied::Terminal {
+        truncated: payload > OSC52_LIMIT,
+    })
+}
+
+fn set_clipboard_text(text: &str) -> Result<(), arboard::Error> {
+    CLIPBOARD.with_borrow_mut(|clipboard| {
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+        clipboard.as_mut().unwrap().set_text(text)
+    })
+}
+
+/// Write the OSC 52 sequence setting the clipboard, returns the length of the encoded payload
+fn write_osc52<W: Write>(writer: &mut W, text: &str) -> std::io::Result<usize> {
+    let payload = base64::engine::general_purpose::STANDARD.encode(text);
+    write!(writer, "\x1b]52;c;{}\x07", payload)?;
+    writer.flush()?;
+    Ok(payload.len())
+}
+
+#[test]
+fn test_write_osc52() {
+    let mut written: Vec<u8> = Vec::new();
+    assert_eq!(write_osc52(&mut written, "ACGT").unwrap(), 8);
+    assert_eq!(written, b"\x1b]52;c;QUNHVA==\x07");
+}
@@ -1,11 +1,16 @@
 use bio::io::fastq;
+use flate2::read::MultiGzDecoder;
 use std::collections::VecDeque;
 use std::env::temp_dir;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek, Write};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use uuid::Uuid;
 
+#[cfg(test)]
+use crate::test_utils::TempFileGuard;
+
 // WIP: refactor
 // use a VecDeque to buffer records
 // store index offset of the vecdeque
@@ -14,13 +19,18 @@ use uuid::Uuid;
 ///
 /// Assumes the file pointer is at the start of a record
 /// Reads 4 lines from the BufReader and parses them into a fastq::Record
-/// Returns None if EOF is reached, Error if the lines are not valid fastq
+/// Returns None if EOF is reached, an InvalidData error if the lines are not a valid record:
+/// no '@' ID, no '+' separator or a quality line of another length than the sequence (an
+/// empty quality line is allowed, see `app::has_quality`)
+/// Lines are read as bytes, so stray non-UTF-8 bytes in sequences don't fail the record,
+/// non-ASCII bytes in the sequence and quality are kept one per base as '?'
 fn parse_record<R: Read>(
     buf_reader: &mut BufReader<R>,
 ) -> Result<Option<fastq::Record>, std::io::Error> {
-    let mut id = String::new();
-    let mut seq = String::new();
-    let mut qual = String::new();
+    let mut id = Vec::new();
+    let mut seq = Vec::new();
+    let mut separator = Vec::new();
+    let mut qual = Vec::new();
 
     #[allow(clippy::type_complexity)]
     let status: (
@@ -29,74 +39,104 @@ fn parse_record<R: Read>(
         Result<usize, std::io::Error>,
         Result<usize, std::io::Error>,
     ) = (
-        buf_reader.read_line(&mut id),
-        buf_reader.read_line(&mut seq),
-        buf_reader.read_line(&mut String::new()), // skip '+'
-        buf_reader.read_line(&mut qual),
+        buf_reader.read_until(b'\n', &mut id),
+        buf_reader.read_until(b'\n', &mut seq),
+        buf_reader.read_until(b'\n', &mut separator),
+        buf_reader.read_until(b'\n', &mut qual),
     );
     match status {
         (Ok(0), Ok(0), Ok(0), Ok(0)) => Ok(None), // EOF reached
         (Ok(_), Ok(_), Ok(_), Ok(_)) => {
-            // id starts with '@'
-            if id.starts_with('@') {
+            let (seq, qual) = (seq.trim_ascii_end(), qual.trim_ascii_end());
+            let invalid = |msg: String| Err(std::io::Error::new(ErrorKind::InvalidData, msg));
+            if !id.starts_with(b"@") {
+                invalid(format!(
+                    "ID field does not start with '@': {}",
+                    String::from_utf8_lossy(id.trim_ascii_end())
+                ))
+            } else if !separator.starts_with(b"+") {
+                invalid(format!(
+                    "no '+' line after the sequence of {}",
+                    String::from_utf8_lossy(id.trim_ascii_end())
+                ))
+            } else if !qual.is_empty() && qual.len() != seq.len() {
+                invalid(format!(
+                    "{} has {} bases but {} quality scores",
+                    String::from_utf8_lossy(id.trim_ascii_end()),
+                    seq.len(),
+                    qual.len()
+                ))
+            } else {
                 Ok(Some(fastq::Record::with_attrs(
-                    &id.trim_end()[1..],
+                    &String::from_utf8_lossy(id.trim_ascii_end())[1..],
                     None,
-                    seq.trim_end().as_bytes(),
-                    qual.trim_end().as_bytes(),
+                    &ascii_bytes(seq),
+                    &ascii_bytes(qual),
                 )))
-            } else {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("ID field does not start with '@': {}{}{}", id, seq, qual),
-                ))
             }
         }
-        _ => Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Error while parsing lines: {}\n{}\n{}\n", id, seq, qual),
-        )),
+        _ => Err(std::io::Error::other(format!(
+            "Error while parsing lines: {}\n{}\n{}\n",
+            String::from_utf8_lossy(&id),
+            String::from_utf8_lossy(&seq),
+            String::from_utf8_lossy(&qual)
+        ))),
     }
 }
 
-/// Try to parse a fastq record from a BufReader
+/// Replace non-ASCII bytes with '?', keeping one byte per base
+fn ascii_bytes(line: &[u8]) -> Vec<u8> {
+    line.iter()
+        .map(|&b| if b.is_ascii() { b } else { b'?' })
+        .collect()
+}
+
+/// Find the start of the first fastq record at or after the current position
 ///
-/// Try reading 7 lines from the BufReader and
-/// workout the start of a fastq record
-/// calls next if the start of a record is found
-fn try_next<R: Read + Seek>(
-    buf_reader: &mut BufReader<R>,
-) -> Result<fastq::Record, std::io::Error> {
-    let mut lines = Vec::with_capacity(8);
-    for i in 0..7 {
-        let mut line = String::new();
+/// Try reading 7 lines from the BufReader (fewer at the end of the file) and
+/// workout the start of a fastq record, i.e. an '@' line followed by a '+' line two lines
+/// later and a quality line as long as the sequence
+/// Seeks to the start of the record and returns its position, or if none is found, to the
+/// first line not tried as a record start (the end of the file if all were)
+fn sync_to_record<R: Read + Seek>(buf_reader: &mut BufReader<R>) -> Result<u64, std::io::Error> {
+    let mut lines: Vec<(Vec<u8>, u64)> = Vec::with_capacity(8);
+    for _ in 0..7 {
+        let mut line = Vec::new();
         let pos = buf_reader.stream_position()?;
-        match buf_reader.read_line(&mut line) {
-            Ok(0) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("try_parse_record EOF reached after {} lines", i),
-                ));
-            }
-            Ok(_) => lines.push((line, pos)),
-            Err(e) => return Err(e),
+        if buf_reader.read_until(b'\n', &mut line)? == 0 {
+            break;
         }
+        lines.push((line, pos));
     }
-    for i in 0..4 {
-        if lines[i].0.starts_with('@')
-            && lines[i + 2].0.starts_with('+')
-            && lines[i + 1].0.len() == lines[i + 3].0.len()
+    for i in 0..lines.len().saturating_sub(3) {
+        if lines[i].0.starts_with(b"@")
+            && lines[i + 2].0.starts_with(b"+")
+            && lines[i + 1].0.trim_ascii_end().len() == lines[i + 3].0.trim_ascii_end().len()
         {
             buf_reader.seek(std::io::SeekFrom::Start(lines[i].1))?;
-            return Ok(next(buf_reader).unwrap().unwrap());
+            return Ok(lines[i].1);
         }
     }
+    if lines.len() == 7 {
+        buf_reader.seek(std::io::SeekFrom::Start(lines[4].1))?;
+    }
     Err(std::io::Error::new(
         std::io::ErrorKind::InvalidData,
         "try_parse_record could not find a valid record",
     ))
 }
 
+/// Try to parse a fastq record from a BufReader
+///
+/// calls next once the start of a record is found by `sync_to_record`
+#[allow(dead_code)]
+fn try_next<R: Read + Seek>(
+    buf_reader: &mut BufReader<R>,
+) -> Result<fastq::Record, std::io::Error> {
+    sync_to_record(buf_reader)?;
+    Ok(next(buf_reader).unwrap().unwrap())
+}
+
 /// Parse a fastq record from a BufReader
 /// and return the record and the file position
 /// return None if EOF is reached
@@ -112,6 +152,7 @@ fn next<R: Read + Seek>(
 }
 
 /// Read records from a BufReader until the given position is reached
+#[allow(dead_code)]
 fn read_to_pos<R: Read + Seek>(
     buf_reader: &mut BufReader<R>,
     pos: u64,
@@ -153,22 +194,6 @@ fn read_to_pos<R: Read + Seek>(
     Ok(buff)
 }
 
-fn skip_n_records<R: Read>(buf_reader: &mut BufReader<R>, n: usize) -> Result<(), std::io::Error> {
-    for _ in 0..(n * 4) {
-        match buf_reader.read_line(&mut String::new()) {
-            Ok(0) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("skip_n_records EOF reached after {} lines", n),
-                ));
-            }
-            Ok(_) => (),
-            Err(e) => return Err(e),
-        }
-    }
-    Ok(())
-}
-
 #[cfg(not(debug_assertions))]
 static RECORD_BUF_SIZE: usize = 1024;
 #[cfg(not(debug_assertions))]
@@ -188,46 +213,412 @@ fn test_buf_size() {
 pub struct FastqReader<R: Read + Seek> {
     buf_reader: BufReader<R>,
     records_buffer: VecDeque<fastq::Record>,
-    offset: usize, // offset of the first record in the buffer
+    offset: usize,  // offset of the first record in the buffer
+    start_pos: u64, // byte position of record 0, non-zero when opened at a byte offset
     pub total_records: Option<usize>,
+    temp_file: Option<PathBuf>, // decompressed file to remove on drop
+    malformed: Vec<String>,     // skipped malformed records not yet reported, see take_malformed
+    record_offsets: Option<Vec<u64>>, // byte position of every record, see build_index
+}
+
+/// Sidecar file of the byte position of every record of a FASTQ file, see
+/// `FastqReader::build_index`
+pub fn index_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".seqsizzle-index");
+    PathBuf::from(name)
+}
+
+/// Start of an index file, followed by the `index_stamp` of the indexed file and the record
+/// positions
+const INDEX_MAGIC: &[u8; 8] = b"SQZINDX1";
+
+/// Size and modification time (seconds, nanoseconds) of a file as recorded in its index, a
+/// file replaced by another one, even an older one (e.g. `cp -p`), changes either
+fn index_stamp(path: &Path) -> Result<[u64; 3], std::io::Error> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok([
+        metadata.len(),
+        modified.as_secs(),
+        modified.subsec_nanos().into(),
+    ])
+}
+
+/// Record positions from the index of a file, None without an index or if the file changed
+/// since it was indexed
+fn read_index(path: &Path) -> Option<Vec<u64>> {
+    let index = index_path(path);
+    let bytes = std::fs::read(&index).ok()?;
+    let words = bytes.strip_prefix(INDEX_MAGIC)?;
+    if words.len() % 8 != 0 {
+        return None;
+    }
+    let mut words = words
+        .chunks_exact(8)
+        .map(|x| u64::from_le_bytes(x.try_into().unwrap()));
+    let stamp: Vec<u64> = words.by_ref().take(3).collect();
+    if stamp != index_stamp(path).ok()? {
+        let (index, path) = (index.display(), path.display());
+        eprintln!("Ignoring {}, {} changed since it was indexed", index, path);
+        return None;
+    }
+    let offsets: Vec<u64> = words.collect();
+    (!offsets.is_empty()).then_some(offsets)
+}
+
+/// Options for decompressing compressed input into a seekable file
+#[derive(Debug, Clone, Default)]
+pub struct DecompressOptions {
+    /// keep the decompressed file in this directory and reuse it in later runs
+    pub cache_dir: Option<PathBuf>,
+    /// directory of the temporary decompressed file (default: the system temp directory)
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl DecompressOptions {
+    fn temp_dir(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(temp_dir)
+    }
+}
+
+/// Check that `dir` exists and files can be created in it,
+/// so a bad directory fails before decompressing anything
+pub fn validate_temp_dir(dir: &Path) -> Result<(), std::io::Error> {
+    if !dir.is_dir() {
+        return Err(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("Temporary directory {} does not exist", dir.display()),
+        ));
+    }
+    let probe = dir.join(format!(".seqsizzle-{}", Uuid::new_v4()));
+    File::create(&probe).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "Temporary directory {} is not writable: {}",
+                dir.display(),
+                e
+            ),
+        )
+    })?;
+    std::fs::remove_file(probe)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// Compression of a file by its extension (`.gz`, `.zst`), or by its magic bytes otherwise
+fn compression(path: &Path) -> Option<Compression> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => return Some(Compression::Gzip),
+        Some("zst") => return Some(Compression::Zstd),
+        _ => {}
+    }
+    // unreadable files fail later with a clear message
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .ok()?;
+    magic_compression(&magic)
+}
+
+fn magic_compression(magic: &[u8]) -> Option<Compression> {
+    match magic {
+        [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+        _ => None,
+    }
+}
+
+/// Whether the file is gzip or zstd compressed, by its extension or magic bytes
+pub fn is_compressed(path: &Path) -> bool {
+    compression(path).is_some()
+}
+
+/// `-` as the input path reads standard input
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Error opening `path` with a message telling the common causes apart
+fn open_error(path: &Path, error: std::io::Error) -> std::io::Error {
+    let msg = match error.kind() {
+        ErrorKind::NotFound => format!("{} does not exist", path.display()),
+        ErrorKind::PermissionDenied => format!("Permission denied reading {}", path.display()),
+        ErrorKind::IsADirectory => format!("{} is a directory, expected a file", path.display()),
+        _ => format!("Error opening {}: {}", path.display(), error),
+    };
+    std::io::Error::new(error.kind(), msg)
+}
+
+/// Open a file for reading, failing with a clear message for directories (which can be
+/// opened but not read on Unix), missing and unreadable files
+pub fn open_file(path: &Path) -> Result<File, std::io::Error> {
+    if path.is_dir() {
+        return Err(open_error(path, ErrorKind::IsADirectory.into()));
+    }
+    File::open(path).map_err(|e| open_error(path, e))
+}
+
+/// Open a possibly gzip or zstd compressed file (or standard input for `-`) for sequential
+/// reading
+pub fn open_input(path: &Path) -> Result<Box<dyn Read>, std::io::Error> {
+    if is_stdin(path) {
+        let mut stdin = BufReader::new(std::io::stdin());
+        let compression = magic_compression(stdin.fill_buf()?);
+        return decoder(stdin, compression);
+    }
+    decoder(BufReader::new(open_file(path)?), compression(path))
+}
+
+fn decoder<R: BufRead + 'static>(
+    reader: R,
+    compression: Option<Compression>,
+) -> Result<Box<dyn Read>, std::io::Error> {
+    match compression {
+        Some(Compression::Gzip) => Ok(Box::new(MultiGzDecoder::new(reader))),
+        Some(Compression::Zstd) => Ok(Box::new(zstd::Decoder::with_buffer(reader)?)),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+/// Decompress a gzip or zstd compressed file (or copy standard input) to dest
+/// written to a partial file first so dest is never incomplete
+fn decompress(path: &Path, dest: &Path) -> Result<(), std::io::Error> {
+    let partial = dest.with_extension(format!("partial-{}", Uuid::new_v4()));
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    if let Err(e) = std::io::copy(&mut open_input(path)?, &mut writer).and_then(|_| writer.flush())
+    {
+        drop(writer);
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    drop(writer);
+    std::fs::rename(&partial, dest)
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike `DefaultHasher`,
+/// for names of files that outlive the process
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Path of the cached decompressed file, keyed by the source path, size and modification time
+fn cache_path(path: &Path, cache_dir: &Path) -> Result<PathBuf, std::io::Error> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut key = std::fs::canonicalize(path)?
+        .as_os_str()
+        .as_encoded_bytes()
+        .to_vec();
+    key.extend(metadata.len().to_le_bytes());
+    key.extend(modified.as_secs().to_le_bytes());
+    key.extend(modified.subsec_nanos().to_le_bytes());
+    let name = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(cache_dir.join(format!("{:016x}-{}", stable_hash(&key), name)))
 }
 
 // Constructor for File
 impl FastqReader<File> {
-    pub fn from_path(path: &Path) -> Self {
-        Self::new(match File::open(path) {
-            Ok(mut file) => {
-                assert!(
-                    file.stream_position().is_ok(),
-                    "File not seekable, are you using a pipe? Consider saving to an actual file"
-                );
-                file
+    /// Open a FASTQ file, decompressing it first if gzip or zstd compressed
+    /// fails for directories, missing or unreadable files and files without records.
+    /// Standard input (`-`) is spooled to a temporary file, removed on drop like a
+    /// decompressed file, so records can be read again when scrolling back
+    pub fn from_path(path: &Path, options: &DecompressOptions) -> Result<Self, std::io::Error> {
+        let reader = if is_stdin(path) {
+            eprintln!("Reading standard input");
+            Self::from_temp_file(path, options)?
+        } else if compression(path).is_none() {
+            let mut reader = Self::from_file(open_file(path)?)?;
+            if let Some(offsets) = read_index(path) {
+                reader.total_records = Some(offsets.len());
+                reader.record_offsets = Some(offsets);
             }
-            Err(e) => panic!("Error opening file '{}': {:?}", path.to_string_lossy(), e),
-        })
+            reader
+        } else {
+            // fail with the path of the input rather than of the decompressed file
+            open_file(path)?;
+            match &options.cache_dir {
+                Some(cache_dir) => {
+                    let cached = cache_path(path, cache_dir)?;
+                    if !cached.exists() {
+                        eprintln!("Decompressing {} to {}", path.display(), cached.display());
+                        std::fs::create_dir_all(cache_dir)
+                            .and_then(|_| decompress(path, &cached))
+                            .map_err(|e| open_error(&cached, e))?;
+                    }
+                    Self::from_file(open_file(&cached)?)?
+                }
+                None => {
+                    eprintln!("Decompressing {}", path.display());
+                    Self::from_temp_file(path, options)?
+                }
+            }
+        };
+        if reader.total_records == Some(0) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                if is_stdin(path) {
+                    String::from("Standard input contains no FASTQ records")
+                } else {
+                    format!("{} contains no FASTQ records", path.display())
+                },
+            ));
+        }
+        Ok(reader)
+    }
+
+    /// Decompress or spool the input to a temporary file removed on drop
+    fn from_temp_file(path: &Path, options: &DecompressOptions) -> Result<Self, std::io::Error> {
+        let mut temp_file = options.temp_dir();
+        temp_file.push(format!("{}.fastq", Uuid::new_v4()));
+        decompress(path, &temp_file).map_err(|e| open_error(&temp_file, e))?;
+        let mut ret = Self::from_file(open_file(&temp_file)?)?;
+        ret.temp_file = Some(temp_file);
+        Ok(ret)
+    }
+
+    /// Path the records can be read again from, the temporary file if decompressed or
+    /// spooled from standard input
+    pub fn input_path<'p>(&'p self, path: &'p Path) -> &'p Path {
+        self.temp_file.as_deref().unwrap_or(path)
+    }
+
+    fn from_file(mut file: File) -> Result<Self, std::io::Error> {
+        assert!(
+            file.stream_position().is_ok(),
+            "File not seekable, are you using a pipe? Consider saving to an actual file"
+        );
+        Self::new(file)
+    }
+}
+
+impl<R: Read + Seek> Drop for FastqReader<R> {
+    fn drop(&mut self) {
+        if let Some(temp_file) = &self.temp_file {
+            let _ = std::fs::remove_file(temp_file);
+        }
     }
 }
 
 // Generic methods
 impl<R: Read + Seek> FastqReader<R> {
-    pub fn new(mut reader: R) -> Self {
+    pub fn new(mut reader: R) -> Result<Self, std::io::Error> {
         assert!(
-            reader.stream_position().unwrap() == 0,
+            reader.stream_position()? == 0,
             "reader not at the start of the file"
         );
         let mut ret = Self {
             buf_reader: BufReader::with_capacity(READER_BUF_SIZE, reader),
             records_buffer: VecDeque::with_capacity(RECORD_BUF_SIZE + 1),
             offset: 0,
+            start_pos: 0,
             total_records: None,
+            temp_file: None,
+            malformed: Vec::new(),
+            record_offsets: None,
         };
-        ret.fill_buffer().unwrap();
-        ret
+        ret.fill_buffer()?;
+        Ok(ret)
+    }
+
+    /// The next record, skipping malformed records up to the next start of a valid record.
+    /// `index` is the index the record gets, to report the skipped records by
+    fn next_record(&mut self, index: usize) -> Result<Option<fastq::Record>, std::io::Error> {
+        Ok(self.next_record_at(index)?.map(|(_, record)| record))
+    }
+
+    /// `next_record` with the byte position of the record
+    fn next_record_at(
+        &mut self,
+        index: usize,
+    ) -> Result<Option<(u64, fastq::Record)>, std::io::Error> {
+        loop {
+            let pos = self.buf_reader.stream_position()?;
+            let error = match next(&mut self.buf_reader) {
+                Err(e) if e.kind() == ErrorKind::InvalidData => e,
+                result => return Ok(result?.map(|record| (pos, record))),
+            };
+            self.malformed.push(format!(
+                "Skipped malformed record {} (byte {}): {}",
+                index + 1,
+                pos,
+                error
+            ));
+            // the next record may start within the lines read as the malformed one
+            self.buf_reader.seek(std::io::SeekFrom::Start(pos + 1))?;
+            while sync_to_record(&mut self.buf_reader).is_err() {
+                if self.buf_reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Read the whole file once, from record 0, to record the byte position of every record
+    /// in the index file of `path` (the file read), loaded by `from_path` as long as the file
+    /// is unchanged so that any record is a seek away. Returns the number of records
+    pub fn build_index(&mut self, path: &Path) -> Result<usize, std::io::Error> {
+        self.buf_reader
+            .seek(std::io::SeekFrom::Start(self.start_pos))?;
+        let mut offsets: Vec<u64> = Vec::new();
+        while let Some((pos, _)) = self.next_record_at(offsets.len())? {
+            offsets.push(pos);
+        }
+        let mut writer = BufWriter::new(File::create(index_path(path))?);
+        writer.write_all(INDEX_MAGIC)?;
+        for word in index_stamp(path)?.iter().chain(&offsets) {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.flush()?;
+        // back to the first buffer
+        self.records_buffer.clear();
+        self.buf_reader
+            .seek(std::io::SeekFrom::Start(self.start_pos))?;
+        self.offset = 0;
+        self.fill_buffer()?;
+        self.malformed.clear(); // reported while indexing
+        self.total_records = Some(offsets.len());
+        self.record_offsets = Some(offsets);
+        Ok(self.total_records.unwrap_or_default())
+    }
+
+    /// Iterate over all records from record 0, skipping and numbering malformed records as
+    /// `get_index` does, so the n-th record is the one the viewer shows at index n
+    pub fn into_records(mut self) -> Result<Records<R>, std::io::Error> {
+        self.buf_reader
+            .seek(std::io::SeekFrom::Start(self.start_pos))?;
+        self.records_buffer.clear();
+        self.offset = 0;
+        Ok(Records {
+            reader: self,
+            index: 0,
+        })
+    }
+
+    /// Messages about the malformed records skipped since the last call
+    pub fn take_malformed(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.malformed)
     }
 
     pub fn fill_buffer(&mut self) -> Result<(), std::io::Error> {
         for _ in 0..RECORD_BUF_SIZE {
-            match next(&mut self.buf_reader)? {
+            match self.next_record(self.offset + self.records_buffer.len())? {
                 Some(res) => {
                     self.records_buffer.push_back(res);
                 }
@@ -251,30 +642,71 @@ impl<R: Read + Seek> FastqReader<R> {
     pub fn rewind(&mut self) -> Result<(), std::io::Error> {
         if self.offset != 0 {
             self.records_buffer.clear();
-            self.buf_reader.rewind()?;
+            self.buf_reader
+                .seek(std::io::SeekFrom::Start(self.start_pos))?;
             self.offset = 0;
             self.fill_buffer()?;
+            self.malformed.clear(); // reported when first read
             Ok(())
         } else {
             Ok(())
         }
     }
 
+    /// Make the first record at or after the byte `offset` record 0, e.g. to get into a huge
+    /// file without reading it from the start. Records before it are no longer reachable and
+    /// the number of records is unknown until the end is reached
+    /// Returns the position of the new record 0
+    pub fn start_at_offset(&mut self, offset: u64) -> Result<u64, std::io::Error> {
+        let pos = self.buf_reader.stream_position()?;
+        self.buf_reader.seek(std::io::SeekFrom::Start(offset))?;
+        let Ok(start_pos) = sync_to_record(&mut self.buf_reader) else {
+            // leave the reader as it was
+            self.buf_reader.seek(std::io::SeekFrom::Start(pos))?;
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("No FASTQ record found at or after byte {}", offset),
+            ));
+        };
+        self.records_buffer.clear();
+        self.start_pos = start_pos;
+        self.offset = 0;
+        self.total_records = None;
+        self.record_offsets = None; // indexes from the start of the file
+        self.fill_buffer()?;
+        Ok(start_pos)
+    }
+
     /// returns the record at the given index
     /// if the index is after the current buffer, forward the buffer to RECORD_BUF_SIZE/4
     /// records after the index
     /// if the index is before the current buffer, rewind the buffer to RECORD_BUF_SIZE/4
     /// records before the index
+    /// With an index (see `build_index`), records before or well after the buffer are seeked
+    /// to directly
     pub fn get_index(&mut self, index: usize) -> Result<Option<fastq::Record>, std::io::Error> {
         if self.total_records.is_some() && index > self.total_records.unwrap() {
             return Ok(None);
         }
+        let buffer_end = self.offset + self.records_buffer.len();
+        if let Some(offsets) = &self.record_offsets {
+            if index < self.offset || index >= buffer_end + RECORD_BUF_SIZE {
+                let Some(&pos) = offsets.get(index.saturating_sub(RECORD_BUF_SIZE / 4)) else {
+                    return Ok(None);
+                };
+                self.records_buffer.clear();
+                self.buf_reader.seek(std::io::SeekFrom::Start(pos))?;
+                self.offset = index.saturating_sub(RECORD_BUF_SIZE / 4);
+                self.fill_buffer()?;
+                return Ok(self.records_buffer.get(index - self.offset).cloned());
+            }
+        }
         if index >= self.offset && index < self.offset + self.records_buffer.len() {
             Ok(Some(self.records_buffer[index - self.offset].clone()))
         } else if index >= self.offset + self.records_buffer.len() {
             // forward the buffer
             for _ in 0..(index - self.offset - self.records_buffer.len() + RECORD_BUF_SIZE / 4) {
-                match next(&mut self.buf_reader)? {
+                match self.next_record(self.offset + self.records_buffer.len())? {
                     Some(res) => {
                         self.records_buffer.push_back(res);
                         if self.records_buffer.len() > RECORD_BUF_SIZE {
@@ -291,33 +723,63 @@ impl<R: Read + Seek> FastqReader<R> {
                 }
             }
 
-            return Ok(Some(self.records_buffer[index - self.offset].clone()));
+            Ok(Some(self.records_buffer[index - self.offset].clone()))
         } else if index < self.offset {
             // rewind the buffer
             if index < RECORD_BUF_SIZE {
                 self.rewind()?;
-                return Ok(Some(self.records_buffer[index].clone()));
+                Ok(Some(self.records_buffer[index].clone()))
             } else {
                 self.records_buffer.clear();
-                self.buf_reader.rewind()?;
+                self.buf_reader
+                    .seek(std::io::SeekFrom::Start(self.start_pos))?;
                 // TODO: seek backwards instead of rewinding
-                skip_n_records(&mut self.buf_reader, index - RECORD_BUF_SIZE / 4)?;
+                // parsed rather than skipped by lines, so malformed records are left out as
+                // when reading forward
+                for skipped in 0..index - RECORD_BUF_SIZE / 4 {
+                    if self.next_record(skipped)?.is_none() {
+                        return Err(std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("EOF reached after {} records while seeking back", skipped),
+                        ));
+                    }
+                }
                 self.offset = index - RECORD_BUF_SIZE / 4;
                 self.fill_buffer()?;
-                return Ok(Some(self.records_buffer[RECORD_BUF_SIZE / 4].clone()));
+                self.malformed.clear(); // reported when first read
+                Ok(Some(self.records_buffer[RECORD_BUF_SIZE / 4].clone()))
             }
         } else {
-            panic!("unexpected case in get_index");
+            Err(std::io::Error::other(format!(
+                "record {} not found around the buffer of records {} to {}",
+                index + 1,
+                self.offset + 1,
+                self.offset + self.records_buffer.len()
+            )))
         }
     }
 }
 
-#[allow(dead_code)]
-fn setup_test() -> (PathBuf, FastqReader<File>, Vec<fastq::Record>) {
-    let mut file_name = temp_dir();
-    file_name.push(format!("{}.fastq", Uuid::new_v4()));
-    let mut file = File::create(file_name.clone()).unwrap();
-    file.write_all(
+/// Sequential pass over the records of a `FastqReader`, see `FastqReader::into_records`
+pub struct Records<R: Read + Seek> {
+    reader: FastqReader<R>,
+    index: usize,
+}
+
+impl<R: Read + Seek> Iterator for Records<R> {
+    type Item = Result<fastq::Record, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.reader.next_record(self.index).transpose()?;
+        self.index += 1;
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+fn setup_test() -> (TempFileGuard, FastqReader<File>, Vec<fastq::Record>) {
+    let file_name = TempFileGuard::with_content(
+        "fastq",
         b"@id1\nAAAA\n+\nIIII\n\
                      @id2\nTTTT\n+\nIIII\n\
                      @id3\nCCCC\n+\nIIII\n\
@@ -328,11 +790,9 @@ fn setup_test() -> (PathBuf, FastqReader<File>, Vec<fastq::Record>) {
                      @id8\nCCCA\n+\nIIII\n\
                      @id9\nTCCC\n+\nIIII\n\
                      @id10\nCTCC\n+\nIIII\n",
-    )
-    .unwrap();
-    file.sync_all().unwrap();
-    let reader = FastqReader::new(File::open(file_name.clone()).unwrap());
-    let records: Vec<fastq::Record> = fastq::Reader::new(File::open(file_name.clone()).unwrap())
+    );
+    let reader = FastqReader::new(File::open(&file_name).unwrap()).unwrap();
+    let records: Vec<fastq::Record> = fastq::Reader::new(File::open(&file_name).unwrap())
         .records()
         .map(|r| r.unwrap())
         .collect();
@@ -340,25 +800,243 @@ fn setup_test() -> (PathBuf, FastqReader<File>, Vec<fastq::Record>) {
     (file_name, reader, records)
 }
 
-#[allow(dead_code)]
-fn cleanup_test(file_name: PathBuf) {
-    match std::fs::remove_file(file_name.clone()) {
-        Ok(_) => (),
-        Err(e) => panic!(
-            "Error removing test file {}: {:?}",
-            file_name.to_string_lossy(),
-            e
-        ),
-    }
-}
-
 #[test]
 fn test_get_index() {
-    let (file_name, mut reader, records) = setup_test();
+    let (_file_name, mut reader, records) = setup_test();
     assert_eq!(reader.get_index(0).unwrap().unwrap(), records[0]);
     assert_eq!(reader.get_index(9).unwrap().unwrap(), records[9]);
     assert_eq!(reader.get_index(4).unwrap().unwrap(), records[4]);
     assert_eq!(reader.get_index(8).unwrap().unwrap(), records[8]);
     assert_eq!(reader.get_index(5).unwrap().unwrap(), records[5]);
-    cleanup_test(file_name);
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let file_name = TempFileGuard::with_content(
+        "fastq",
+        b"@id1 desc\r\nACGT\r\n+\r\nIIII\r\n\
+          @id2\r\nTTTTT\r\n+\r\n#####\r\n\
+          @id3\r\nCC\r\n+\r\nII\r\n",
+    );
+    let mut reader = FastqReader::new(File::open(&file_name).unwrap()).unwrap();
+    let record = reader.get_index(1).unwrap().unwrap();
+    assert_eq!(
+        (record.id(), record.seq(), record.qual()),
+        ("id2", &b"TTTTT"[..], &b"#####"[..])
+    );
+    assert_eq!(reader.get_index(0).unwrap().unwrap().id(), "id1 desc");
+    assert_eq!(reader.get_index(3).unwrap(), None);
+    assert_eq!(reader.total_records, Some(3));
+    // syncing compares the sequence and quality lengths without the \r
+    assert_eq!(reader.start_at_offset(20).unwrap(), 26);
+    assert_eq!(reader.get_index(0).unwrap().unwrap().qual(), b"#####");
+}
+
+#[test]
+fn test_malformed_records() {
+    let content: String = (0..12)
+        .map(|i| match i {
+            2 => String::from("@bad2\nACGT\n+\nIII\n"),
+            7 => String::from("@bad7\nACGT\n"),
+            _ => format!("@id{}\nACGT\n+\nIIII\n", i),
+        })
+        .collect();
+    let file_name = TempFileGuard::with_content("fastq", content);
+    let mut reader = FastqReader::new(File::open(&file_name).unwrap()).unwrap();
+    assert_eq!(reader.get_index(2).unwrap().unwrap().id(), "id3");
+    assert!(reader.take_malformed()[0].starts_with("Skipped malformed record 3"));
+    // a truncated record is skipped up to the next valid one
+    assert_eq!(reader.get_index(6).unwrap().unwrap().id(), "id8");
+    assert_eq!(reader.take_malformed().len(), 1);
+    assert_eq!(reader.get_index(10).unwrap(), None);
+    // reading back to a record skips the malformed ones alike, reported only once
+    assert_eq!(reader.get_index(5).unwrap().unwrap().id(), "id6");
+    assert!(reader.take_malformed().is_empty());
+    // a pass over the file numbers the records as get_index
+    let ids: Vec<String> = reader
+        .into_records()
+        .unwrap()
+        .map(|x| x.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids.len(), 10);
+    assert_eq!((ids[2].as_str(), ids[6].as_str()), ("id3", "id8"));
+}
+
+#[test]
+fn test_record_index() {
+    let (file_name, mut reader, records) = setup_test();
+    let _index = TempFileGuard(index_path(&file_name));
+    assert_eq!(reader.build_index(&file_name).unwrap(), 10);
+    assert_eq!(
+        std::fs::metadata(index_path(&file_name)).unwrap().len(),
+        8 + 24 + 80
+    );
+    let options = DecompressOptions::default();
+    let mut reader = FastqReader::from_path(&file_name, &options).unwrap();
+    assert_eq!(reader.total_records, Some(10));
+    // seeked to directly, back and forth
+    assert_eq!(reader.get_index(9).unwrap().unwrap(), records[9]);
+    assert_eq!(reader.get_index(5).unwrap().unwrap(), records[5]);
+    assert_eq!(reader.get_index(0).unwrap().unwrap(), records[0]);
+    assert_eq!(reader.get_index(10).unwrap(), None);
+    // ignored once the file changes, also to an older modification time as with `cp -p`
+    let indexed = |options: &DecompressOptions| {
+        let reader = FastqReader::from_path(&file_name, options).unwrap();
+        reader.record_offsets.is_some()
+    };
+    let modified = std::fs::metadata(&file_name).unwrap().modified().unwrap();
+    let file = File::options().append(true).open(&file_name).unwrap();
+    file.set_modified(modified - std::time::Duration::from_secs(60))
+        .unwrap();
+    assert!(!indexed(&options));
+    file.set_modified(modified).unwrap();
+    assert!(indexed(&options));
+    // or to another size with the same modification time, as with `rsync -t`
+    (&file).write_all(b"@id11\nA\n+\nI\n").unwrap();
+    file.set_modified(modified).unwrap();
+    assert!(!indexed(&options));
+}
+
+#[test]
+fn test_start_at_offset() {
+    let (_file_name, mut reader, records) = setup_test();
+    // mid-way through the sequence of id2, the next record is id3 at byte 34
+    assert_eq!(reader.start_at_offset(23).unwrap(), 34);
+    assert_eq!(reader.get_index(0).unwrap().unwrap(), records[2]);
+    assert_eq!(reader.get_index(7).unwrap().unwrap(), records[9]);
+    assert_eq!(reader.get_index(8).unwrap(), None);
+    assert_eq!(reader.total_records, Some(8));
+    // back to the record at the offset, not the start of the file
+    assert_eq!(reader.get_index(1).unwrap().unwrap(), records[3]);
+    reader.rewind().unwrap();
+    assert_eq!(reader.get_index(0).unwrap().unwrap(), records[2]);
+    // at a record start, and past the last record
+    assert_eq!(reader.start_at_offset(34).unwrap(), 34);
+    assert!(reader.start_at_offset(1000).is_err());
+}
+
+#[test]
+fn test_gzipped_input() {
+    use flate2::write::GzEncoder;
+    let (file_name, _, records) = setup_test();
+    let gz_name = TempFileGuard(file_name.with_extension("fastq.gz"));
+    let mut encoder = GzEncoder::new(File::create(&gz_name).unwrap(), Default::default());
+    std::io::copy(&mut File::open(&file_name).unwrap(), &mut encoder).unwrap();
+    encoder.finish().unwrap();
+
+    // temporary decompressed file is removed on drop
+    let mut reader = FastqReader::from_path(&gz_name, &DecompressOptions::default()).unwrap();
+    assert_eq!(reader.get_index(9).unwrap().unwrap(), records[9]);
+    let temp_file = reader.temp_file.clone().unwrap();
+    assert!(temp_file.exists());
+    drop(reader);
+    assert!(!temp_file.exists());
+
+    // cached decompressed file is kept and reused
+    let cache_dir = TempFileGuard::new("");
+    let options = DecompressOptions {
+        cache_dir: Some(cache_dir.to_path_buf()),
+        ..Default::default()
+    };
+    let mut reader = FastqReader::from_path(&gz_name, &options).unwrap();
+    assert_eq!(reader.get_index(5).unwrap().unwrap(), records[5]);
+    drop(reader);
+    let cached = cache_path(&gz_name, &cache_dir).unwrap();
+    assert!(cached.exists());
+    let modified = std::fs::metadata(&cached).unwrap().modified().unwrap();
+    FastqReader::from_path(&gz_name, &options).unwrap();
+    assert_eq!(
+        std::fs::metadata(&cached).unwrap().modified().unwrap(),
+        modified
+    );
+    // cache names must not change with the Rust release
+    assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+
+    // temporary decompressed file in a chosen directory
+    let scratch = TempFileGuard::new("");
+    assert!(validate_temp_dir(&scratch).is_err());
+    std::fs::create_dir(&scratch).unwrap();
+    validate_temp_dir(&scratch).unwrap();
+    let options = DecompressOptions {
+        temp_dir: Some(scratch.to_path_buf()),
+        ..Default::default()
+    };
+    let reader = FastqReader::from_path(&gz_name, &options).unwrap();
+    assert!(reader.temp_file.as_ref().unwrap().starts_with(&scratch));
+    drop(reader);
+    // with the decompressed file removed
+    assert_eq!(std::fs::read_dir(&scratch).unwrap().count(), 0);
+}
+
+#[test]
+fn test_multi_member_gzip() {
+    use flate2::write::GzEncoder;
+    // bgzip and `cat a.gz b.gz` write several gzip members, all of which are decompressed
+    let (file_name, _, records) = setup_test();
+    let gz_name = TempFileGuard(file_name.with_extension("fastq.gz"));
+    let content = std::fs::read(&file_name).unwrap();
+    let mut gz = File::create(&gz_name).unwrap();
+    for _ in 0..2 {
+        let mut encoder = GzEncoder::new(&mut gz, Default::default());
+        encoder.write_all(&content).unwrap();
+        encoder.finish().unwrap();
+    }
+    let mut reader = FastqReader::from_path(&gz_name, &DecompressOptions::default()).unwrap();
+    assert_eq!(reader.get_index(19).unwrap().unwrap(), records[9]);
+    assert_eq!(reader.get_index(20).unwrap(), None);
+    assert_eq!(reader.total_records, Some(20));
+    drop(reader);
+}
+
+#[test]
+fn test_zstd_input() {
+    let (file_name, _, records) = setup_test();
+    let zst_name = TempFileGuard(file_name.with_extension("fastq.zst"));
+    let content = std::fs::read(&file_name).unwrap();
+    std::fs::write(&zst_name, zstd::encode_all(&content[..], 0).unwrap()).unwrap();
+    let mut reader = FastqReader::from_path(&zst_name, &DecompressOptions::default()).unwrap();
+    assert_eq!(reader.get_index(9).unwrap().unwrap(), records[9]);
+    let temp_file = reader.temp_file.clone().unwrap();
+    drop(reader);
+    assert!(!temp_file.exists());
+    // detected by the magic bytes without the extension
+    let unnamed = TempFileGuard(file_name.with_extension(""));
+    std::fs::rename(&zst_name, &unnamed).unwrap();
+    assert_eq!(compression(&unnamed), Some(Compression::Zstd));
+    assert_eq!(compression(&file_name), None);
+    let mut reader = FastqReader::from_path(&unnamed, &DecompressOptions::default()).unwrap();
+    assert_eq!(reader.get_index(0).unwrap().unwrap(), records[0]);
+    drop(reader);
+}
+
+#[test]
+fn test_open_errors() {
+    let dir = TempFileGuard::dir();
+    let message = |path: &Path| {
+        FastqReader::from_path(path, &DecompressOptions::default())
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        message(&dir),
+        format!("{} is a directory, expected a file", dir.display())
+    );
+    let missing = dir.join("missing.fastq");
+    assert_eq!(
+        message(&missing),
+        format!("{} does not exist", missing.display())
+    );
+    let empty = dir.join("empty.fastq");
+    File::create(&empty).unwrap();
+    assert_eq!(
+        message(&empty),
+        format!("{} contains no FASTQ records", empty.display())
+    );
+    // permissions are not enforced for root, so the error is mapped directly
+    let denied = open_error(&empty, ErrorKind::PermissionDenied.into());
+    assert_eq!(denied.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(
+        denied.to_string(),
+        format!("Permission denied reading {}", empty.display())
+    );
 }
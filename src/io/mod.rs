@@ -1 +1,4 @@
+pub mod clipboard;
 pub mod fastq;
+pub mod output;
+pub mod view_export;
@@ -0,0 +1,159 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Writer for output files, gzip-compressed if the path ends with `.gz`
+pub enum OutputWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputWriter {
+    /// Wrap an opened file according to the extension of its path
+    pub fn new(file: File, path: &Path) -> Self {
+        let writer = BufWriter::new(file);
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            OutputWriter::Gzip(GzEncoder::new(writer, Compression::default()))
+        } else {
+            OutputWriter::Plain(writer)
+        }
+    }
+
+    /// Create (or truncate) the file at path
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self::new(File::create(path)?, path))
+    }
+
+    /// Flush and write the gzip trailer if compressed
+    pub fn finish(self) -> std::io::Result<()> {
+        match self {
+            OutputWriter::Plain(mut writer) => writer.flush(),
+            OutputWriter::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+/// Write the file at path through a temporary file next to it, renamed over it once complete,
+/// so that a crash or interrupt while writing leaves the previous file rather than a truncated
+/// one
+pub fn write_replacing(
+    path: &Path,
+    write: impl FnOnce(&mut OutputWriter) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let partial = path.with_file_name(format!(".{}.{}.partial", name, uuid::Uuid::new_v4()));
+    let result = File::create(&partial)
+        .and_then(|file| {
+            // compressed according to the final path
+            let mut writer = OutputWriter::new(file, path);
+            write(&mut writer)?;
+            writer.finish()
+        })
+        .and_then(|()| std::fs::rename(&partial, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputWriter::Plain(writer) => writer.write(buf),
+            OutputWriter::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputWriter::Plain(writer) => writer.flush(),
+            OutputWriter::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// FASTA writer wrapping sequences at `width` columns, 0 writes each sequence on one line
+pub struct FastaWriter<W: Write> {
+    writer: W,
+    width: usize,
+}
+
+impl<W: Write> FastaWriter<W> {
+    pub fn new(writer: W, width: usize) -> Self {
+        Self { writer, width }
+    }
+
+    pub fn write(&mut self, id: &str, desc: Option<&str>, seq: &[u8]) -> std::io::Result<()> {
+        match desc {
+            Some(desc) => writeln!(self.writer, ">{} {}", id, desc)?,
+            None => writeln!(self.writer, ">{}", id)?,
+        }
+        if self.width == 0 || seq.is_empty() {
+            self.writer.write_all(seq)?;
+            return self.writer.write_all(b"\n");
+        }
+        for line in seq.chunks(self.width) {
+            self.writer.write_all(line)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[test]
+fn test_gzip_output() {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    let path = crate::test_utils::TempFileGuard::new("csv.gz");
+    let mut writer = OutputWriter::create(&path).unwrap();
+    writer
+        .write_all(b"pattern,color,editdistance,comment\n")
+        .unwrap();
+    writer.finish().unwrap();
+    let mut decompressed = String::new();
+    GzDecoder::new(File::open(&path).unwrap())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, "pattern,color,editdistance,comment\n");
+}
+
+#[test]
+fn test_write_replacing() {
+    let dir = crate::test_utils::TempFileGuard::dir();
+    let path = dir.join("patterns.csv");
+    std::fs::write(&path, "previous\n").unwrap();
+    // a failed write leaves the previous file and no partial file behind
+    let failed = write_replacing(&path, |writer| {
+        writer.write_all(b"partial")?;
+        Err(std::io::Error::other("interrupted"))
+    });
+    assert!(failed.is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous\n");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    write_replacing(&path, |writer| writer.write_all(b"new\n")).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\n");
+}
+
+#[test]
+fn test_fasta_writer() {
+    let mut out: Vec<u8> = Vec::new();
+    let mut writer = FastaWriter::new(&mut out, 4);
+    writer.write("read", Some("desc"), b"ACGTACGTAC").unwrap();
+    writer.write("exact", None, b"ACGT").unwrap();
+    writer.write("empty", None, b"").unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        ">read desc\nACGT\nACGT\nAC\n>exact\nACGT\n>empty\n\n"
+    );
+    let mut out: Vec<u8> = Vec::new();
+    FastaWriter::new(&mut out, 0)
+        .write("read", None, b"ACGTACGTAC")
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), ">read\nACGTACGTAC\n");
+}
@@ -0,0 +1,216 @@
+use crate::read_stylizing::color_depth::to_rgb;
+use ratatui::prelude::{Color, Line, Modifier, Style};
+use std::io::Write;
+use std::path::Path;
+
+/// Format of an exported view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewFormat {
+    Ansi, // escape codes, e.g. for `less -R` or `cat` in a terminal
+    Html, // a `<pre>` block of styled spans
+}
+
+impl ViewFormat {
+    /// HTML for `.html` / `.htm` files (also gzip-compressed), ANSI otherwise
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.to_string_lossy().to_lowercase();
+        let name = name.strip_suffix(".gz").unwrap_or(&name);
+        if name.ends_with(".html") || name.ends_with(".htm") {
+            ViewFormat::Html
+        } else {
+            ViewFormat::Ansi
+        }
+    }
+}
+
+/// Write rendered lines with the colors and modifiers of their spans
+pub fn write_lines<W: Write>(
+    writer: &mut W,
+    lines: &[Line],
+    format: ViewFormat,
+) -> std::io::Result<()> {
+    if format == ViewFormat::Html {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html><head><meta charset=\"utf-8\"></head>")?;
+        writeln!(writer, "<body style=\"background-color: #000000\">")?;
+        let color = css_color(Color::Gray).unwrap_or_default();
+        write!(writer, "<pre style=\"color: {}\">", color)?;
+    }
+    for line in lines {
+        for span in &line.spans {
+            let style = line.style.patch(span.style);
+            match format {
+                ViewFormat::Ansi => match ansi_codes(style).as_str() {
+                    "" => write!(writer, "{}", span.content)?,
+                    codes => write!(writer, "\x1b[{}m{}\x1b[0m", codes, span.content)?,
+                },
+                ViewFormat::Html => match css(style).as_str() {
+                    "" => write!(writer, "{}", escape_html(&span.content))?,
+                    css => write!(
+                        writer,
+                        "<span style=\"{}\">{}</span>",
+                        css,
+                        escape_html(&span.content)
+                    )?,
+                },
+            }
+        }
+        writeln!(writer)?;
+    }
+    if format == ViewFormat::Html {
+        writeln!(writer, "</pre></body></html>")?;
+    }
+    Ok(())
+}
+
+/// SGR parameters of a style, e.g. `1;31`, empty for the terminal's default style
+fn ansi_codes(style: Style) -> String {
+    const MODIFIERS: [(Modifier, &str); 6] = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    let mut codes: Vec<String> = MODIFIERS
+        .iter()
+        .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+        .map(|(_, code)| code.to_string())
+        .collect();
+    codes.extend(style.fg.and_then(|color| ansi_color(color, false)));
+    codes.extend(style.bg.and_then(|color| ansi_color(color, true)));
+    codes.join(";")
+}
+
+/// SGR parameters of a foreground or background color, None for the default colors
+fn ansi_color(color: Color, background: bool) -> Option<String> {
+    let layer: u8 = if background { 4 } else { 3 };
+    // 30-37 and 90-97 for the foreground, 40-47 and 100-107 for the background
+    let basic = |i: u8| {
+        let base = if i < 8 {
+            layer * 10
+        } else {
+            (layer + 6) * 10 - 8
+        };
+        Some((base + i).to_string())
+    };
+    match color {
+        Color::Reset => None,
+        Color::Black => basic(0),
+        Color::Red => basic(1),
+        Color::Green => basic(2),
+        Color::Yellow => basic(3),
+        Color::Blue => basic(4),
+        Color::Magenta => basic(5),
+        Color::Cyan => basic(6),
+        Color::Gray => basic(7),
+        Color::DarkGray => basic(8),
+        Color::LightRed => basic(9),
+        Color::LightGreen => basic(10),
+        Color::LightYellow => basic(11),
+        Color::LightBlue => basic(12),
+        Color::LightMagenta => basic(13),
+        Color::LightCyan => basic(14),
+        Color::White => basic(15),
+        Color::Rgb(r, g, b) => Some(format!("{}8;2;{};{};{}", layer, r, g, b)),
+        Color::Indexed(i) => Some(format!("{}8;5;{}", layer, i)),
+    }
+}
+
+/// Inline CSS of a style, empty for the default style
+fn css(style: Style) -> String {
+    let (fg, bg) = if style.add_modifier.contains(Modifier::REVERSED) {
+        // the page colors of `write_lines` for default colors
+        (
+            style.bg.or(Some(Color::Black)),
+            style.fg.or(Some(Color::Gray)),
+        )
+    } else {
+        (style.fg, style.bg)
+    };
+    let mut properties: Vec<String> = Vec::new();
+    properties.extend(fg.and_then(css_color).map(|x| format!("color: {}", x)));
+    properties.extend(
+        bg.and_then(css_color)
+            .map(|x| format!("background-color: {}", x)),
+    );
+    if style.add_modifier.contains(Modifier::BOLD) {
+        properties.push(String::from("font-weight: bold"));
+    }
+    if style.add_modifier.contains(Modifier::DIM) {
+        properties.push(String::from("opacity: 0.6"));
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        properties.push(String::from("font-style: italic"));
+    }
+    let decorations: Vec<&str> = [
+        (Modifier::UNDERLINED, "underline"),
+        (Modifier::CROSSED_OUT, "line-through"),
+    ]
+    .iter()
+    .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+    .map(|(_, decoration)| *decoration)
+    .collect();
+    if !decorations.is_empty() {
+        properties.push(format!("text-decoration: {}", decorations.join(" ")));
+    }
+    properties.join("; ")
+}
+
+/// Hex RGB of a color as the xterm defaults show it, None for the default colors
+fn css_color(color: Color) -> Option<String> {
+    to_rgb(color).map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[test]
+fn test_write_lines() {
+    use ratatui::prelude::Span;
+    let lines = vec![
+        Line::from("read<1>"),
+        Line::from(vec![
+            Span::raw("AC"),
+            Span::styled(
+                "GT",
+                Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                "A",
+                Style::new()
+                    .fg(Color::Rgb(1, 2, 3))
+                    .add_modifier(Modifier::UNDERLINED),
+            ),
+        ]),
+    ];
+    let mut ansi: Vec<u8> = Vec::new();
+    write_lines(&mut ansi, &lines, ViewFormat::Ansi).unwrap();
+    assert_eq!(
+        String::from_utf8(ansi).unwrap(),
+        "read<1>\nAC\x1b[1;31mGT\x1b[0m\x1b[4;38;2;1;2;3mA\x1b[0m\n"
+    );
+    let mut html: Vec<u8> = Vec::new();
+    write_lines(&mut html, &lines, ViewFormat::Html).unwrap();
+    let html = String::from_utf8(html).unwrap();
+    assert!(html
+        .contains("read&lt;1&gt;\nAC<span style=\"color: #cd0000; font-weight: bold\">GT</span>"));
+    assert!(html
+        .contains("<span style=\"color: #010203; text-decoration: underline\">A</span>\n</pre>"));
+    assert_eq!(ansi_color(Color::LightBlue, true).as_deref(), Some("104"));
+    let reversed = Style::new().fg(Color::Red).add_modifier(Modifier::REVERSED);
+    assert_eq!(ansi_codes(reversed), "7;31");
+    assert_eq!(css(reversed), "color: #000000; background-color: #cd0000");
+    assert_eq!(
+        ViewFormat::from_path(Path::new("view.HTML.gz")),
+        ViewFormat::Html
+    );
+    assert_eq!(
+        ViewFormat::from_path(Path::new("view.txt")),
+        ViewFormat::Ansi
+    );
+}
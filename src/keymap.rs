@@ -0,0 +1,906 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a keybinding applies, contexts are looked up in order of precedence
+/// (see `control::handle_input`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Global,
+    Viewer,
+    SearchPanel,   // anywhere in the search panel
+    PatternsList,  // search panel, focusing on the patterns list
+    PatternInputs, // search panel, focusing on one of the input boxes
+    Bookmarks,     // bookmarks popup
+    Report,        // text popup, e.g. the summary of the selected records, or match statistics
+}
+
+impl KeyContext {
+    /// Name of the context as used in the config file, e.g. `[keymap.viewer]`
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyContext::Global => "global",
+            KeyContext::Viewer => "viewer",
+            KeyContext::SearchPanel => "search_panel",
+            KeyContext::PatternsList => "patterns_list",
+            KeyContext::PatternInputs => "pattern_inputs",
+            KeyContext::Bookmarks => "bookmarks",
+            KeyContext::Report => "report",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ToggleSearchPanel,
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    ScrollToTop,
+    ScrollLeft,
+    ScrollRight,
+    ToggleCoreUnderline,
+    ToggleWrap,
+    ToggleInvertMatch,
+    ToggleQualityStyling,
+    ToggleQualityMode,
+    ToggleLengthGradient,
+    ToggleEditMarkers,
+    ToggleTrimPreview,
+    ToggleComplementTrack,
+    ToggleMatchConfidence,
+    ToggleMatchMarkers,
+    ToggleLowercaseItalic,
+    ToggleBaseComposition,
+    ToggleBaseColors,
+    ToggleQualityLine,
+    ToggleBookmark,
+    ToggleBookmarksPopup,
+    SaveState,
+    SelectRangeStart,
+    SelectRangeEnd,
+    ClearSelection,
+    ExportSelection,
+    ExportSelectionIds,
+    ExportView,
+    CopySequence,
+    CopyRecord,
+    SummarizeSelection,
+    CloseReport,
+    ExplainMatches,
+    ShowMatchStatistics,
+    ShowBaseQuality,
+    FindRead,
+    GoToRecord,
+    NextMatch,
+    PreviousMatch,
+    ShowHelp,
+    PreviousBookmark,
+    NextBookmark,
+    JumpToBookmark,
+    DeleteBookmark,
+    FocusNext,
+    FocusPrevious,
+    SavePatterns,
+    PreviousPattern,
+    NextPattern,
+    DeletePattern,
+    EditPattern,
+    TogglePattern,
+    MovePatternUp,
+    MovePatternDown,
+    WidenEditDistance,
+    NarrowEditDistance,
+    AddPattern,
+}
+
+pub struct ActionInfo {
+    pub context: KeyContext,
+    pub action: Action,
+    pub name: &'static str, // used in the config file
+    pub default_keys: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// All bindable actions and their default keys
+pub const ACTIONS: &[ActionInfo] = &[
+    ActionInfo {
+        context: KeyContext::Global,
+        action: Action::Quit,
+        name: "quit",
+        default_keys: &["ctrl+c"],
+        description: "quit",
+    },
+    ActionInfo {
+        context: KeyContext::Global,
+        action: Action::ToggleSearchPanel,
+        name: "toggle_search_panel",
+        default_keys: &["/", "ctrl+f"],
+        description: "toggle the search panel",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::Quit,
+        name: "quit",
+        default_keys: &["q"],
+        description: "quit",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ScrollDown,
+        name: "scroll_down",
+        default_keys: &["j", "down"],
+        description: "scroll down one step (a line by default)",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ScrollUp,
+        name: "scroll_up",
+        default_keys: &["k", "up"],
+        description: "scroll up one step (a line by default)",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::HalfPageDown,
+        name: "half_page_down",
+        default_keys: &["ctrl+d"],
+        description: "scroll down half a screen",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::HalfPageUp,
+        name: "half_page_up",
+        default_keys: &["ctrl+u"],
+        description: "scroll up half a screen",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::PageDown,
+        name: "page_down",
+        default_keys: &["pagedown", "space"],
+        description: "scroll down a screen",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::PageUp,
+        name: "page_up",
+        default_keys: &["pageup", "ctrl+b"],
+        description: "scroll up a screen",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ScrollToTop,
+        name: "scroll_to_top",
+        default_keys: &["g g"],
+        description: "scroll to the first record",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ScrollLeft,
+        name: "scroll_left",
+        default_keys: &["h", "left"],
+        description: "scroll left when not wrapping",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ScrollRight,
+        name: "scroll_right",
+        default_keys: &["l", "right"],
+        description: "scroll right when not wrapping",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleCoreUnderline,
+        name: "toggle_core_underline",
+        default_keys: &["u"],
+        description: "toggle underlining the exact core of matches",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleWrap,
+        name: "toggle_wrap",
+        default_keys: &["w"],
+        description: "toggle line wrapping",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleInvertMatch,
+        name: "toggle_invert_match",
+        default_keys: &["v"],
+        description: "toggle coloring the unmatched regions instead of the matches",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleQualityStyling,
+        name: "toggle_quality_styling",
+        default_keys: &["Q"],
+        description: "toggle styling bases by quality",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleQualityMode,
+        name: "toggle_quality_mode",
+        default_keys: &["e"],
+        description: "switch between dimming low and emphasizing high quality bases",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleLengthGradient,
+        name: "toggle_length_gradient",
+        default_keys: &["L"],
+        description: "toggle coloring IDs by read length",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleEditMarkers,
+        name: "toggle_edit_markers",
+        default_keys: &["i"],
+        description:
+            "toggle marking substitutions (bold), extra (underlined) and missing (italic) \
+            bases in matches",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleTrimPreview,
+        name: "toggle_trim_preview",
+        default_keys: &["t"],
+        description: "toggle striking through adaptors at the read ends and poly-A tails",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleComplementTrack,
+        name: "toggle_complement_track",
+        default_keys: &["c"],
+        description: "toggle showing the complement of each base under the sequence",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleMatchConfidence,
+        name: "toggle_match_confidence",
+        default_keys: &["f"],
+        description: "toggle fading matches by edit distance",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleMatchMarkers,
+        name: "toggle_match_markers",
+        default_keys: &["M"],
+        description: "toggle a column marking the patterns matched in each read",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleLowercaseItalic,
+        name: "toggle_lowercase_italic",
+        default_keys: &["I"],
+        description: "toggle italicizing lowercase (low confidence) bases",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleBaseComposition,
+        name: "toggle_base_composition",
+        default_keys: &["G"],
+        description: "toggle the length and GC% (and N%) of each read after its ID",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleBaseColors,
+        name: "toggle_base_colors",
+        default_keys: &["b"],
+        description: "toggle coloring bases by identity, pattern colors on top",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleQualityLine,
+        name: "toggle_quality_line",
+        default_keys: &["r"],
+        description: "toggle showing the raw quality string under the sequence",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleBookmark,
+        name: "toggle_bookmark",
+        default_keys: &["m"],
+        description: "bookmark the record at the top, or remove its bookmark",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ToggleBookmarksPopup,
+        name: "show_bookmarks",
+        default_keys: &["'"],
+        description: "list the bookmarks",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::SaveState,
+        name: "save_state",
+        default_keys: &["S"],
+        description: "save the patterns, styling and position to seqsizzle-state.toml",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::SelectRangeStart,
+        name: "select_range_start",
+        default_keys: &["["],
+        description: "start the selection at the record at the top",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::SelectRangeEnd,
+        name: "select_range_end",
+        default_keys: &["]"],
+        description: "end the selection at the record at the top",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ClearSelection,
+        name: "clear_selection",
+        default_keys: &["esc"],
+        description: "clear the selection",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ExportSelection,
+        name: "export_selection",
+        default_keys: &["E"],
+        description: "ask for a file and write the selected records to it",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ExportSelectionIds,
+        name: "export_selection_ids",
+        default_keys: &["W"],
+        description: "ask for a file and write the IDs of the selected records to it",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ExportView,
+        name: "export_view",
+        default_keys: &["V"],
+        description: "ask for a file and write the selected or loaded records in color to it",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::CopySequence,
+        name: "copy_sequence",
+        default_keys: &["y"],
+        description: "copy the sequence of the record at the top to the clipboard",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::CopyRecord,
+        name: "copy_record",
+        default_keys: &["Y"],
+        description: "copy the record at the top to the clipboard as FASTQ / FASTA",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::SummarizeSelection,
+        name: "summarize_selection",
+        default_keys: &["s"],
+        description: "summarize the pattern combinations of the selected records",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ExplainMatches,
+        name: "explain_matches",
+        default_keys: &["x"],
+        description: "show the best match of the selected (or every) pattern in the record at \
+            the top",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ShowMatchStatistics,
+        name: "show_match_statistics",
+        default_keys: &["H"],
+        description: "show histograms of the edit distances of each pattern's matches",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ShowBaseQuality,
+        name: "show_base_quality",
+        default_keys: &["p"],
+        description: "ask for a base position and show the base's exact quality score",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::FindRead,
+        name: "find_read",
+        default_keys: &["F"],
+        description: "ask for [N] MOTIF and jump to the Nth read below containing the motif",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::GoToRecord,
+        name: "go_to_record",
+        default_keys: &[":"],
+        description: "ask for a record number and jump to it",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::NextMatch,
+        name: "next_match",
+        default_keys: &["n"],
+        description: "jump to the next record matching any pattern",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::PreviousMatch,
+        name: "previous_match",
+        default_keys: &["N"],
+        description: "jump to the previous record matching any pattern",
+    },
+    ActionInfo {
+        context: KeyContext::Viewer,
+        action: Action::ShowHelp,
+        name: "show_help",
+        default_keys: &["?"],
+        description: "list all keybindings",
+    },
+    ActionInfo {
+        context: KeyContext::SearchPanel,
+        action: Action::ToggleSearchPanel,
+        name: "close",
+        default_keys: &["esc"],
+        description: "close the search panel",
+    },
+    ActionInfo {
+        context: KeyContext::SearchPanel,
+        action: Action::FocusNext,
+        name: "focus_next",
+        default_keys: &["right", "tab"],
+        description: "focus the next field",
+    },
+    ActionInfo {
+        context: KeyContext::SearchPanel,
+        action: Action::FocusPrevious,
+        name: "focus_previous",
+        default_keys: &["left", "backtab"],
+        description: "focus the previous field",
+    },
+    ActionInfo {
+        context: KeyContext::SearchPanel,
+        action: Action::SavePatterns,
+        name: "save_patterns",
+        default_keys: &["ctrl+s"],
+        description: "save the patterns to a CSV file",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::PreviousPattern,
+        name: "previous_pattern",
+        default_keys: &["up"],
+        description: "select the previous pattern",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::NextPattern,
+        name: "next_pattern",
+        default_keys: &["down"],
+        description: "select the next pattern",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::DeletePattern,
+        name: "delete_pattern",
+        default_keys: &["d", "delete", "backspace"],
+        description: "delete the selected pattern",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::EditPattern,
+        name: "edit_pattern",
+        default_keys: &["enter"],
+        description: "pop the selected pattern into the input boxes",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::TogglePattern,
+        name: "toggle_pattern",
+        default_keys: &["space"],
+        description: "disable the selected pattern without deleting it, or enable it again",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::MovePatternUp,
+        name: "move_pattern_up",
+        default_keys: &["ctrl+up"],
+        description: "move the selected pattern up the list",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::MovePatternDown,
+        name: "move_pattern_down",
+        default_keys: &["ctrl+down"],
+        description: "move the selected pattern down the list",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::WidenEditDistance,
+        name: "widen_edit_distance",
+        default_keys: &["+", "="],
+        description: "increase the edit distance of the selected pattern",
+    },
+    ActionInfo {
+        context: KeyContext::PatternsList,
+        action: Action::NarrowEditDistance,
+        name: "narrow_edit_distance",
+        default_keys: &["-"],
+        description: "decrease the edit distance of the selected pattern",
+    },
+    ActionInfo {
+        context: KeyContext::PatternInputs,
+        action: Action::AddPattern,
+        name: "add_pattern",
+        default_keys: &["enter"],
+        description: "add the inputs as a search pattern",
+    },
+    ActionInfo {
+        context: KeyContext::Report,
+        action: Action::CloseReport,
+        name: "close",
+        default_keys: &["esc", "q"],
+        description: "close the popup",
+    },
+    ActionInfo {
+        context: KeyContext::Bookmarks,
+        action: Action::ToggleBookmarksPopup,
+        name: "close",
+        default_keys: &["esc", "'", "q"],
+        description: "close the bookmarks",
+    },
+    ActionInfo {
+        context: KeyContext::Bookmarks,
+        action: Action::PreviousBookmark,
+        name: "previous_bookmark",
+        default_keys: &["up", "k"],
+        description: "select the previous bookmark",
+    },
+    ActionInfo {
+        context: KeyContext::Bookmarks,
+        action: Action::NextBookmark,
+        name: "next_bookmark",
+        default_keys: &["down", "j"],
+        description: "select the next bookmark",
+    },
+    ActionInfo {
+        context: KeyContext::Bookmarks,
+        action: Action::JumpToBookmark,
+        name: "jump_to_bookmark",
+        default_keys: &["enter"],
+        description: "jump to the selected bookmark",
+    },
+    ActionInfo {
+        context: KeyContext::Bookmarks,
+        action: Action::DeleteBookmark,
+        name: "delete_bookmark",
+        default_keys: &["d", "delete", "backspace"],
+        description: "delete the selected bookmark",
+    },
+];
+
+/// A single key press, e.g. `ctrl+f`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for KeyBinding {
+    fn from(keyevent: KeyEvent) -> Self {
+        let mut modifiers = keyevent.modifiers;
+        // shift is already part of upper case characters and BackTab
+        if matches!(keyevent.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self {
+            code: keyevent.code,
+            modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the key itself may be '+', e.g. "ctrl++"
+        let (modifier_str, key) = match s.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest, "+"),
+            _ => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_str.split('+').filter(|x| !x.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier {} in key {}", modifier, s)),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            _ => match key.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                    KeyCode::BackTab
+                }
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                f if f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                    KeyCode::F(f[1..].parse().unwrap())
+                }
+                _ => return Err(format!("Unknown key {}", s)),
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "shift+tab"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+/// A sequence of key presses separated by spaces, e.g. `g g`
+fn parse_key_sequence(s: &str) -> Result<Vec<KeyBinding>, String> {
+    let sequence = s
+        .split_whitespace()
+        .map(KeyBinding::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err(String::from("Empty key"));
+    }
+    Ok(sequence)
+}
+
+pub enum KeyLookup {
+    Action(Action),
+    Prefix, // the keys so far start a longer binding, wait for more
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, Vec<(Vec<KeyBinding>, Action)>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new()).expect("Invalid default keybindings")
+    }
+}
+
+impl Keymap {
+    /// Build the keymap from the `[keymap.<context>]` sections of the config file, mapping
+    /// action names to lists of keys. Actions not in the config keep their default keys,
+    /// configured keys take precedence over default ones.
+    pub fn from_config(
+        config: &HashMap<String, HashMap<String, Vec<String>>>,
+    ) -> Result<Self, String> {
+        for (context_name, actions) in config {
+            if !ACTIONS.iter().any(|x| x.context.name() == context_name) {
+                return Err(format!("Unknown keymap section {}", context_name));
+            }
+            for action_name in actions.keys() {
+                if !ACTIONS
+                    .iter()
+                    .any(|x| x.context.name() == context_name && x.name == action_name)
+                {
+                    return Err(format!(
+                        "Unknown action {} in keymap section {}",
+                        action_name, context_name
+                    ));
+                }
+            }
+        }
+
+        let mut bindings: HashMap<KeyContext, Vec<(Vec<KeyBinding>, Action)>> = HashMap::new();
+        let mut defaults: Vec<(KeyContext, Vec<KeyBinding>, Action)> = Vec::new();
+        for info in ACTIONS {
+            let configured = config
+                .get(info.context.name())
+                .and_then(|actions| actions.get(info.name));
+            let keys: Vec<&str> = match configured {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => info.default_keys.to_vec(),
+            };
+            for key in keys {
+                let sequence = parse_key_sequence(key)
+                    .map_err(|e| format!("{} (keymap.{}.{})", e, info.context.name(), info.name))?;
+                if configured.is_some() {
+                    bindings
+                        .entry(info.context)
+                        .or_default()
+                        .push((sequence, info.action));
+                } else {
+                    defaults.push((info.context, sequence, info.action));
+                }
+            }
+        }
+        for (context, sequence, action) in defaults {
+            bindings
+                .entry(context)
+                .or_default()
+                .push((sequence, action));
+        }
+        Ok(Self { bindings })
+    }
+
+    /// Look up the keys pressed so far in the contexts, earlier contexts take precedence
+    pub fn lookup(&self, contexts: &[KeyContext], keys: &[KeyBinding]) -> KeyLookup {
+        let mut is_prefix = false;
+        for context in contexts {
+            for (sequence, action) in self.bindings.get(context).into_iter().flatten() {
+                if sequence == keys {
+                    return KeyLookup::Action(*action);
+                }
+                is_prefix |= sequence.len() > keys.len() && sequence.starts_with(keys);
+            }
+        }
+        if is_prefix {
+            KeyLookup::Prefix
+        } else {
+            KeyLookup::None
+        }
+    }
+
+    /// Keys bound to an action, formatted for display
+    pub fn keys_for(&self, context: KeyContext, action: Action) -> Vec<String> {
+        self.bindings
+            .get(&context)
+            .into_iter()
+            .flatten()
+            .filter(|(_, x)| *x == action)
+            .map(|(sequence, _)| {
+                sequence
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// A line per action with its keys and description, under a `[context]` header per
+    /// context in the order of `ACTIONS`; actions without keys are left out
+    pub fn help_lines(&self) -> Vec<String> {
+        let rows: Vec<(KeyContext, String, &str)> = ACTIONS
+            .iter()
+            .filter_map(|info| {
+                let keys = self.keys_for(info.context, info.action);
+                (!keys.is_empty()).then(|| (info.context, keys.join(", "), info.description))
+            })
+            .collect();
+        let width = rows
+            .iter()
+            .map(|(_, keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut lines = Vec::new();
+        let mut current = None;
+        for (context, keys, description) in rows {
+            if current != Some(context) {
+                if current.is_some() {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{}]", context.name()));
+                current = Some(context);
+            }
+            lines.push(format!("  {:<width$}  {}", keys, description));
+        }
+        lines
+    }
+}
+
+#[test]
+fn test_keymap() {
+    let key = |s: &str| KeyBinding::from_str(s).unwrap();
+    assert_eq!(
+        key("ctrl+f"),
+        KeyBinding::from(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL))
+    );
+    assert_eq!(
+        key("shift+j"),
+        KeyBinding::from(KeyEvent::new(KeyCode::Char('J'), KeyModifiers::SHIFT))
+    );
+    assert_eq!(key("ctrl++").code, KeyCode::Char('+'));
+    assert!(KeyBinding::from_str("hyper+x").is_err());
+
+    let config = HashMap::from([(
+        String::from("viewer"),
+        HashMap::from([
+            (String::from("scroll_down"), vec![String::from("n")]),
+            (String::from("toggle_wrap"), vec![String::from("j")]),
+        ]),
+    )]);
+    let keymap = Keymap::from_config(&config).unwrap();
+    let contexts = [KeyContext::Global, KeyContext::Viewer];
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("n")]),
+        KeyLookup::Action(Action::ScrollDown)
+    ));
+    // configured keys win over defaults, remapped actions lose their defaults
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("j")]),
+        KeyLookup::Action(Action::ToggleWrap)
+    ));
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("down")]),
+        KeyLookup::None
+    ));
+    // unmapped actions keep their defaults
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("g")]),
+        KeyLookup::Prefix
+    ));
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("g"), key("g")]),
+        KeyLookup::Action(Action::ScrollToTop)
+    ));
+    assert!(matches!(
+        keymap.lookup(&contexts, &[key("/")]),
+        KeyLookup::Action(Action::ToggleSearchPanel)
+    ));
+
+    let config = HashMap::from([(
+        String::from("viewer"),
+        HashMap::from([(String::from("fly"), vec![String::from("f")])]),
+    )]);
+    assert!(Keymap::from_config(&config).is_err());
+}
+
+#[test]
+fn test_help_lines() {
+    let lines = Keymap::default().help_lines();
+    assert_eq!(lines[0], "[global]");
+    assert!(lines
+        .iter()
+        .any(|x| x.starts_with("  g g") && x.ends_with("scroll to the first record")));
+    assert!(lines.contains(&String::from("[patterns_list]")));
+    // one line per bound action, a header and a blank line between contexts
+    let contexts = 7;
+    assert_eq!(lines.len(), ACTIONS.len() + 2 * contexts - 1);
+}
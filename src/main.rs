@@ -1,21 +1,37 @@
 pub mod app;
+pub mod collapsing;
+pub mod config;
 pub mod control;
 pub mod event;
 pub mod io;
+pub mod keymap;
+pub mod match_coordinates;
+pub mod match_profile;
+pub mod match_summarizing;
+pub mod presets;
+pub mod primer_sites;
 pub mod read_stylizing;
 pub mod search_panel;
+pub mod session;
+#[cfg(test)]
+mod test_utils;
+pub mod trimming;
 pub mod tui;
-pub mod match_summarizing;
 mod ui;
 
-use crate::control::{handle_input, SearchPatternEdit, Update};
-use anyhow::Result;
-use app::{App, SearchPattern};
+use crate::control::{handle_input, BookmarkEdit, SearchPatternEdit, SelectionEdit, Update};
+use anyhow::{Context, Result};
+use app::{App, MatchAlgorithm, Molecule, SearchPattern};
 use bio::io::fastq;
 use clap::{Parser, Subcommand};
+use config::Config;
 use event::{Event, EventHandler};
+use io::fastq::{DecompressOptions, FastqReader};
+use io::output::OutputWriter;
 use ratatui::prelude::{Color, CrosstermBackend, Terminal};
+use session::ViewerState;
 use shadow_rs::shadow;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tui::Tui;
 
@@ -24,13 +40,19 @@ shadow!(build);
 /// A pager for viewing FASTQ files with fuzzy matching, allowing different adaptors to be colored differently.
 #[derive(Parser, Debug)]
 #[command(author, about, long_about = None)]
+#[command(
+    after_help = "Exit codes: 0 success, 2 invalid arguments, 3 no patterns given, \
+    4 I/O error, 5 invalid input (FASTQ, pattern CSV or config file)"
+)]
 #[command(version = build::CLAP_LONG_VERSION)]
 struct Args {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// The FASTQ file to view
-    file: PathBuf,
+    /// The FASTQ file to view, can be gzip (.gz) or zstd (.zst) compressed, `-` for standard
+    /// input
+    #[clap(required_unless_present = "replay")]
+    file: Option<PathBuf>,
 
     /// Start with 10x 3' kit adaptors:
     ///  - Patrial Read1: CTACACGACGCTCTTCCGATCT (and reverse complement)
@@ -47,17 +69,217 @@ struct Args {
     #[clap(long, verbatim_doc_comment)]
     adapter_5p: bool,
 
+    /// Start with Oxford Nanopore ligation adaptors
+    ///  - Y adapter top strand: AATGTACTTCGTTCAGTTACGTATTGCT (and reverse complement)
+    ///  - Y adapter bottom strand: GCAATACGTAACTGAACGAAGT (and reverse complement)
+    ///  - Poly(>10)A/T
+    #[clap(long, verbatim_doc_comment)]
+    adapter_ont: bool,
+
+    /// Start with the PacBio SMRTbell hairpin adaptor
+    ///  - SMRTbell: ATCTCTCTCAACAACAACAACGGAGGAGGAGGAAAAGAGAGAGAT (and reverse complement)
+    ///  - Poly(>10)A/T
+    #[clap(long, verbatim_doc_comment)]
+    adapter_pacbio: bool,
+
     /// Start with patterns from a CSV file
     /// Must have the following header:
     /// pattern,color,editdistance,comment
+    /// optionally followed by algorithm (myers, affine or regex) and revcomp (true or false)
+    /// columns
+    /// Variants sharing a color and comment can be grouped as one pattern, e.g. ACGT|ACCT
     #[clap(short = 'p', long = "patterns", verbatim_doc_comment)]
     patterns_path: Option<PathBuf>,
 
-    /// Save the search panel to a CSV file before quitting.
+    /// Start with patterns from a FASTA file (can be compressed), e.g. a vendor's adaptor list.
+    /// Record IDs become the comments, colors rotate and the edit distance is a tenth of the
+    /// pattern length unless --fasta-edit-distance is given
+    #[clap(long = "patterns-fasta", value_name = "FASTA")]
+    patterns_fasta_path: Option<PathBuf>,
+
+    /// Edit distance of all patterns from --patterns-fasta
+    #[clap(long, value_name = "N", requires = "patterns_fasta_path")]
+    fasta_edit_distance: Option<u8>,
+
+    /// Save the search panel to a CSV file (gzip-compressed if ending with .gz) before quitting.
     /// To be removed in the future since you can now hit
     /// Ctrl-S in the search panel to save the patterns.
     #[clap(short = 's', long = "save-patterns")]
     save_patterns_path: Option<PathBuf>,
+
+    /// Autosave the patterns shortly after each edit, offering to restore them on the next
+    /// launch with the same FILE if seqsizzle did not exit cleanly, e.g. on a crash
+    #[clap(long)]
+    autosave: bool,
+
+    /// Keep compressed input decompressed in DIR (default: seqsizzle under the system temp
+    /// directory) and reuse it in later runs as long as the input is unchanged
+    #[clap(long, value_name = "DIR", num_args = 0..=1, require_equals = true)]
+    keep_decompressed: Option<Option<PathBuf>>,
+
+    /// Decompress compressed input into DIR instead of the system temp directory ($TMPDIR),
+    /// e.g. a scratch filesystem with room for large files
+    #[clap(long, value_name = "DIR")]
+    temp_dir: Option<PathBuf>,
+
+    /// Also search each read followed by its reverse complement and highlight matches spanning
+    /// the junction, labeling the read as a potential (fold-back) chimera
+    #[clap(long)]
+    chimera_junctions: bool,
+
+    /// RNA reads: match U in reads and patterns as T, and show U as the complement of A
+    #[clap(long)]
+    rna: bool,
+
+    /// Protein sequences: patterns match amino acids, with the ambiguity codes B (D or N),
+    /// Z (E or Q), J (I or L) and X (any) instead of the IUPAC nucleotide codes, and nothing
+    /// is reverse complemented
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "rna", "chimera_junctions", "adapter_3p", "adapter_5p", "adapter_ont", "adapter_pacbio"
+        ]
+    )]
+    protein: bool,
+
+    /// Match all patterns with affine gap penalties instead of plain edit distance, so that a
+    /// long indel costs about as much as a substitution. Slower, per pattern with the
+    /// algorithm column of the pattern CSV file. Regex patterns are left as they are
+    #[clap(long)]
+    affine_gaps: bool,
+
+    /// Style bases by quality: dim the ones below --quality-threshold, or emphasize (bold) the
+    /// ones at or above it. Toggle with `Q` in the viewer, switch with `e`
+    #[clap(long, value_enum, value_name = "MODE")]
+    quality_style: Option<read_stylizing::QualityStyleMode>,
+
+    /// Phred quality threshold for --quality-style
+    #[clap(long, value_name = "Q", default_value_t = 20)]
+    quality_threshold: u8,
+
+    /// Color IDs by read length from blue (short) to red (long), toggle with `L` in the viewer
+    #[clap(long)]
+    length_gradient: bool,
+
+    /// Fixed read length scale for --length-gradient, defaults to the lengths on screen
+    #[clap(
+        long,
+        value_name = "MIN..MAX",
+        value_parser = read_stylizing::length_gradient::parse_length_scale
+    )]
+    length_scale: Option<(usize, usize)>,
+
+    /// Matches starting or ending within this many bases of the read ends are trimmed by the
+    /// trim preview (`t` in the viewer) and the trim subcommand, lower it to keep motifs near
+    /// the ends
+    #[clap(long, value_name = "BASES", default_value_t = trimming::TRIM_END_WINDOW)]
+    trim_window: usize,
+
+    /// Fade fuzzy matches by edit distance, exact matches in the full pattern color, toggle
+    /// with `f` in the viewer
+    #[clap(long)]
+    match_confidence: bool,
+
+    /// Column right of the reads with the first letter of the comment of each matched pattern,
+    /// to scan for matches without telling colors apart, toggle with `M` in the viewer
+    #[clap(long)]
+    match_markers: bool,
+
+    /// Italicize lowercase bases, which some assemblers use for low confidence, regardless of
+    /// quality, toggle with `I` in the viewer
+    #[clap(long)]
+    lowercase_italic: bool,
+
+    /// Show the length and GC content (and N content if any) of each read after its ID, e.g.
+    /// to spot contamination, toggle with `G` in the viewer
+    #[clap(long)]
+    base_composition: bool,
+
+    /// Color the bases by identity (A green, C blue, G yellow, T / U red, N gray), e.g. to
+    /// spot homopolymers; pattern colors take precedence, toggle with `b` in the viewer
+    #[clap(long)]
+    base_colors: bool,
+
+    /// Show the raw quality string of each FASTQ record under its sequence, aligned base by
+    /// base, toggle with `r` in the viewer
+    #[clap(long)]
+    quality_line: bool,
+
+    /// Line after each record to tell reads apart
+    #[clap(
+        long,
+        value_enum,
+        value_name = "SEPARATOR",
+        default_value_t = app::RecordSeparator::None
+    )]
+    record_separator: app::RecordSeparator,
+
+    /// Emphasis of matched bases besides the pattern color, e.g. when colors are hard to tell
+    /// apart on a terminal
+    #[clap(long, value_enum, value_name = "STYLE", default_value_t = app::MatchStyle::Color)]
+    match_style: app::MatchStyle,
+
+    /// TOML config file, e.g. to remap keys (default: ~/.config/seqsizzle/config.toml if it exists)
+    #[clap(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Restore a viewer state saved with `S`: file (unless given), patterns, styling and position
+    #[clap(long, value_name = "STATE")]
+    replay: Option<PathBuf>,
+
+    /// View reads grouped by the combination of patterns they match (`combination`, largest
+    /// group first), or with the reads matching a pattern (search string or comment) first.
+    /// Reads the whole file before opening the viewer, which may take a while for large files
+    #[clap(long, value_name = "combination|PATTERN")]
+    group_by: Option<match_summarizing::GroupBy>,
+
+    /// Open the viewer scrolled to the N-th record (1-based)
+    #[clap(long, value_name = "N", conflicts_with = "start_id")]
+    start_at: Option<usize>,
+
+    /// Open the viewer scrolled to the first record with the given ID
+    #[clap(long, value_name = "ID")]
+    start_id: Option<String>,
+
+    /// Open the viewer at the first record starting at or after byte BYTES of the file (of
+    /// the decompressed file for compressed input), numbering records from there without reading
+    /// the file up to it. --start-at and --start-id count from that record
+    #[clap(long, value_name = "BYTES", conflicts_with_all = ["group_by", "replay"])]
+    start_offset: Option<u64>,
+
+    /// Read the file once to write the byte position of every record to
+    /// FILE.seqsizzle-index, used by later runs (until the file changes) to jump to any record
+    /// directly, e.g. with `:` or when scrolling back. Uncompressed files only
+    #[clap(long)]
+    build_index: bool,
+
+    /// Show one read of each run of identical sequences (`consecutive`) or of each distinct
+    /// sequence (`all`), with the number of reads after its ID. Reads the whole file before
+    /// opening the viewer
+    #[clap(
+        long,
+        value_enum,
+        value_name = "MODE",
+        conflicts_with_all = ["group_by", "start_offset"]
+    )]
+    collapse: Option<collapsing::CollapseMode>,
+
+    /// TSV without header of read_id and the 0-based start of the read within a reference
+    /// such as an amplicon, the reference coordinates of the listed reads are shown after
+    /// their IDs
+    #[clap(long, value_name = "FILE")]
+    coordinate_offsets: Option<PathBuf>,
+
+    /// Number of threads searching the reads on screen in parallel, defaults to one per core;
+    /// fewer leave cores free on shared servers
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Number of records rendered from the top of the view on, at least the records a screen
+    /// shows (half the terminal height); defaults to 100. More smooth out scrolling on tall
+    /// terminals, fewer save work with many patterns
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    buffer_size: Option<u64>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -73,120 +295,803 @@ enum Commands {
         /// Print the counts of each summarized catagory instead of the percentage
         #[clap(long)]
         counts: bool,
+
+        /// Report whether each category has a valid structure, i.e. the pattern (search string
+        /// or comment) occurs MIN to MAX times, e.g. `--require R1=2` or `--require TSO=1..`.
+        /// Can be repeated, adds a valid_structure column
+        #[clap(long = "require", value_name = "PATTERN=MIN..MAX")]
+        constraints: Vec<match_summarizing::OccurrenceConstraint>,
+
+        /// Instead of the summary, write the reads of one pattern combination as printed in
+        /// the summary (e.g. `ATCG..GATC`, or `R1..TSO` with comments in place of search
+        /// strings) as FASTQ to stdout
+        #[clap(long, value_name = "COMBINATION")]
+        filter: Option<String>,
+    },
+    /// Write the coordinates of all matches of the patterns (specified the same way as for
+    /// summarize) in all reads, as TSV with a header: read_id, pattern, comment, start, end,
+    /// edit_distance. Coordinates are 0-based, end exclusive.
+    Coordinates {
+        /// Output file, defaults to stdout, gzip-compressed if ending with .gz
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write BED (read_id, start, end, comment, edit_distance, strand) instead of TSV
+        #[clap(long)]
+        bed: bool,
+    },
+    /// Write the fraction of reads matched by any of the patterns (specified the same way as
+    /// for summarize) at each relative read position, as CSV with a header: bin_start,
+    /// bin_end (percent of the read length), reads, fraction. Shows where adaptors sit.
+    Profile {
+        /// Output file, defaults to stdout, gzip-compressed if ending with .gz
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// Number of equal bins from the start to the end of the reads
+        #[clap(long, default_value_t = 100, value_parser = clap::value_parser!(u16).range(1..))]
+        bins: u16,
+    },
+    /// Write the reads trimmed as in the trim preview (`t` in the viewer): matches of the
+    /// patterns (specified the same way as for summarize) within 10 bases (--trim-window) of
+    /// either read end, then poly-A / poly-T tails (10+ bases) are removed, qualities are
+    /// sliced to match.
+    Trim {
+        /// Output file, defaults to stdout, gzip-compressed if ending with .gz
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// Only trim matches of this pattern (search string or comment), can be repeated,
+        /// defaults to all patterns
+        #[clap(long = "trim-pattern", value_name = "PATTERN")]
+        trim_patterns: Vec<String>,
+
+        /// Drop reads shorter than this after trimming
+        #[clap(long, default_value_t = 1)]
+        min_length: usize,
+
+        /// Write FASTA instead of FASTQ
+        #[clap(long)]
+        fasta: bool,
+
+        /// Wrap FASTA sequences at this many columns, 0 for one line per sequence
+        #[clap(long, default_value_t = 60, value_name = "COLUMNS")]
+        fasta_width: usize,
+    },
+    /// Write every site of a reference FASTA (the FILE argument) a primer could bind, on
+    /// either strand, as TSV with a header: record_id, strand, start, end (0-based, end
+    /// exclusive), edit_distance, edits (e.g. `18A>G` a mismatch at primer base 18 counted
+    /// from the 5' end), site. IUPAC codes in the primer match any of their bases. The number
+    /// of sites at each edit distance is reported on stderr to judge the primer's specificity
+    PrimerSites {
+        /// Primer sequence, 5' to 3'
+        primer: String,
+
+        /// Largest edit distance of a binding site
+        #[clap(short, long, default_value_t = 2)]
+        edit_distance: u8,
+
+        /// Output file, defaults to stdout, gzip-compressed if ending with .gz
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Exit codes, 0 on success and 2 for invalid arguments (from clap)
+const EXIT_NO_PATTERNS: i32 = 3;
+const EXIT_IO_ERROR: i32 = 4;
+const EXIT_INVALID_INPUT: i32 = 5;
+
+#[derive(Debug)]
+enum CliError {
+    NoPatterns,
+    InvalidInput(String),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::NoPatterns => write!(
+                f,
+                "No patterns to search with, specify --patterns or one of the --adapter-* presets \
+                 before the subcommand, e.g. seqsizzle my.fastq -p my_patterns.csv summarize"
+            ),
+            CliError::InvalidInput(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn exit_code(error: &anyhow::Error) -> i32 {
+    if let Some(CliError::NoPatterns) = error.downcast_ref::<CliError>() {
+        EXIT_NO_PATTERNS
+    } else if error.chain().any(|e| {
+        e.is::<std::io::Error>()
+            || e.downcast_ref::<csv::Error>()
+                .is_some_and(csv::Error::is_io_error)
+    }) {
+        EXIT_IO_ERROR
+    } else {
+        EXIT_INVALID_INPUT
     }
 }
 
-fn main() -> Result<()> {
+/// Read patterns from a CSV file with the header pattern,color,editdistance,comment
+fn read_patterns_csv(path: &std::path::Path) -> Result<Vec<SearchPattern>> {
+    let invalid = |msg: String| CliError::InvalidInput(format!("{}: {}", path.display(), msg));
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Error opening pattern CSV file {}", path.display()))?;
+    let headers = reader.headers()?.clone();
+    let columns = headers.len();
+    let optional: Vec<&str> = headers.iter().skip(4).collect();
+    let column = |name: &str| optional.iter().position(|x| *x == name).map(|i| i + 4);
+    let (algorithm_column, rev_comp_column, enabled_column) =
+        (column("algorithm"), column("revcomp"), column("enabled"));
+    let optional_count = [algorithm_column, rev_comp_column, enabled_column]
+        .iter()
+        .flatten()
+        .count();
+    if !(headers
+        .iter()
+        .take(4)
+        .eq(["pattern", "color", "editdistance", "comment"])
+        && columns == 4 + optional_count)
+    {
+        return Err(invalid(String::from(
+            "Pattern CSV file headers must be: \
+             pattern,color,editdistance,comment[,algorithm][,revcomp][,enabled]",
+        ))
+        .into());
+    }
+    let mut patterns: Vec<SearchPattern> = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        if record.len() != columns {
+            return Err(invalid(format!("Malformed pattern CSV file record: {:?}", record)).into());
+        }
+        let color = record[1].parse::<Color>().map_err(|_| {
+            invalid(format!(
+                "Error parsing pattern CSV file record color: {}",
+                &record[1]
+            ))
+        })?;
+        let editdistance = record[2].parse::<u8>().map_err(|_| {
+            invalid(format!(
+                "Error parsing pattern CSV file record editdistance: {}",
+                &record[2]
+            ))
+        })?;
+        let algorithm = algorithm_column
+            .map_or("", |i| &record[i])
+            .parse::<MatchAlgorithm>()
+            .map_err(invalid)?;
+        let flag = |column: Option<usize>, name: &str, default: bool| match column
+            .map_or("", |i| &record[i])
+        {
+            "" => Ok(default),
+            "false" => Ok(false),
+            "true" => Ok(true),
+            x => Err(invalid(format!(
+                "Expected true or false for {}, got {}",
+                name, x
+            ))),
+        };
+        let rev_comp = flag(rev_comp_column, "revcomp", false)?;
+        let enabled = flag(enabled_column, "enabled", true)?;
+        patterns.push(
+            SearchPattern::new(record[0].to_string(), color, editdistance, &record[3])
+                .with_algorithm(algorithm)
+                .with_rev_comp(rev_comp)
+                .with_enabled(enabled),
+        );
+    }
+    Ok(patterns)
+}
+
+/// Read patterns from a FASTA file, one per record with a sequence, colored in turn from the
+/// palette
+fn read_patterns_fasta(
+    path: &std::path::Path,
+    edit_distance: Option<u8>,
+    palette: &[Color],
+) -> Result<Vec<SearchPattern>> {
+    let mut patterns: Vec<SearchPattern> = Vec::new();
+    for record in bio::io::fasta::Reader::new(open_fastq(path)?).records() {
+        let record = record.map_err(|e| {
+            CliError::InvalidInput(format!("{}: invalid FASTA record: {}", path.display(), e))
+        })?;
+        let seq = String::from_utf8_lossy(record.seq()).to_string();
+        if seq.is_empty() {
+            continue; // a header without sequence lines
+        }
+        let edit_distance =
+            edit_distance.unwrap_or_else(|| (seq.len() / 10).min(u8::MAX.into()) as u8);
+        patterns.push(SearchPattern::new(
+            seq,
+            palette[patterns.len() % palette.len()],
+            edit_distance,
+            record.id(),
+        ));
+    }
+    Ok(patterns)
+}
+
+#[test]
+fn test_read_patterns_fasta() {
+    let content = ">adaptor first\nACGTACGTAC\nGGTT\n>empty\n>polyA\nAAAA\n";
+    let path = test_utils::TempFileGuard::with_content("fasta", content);
+    let palette = presets::DEFAULT_PALETTE;
+    let patterns = read_patterns_fasta(&path, None, &palette).unwrap();
+    let summary: Vec<(&str, Color, u8, &str)> = patterns
+        .iter()
+        .map(|x| {
+            (
+                x.search_string.as_str(),
+                x.color,
+                x.edit_distance,
+                x.comment.as_str(),
+            )
+        })
+        .collect();
+    // edit distance of a tenth of the length, colors continuing past the empty record
+    assert_eq!(
+        summary,
+        vec![
+            ("ACGTACGTACGGTT", palette[0], 1, "adaptor"),
+            ("AAAA", palette[1], 0, "polyA"),
+        ]
+    );
+    let patterns = read_patterns_fasta(&path, Some(3), &palette).unwrap();
+    assert!(patterns.iter().all(|x| x.edit_distance == 3));
+}
+
+#[test]
+fn test_read_patterns_csv() {
+    let path = test_utils::TempFileGuard::new("csv");
+    let patterns = vec![
+        SearchPattern::new(String::from("ACGT"), Color::Red, 1, "R1").with_rev_comp(true),
+        SearchPattern::new(String::from("TTTT"), Color::Blue, 0, "").with_enabled(false),
+        SearchPattern::new(String::from("A{10,}$|^T{10,}"), Color::Green, 0, "polyA")
+            .with_algorithm(MatchAlgorithm::Regex),
+        SearchPattern::new(String::from("GGCC"), Color::Rgb(0x1f, 0x77, 0xb4), 0, ""),
+        SearchPattern::new(String::from("GGAA"), Color::Indexed(208), 0, ""),
+    ];
+    app::save_patterns_csv(&patterns, &path).unwrap();
+    assert_eq!(read_patterns_csv(&path).unwrap(), patterns);
+    // lowercase hex codes too
+    std::fs::write(
+        &path,
+        "pattern,color,editdistance,comment\nACGT,#1f77b4,1,\n",
+    )
+    .unwrap();
+    assert_eq!(
+        read_patterns_csv(&path).unwrap()[0].color,
+        Color::Rgb(0x1f, 0x77, 0xb4)
+    );
+    std::fs::write(
+        &path,
+        "pattern,color,editdistance,comment,revcomp,algorithm\nACGT,Red,1,,true,affine\n",
+    )
+    .unwrap();
+    let read = read_patterns_csv(&path).unwrap();
+    assert!(read[0].search_rev_comp && read[0].algorithm == MatchAlgorithm::AffineGap);
+    std::fs::write(
+        &path,
+        "pattern,color,editdistance,comment,revcomp,revcomp\nACGT,Red,1,,true,true\n",
+    )
+    .unwrap();
+    assert!(read_patterns_csv(&path).is_err());
+}
+
+/// Read a TSV of read IDs and their 0-based start within a reference
+fn read_coordinate_offsets(path: &std::path::Path) -> Result<HashMap<String, usize>> {
+    let invalid = |msg: String| CliError::InvalidInput(format!("{}: {}", path.display(), msg));
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .from_reader(open_fastq(path)?);
+    let mut offsets = HashMap::new();
+    for record in reader.records() {
+        let record = record?;
+        if record.len() != 2 {
+            return Err(invalid(format!("Expected read_id and offset, got {:?}", record)).into());
+        }
+        let offset = record[1]
+            .parse()
+            .map_err(|_| invalid(format!("Invalid offset {} of {}", &record[1], &record[0])))?;
+        offsets.insert(record[0].to_string(), offset);
+    }
+    Ok(offsets)
+}
+
+#[test]
+fn test_read_coordinate_offsets() {
+    let path = test_utils::TempFileGuard::with_content("tsv", "read1\t0\nread2\t150\n");
+    let offsets = read_coordinate_offsets(&path).unwrap();
+    assert_eq!(offsets.len(), 2);
+    assert_eq!(offsets["read2"], 150);
+    std::fs::write(&path, "read1\t-5\n").unwrap();
+    assert!(read_coordinate_offsets(&path).is_err());
+}
+
+/// Open a possibly compressed file, errors already name the path
+fn open_fastq(path: &std::path::Path) -> Result<Box<dyn std::io::Read>> {
+    Ok(io::fastq::open_input(path)?)
+}
+
+/// Ask a yes / no question on the terminal, None if stdin is not a terminal
+fn confirm(question: &str) -> Option<bool> {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    Some(std::io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y"))
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {:#}", error);
+        std::process::exit(exit_code(&error));
+    }
+}
+
+fn run() -> Result<()> {
     if !shadow_rs::git_clean() {
-        print!(
+        eprint!(
             "Warning: built with dirty repo:\n{}",
             shadow_rs::git_status_file()
         );
     }
 
     let args = Args::parse();
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .context("Error setting up the thread pool")?;
+    }
+    if let Some(size) = args.buffer_size {
+        // every record takes at least two lines, its ID and sequence, within the borders
+        if let Ok((_, rows)) = crossterm::terminal::size() {
+            let shown = rows.saturating_sub(2) / 2;
+            if size < shown as u64 {
+                return Err(CliError::InvalidInput(format!(
+                    "--buffer-size {} is smaller than the {} records a {}-line terminal shows",
+                    size, shown, rows
+                ))
+                .into());
+            }
+        }
+    }
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => match Config::default_path().filter(|path| path.exists()) {
+            Some(path) => Config::load(&path)?,
+            None => Config::default(),
+        },
+    };
+    let molecule = Molecule::from_flags(args.rna, args.protein);
+    let alphabet = config.alphabet.as_deref().unwrap_or(molecule.alphabet());
+    let palette = config.palette()?;
+    let scroll_config = config.scroll()?;
+    let replay = args.replay.as_deref().map(ViewerState::load).transpose()?;
+    let file = match (&args.file, &replay) {
+        (Some(file), _) => file.clone(),
+        (None, Some(state)) => state.file.clone(),
+        (None, None) => unreachable!("clap requires FILE unless --replay is given"),
+    };
+    if let Some(dir) = &args.temp_dir {
+        io::fastq::validate_temp_dir(dir)?;
+    }
 
     // add patterns based on command line arguments
     let mut patterns: Vec<SearchPattern> = Vec::new();
-    if args.adapter_3p {
-        patterns.extend_from_slice(&[
-            SearchPattern::new("CTACACGACGCTCTTCCGATCT".to_string(), Color::Blue, 3, "R1"),
-            SearchPattern::new("AGATCGGAAGAGCGTCGTGTAG".to_string(), Color::Green, 3, "TSO"),
-            SearchPattern::new("TGGTATCAACGCAGAGTACATGGG".to_string(), Color::Red, 3, "R1 rev"),
-            SearchPattern::new("CCCATGTACTCTGCGTTGATACCA".to_string(), Color::Yellow, 3, "TSO rev"),
-            SearchPattern::new("TTTTTTTTTTTT".to_string(), Color::Gray, 1, ""),
-            SearchPattern::new("AAAAAAAAAAAA".to_string(), Color::Gray, 1, ""),
-        ]);
-    }
-    if args.adapter_5p {
-        patterns.extend_from_slice(&[
-            SearchPattern::new("CTACACGACGCTCTTCCGATCT".to_string(), Color::Blue, 3, "R1"),
-            SearchPattern::new("TTTCTTATATGGG".to_string(), Color::Green, 2, "TSO"),
-            SearchPattern::new("TGGTATCAACGCAGAGTACATGGG".to_string(), Color::Red, 3, "R1 rev"),
-            SearchPattern::new("CCCATATAAGAAA".to_string(), Color::Yellow, 2, "TSO rev"),
-            SearchPattern::new("AGATCGGAAGAGCACACGTCTGAA".to_string(), Color::Cyan, 3, "R2"),
-            SearchPattern::new("TTCAGACGTGTGCTCTTCCGATCT".to_string(), Color::Magenta, 3, "R2 rev"),
-            SearchPattern::new("TTTTTTTTTTTT".to_string(), Color::Gray, 1, ""),
-            SearchPattern::new("AAAAAAAAAAAA".to_string(), Color::Gray, 1, ""),
-        ]);
+    for (enabled, preset) in [
+        (args.adapter_3p, presets::Preset::TenX3p),
+        (args.adapter_5p, presets::Preset::TenX5p),
+        (args.adapter_ont, presets::Preset::Ont),
+        (args.adapter_pacbio, presets::Preset::PacBio),
+    ] {
+        if enabled {
+            patterns.extend(preset.patterns(&palette));
+        }
     }
 
     // add patterns from CSV file
-    if let Some(path) = args.patterns_path {
-        let err_str = "Error opening provided pattern CSV file";
-        let mut reader = csv::Reader::from_path(path).expect(err_str);
-        assert_eq!(
-            reader
-                .headers()
-                .expect("Error reading pattern CSV file headers")
-                .clone(),
-            csv::StringRecord::from(vec!["pattern", "color", "editdistance", "comment"]),
-            "Pattern CSV file headers must be: pattern,color,editdistance,comment"
+    if let Some(path) = &args.patterns_path {
+        patterns.extend(read_patterns_csv(path)?);
+    }
+    if let Some(path) = &args.patterns_fasta_path {
+        patterns.extend(read_patterns_fasta(
+            path,
+            args.fasta_edit_distance,
+            &palette,
+        )?);
+    }
+    if let Some(state) = &replay {
+        patterns.extend(state.search_patterns.iter().cloned());
+    }
+    let autosave_path = session::autosave_path(&file);
+    if args.autosave && args.command.is_none() && autosave_path.exists() {
+        let autosaved = read_patterns_csv(&autosave_path)?;
+        let question = format!(
+            "Found {} autosaved patterns of {} from a session that did not exit cleanly, \
+            restore them?",
+            autosaved.len(),
+            file.display()
         );
-        reader.records().for_each(|record| {
-            let record = record.expect(err_str);
-            let color = record.get(1).expect(err_str);
-            let editdistance = record.get(2).expect(err_str);
-            let comment = record.get(3).expect(err_str).parse::<String>().unwrap();
-            let pattern = SearchPattern::new(
-                record.get(0).expect(err_str).to_string(),
-                color.parse::<Color>().unwrap_or_else(|_| panic!("Error parsing pattern CSV file record color: {}", color)),
-                editdistance.parse::<u8>().unwrap_or_else(|_| panic!("Error parsing pattern CSV file record editdistance: {}",
-                        editdistance)),
-                comment.as_str(),
-            );
-            patterns.push(pattern);
-        });
+        match confirm(&question) {
+            Some(true) => patterns = autosaved,
+            Some(false) => std::fs::remove_file(&autosave_path).with_context(|| {
+                format!("Error removing autosave file {}", autosave_path.display())
+            })?,
+            None => eprintln!(
+                "Warning: autosaved patterns not restored as stdin is not a terminal, load them \
+                 with -p {} before editing patterns overwrites them",
+                autosave_path.display()
+            ),
+        }
+    }
+
+    if args.affine_gaps {
+        patterns = patterns
+            .into_iter()
+            .map(|x| match x.algorithm {
+                MatchAlgorithm::Regex => x,
+                _ => x.with_algorithm(MatchAlgorithm::AffineGap),
+            })
+            .collect();
+    }
+
+    // warn about patterns that would never match
+    let mut pattern_warnings: Vec<String> = Vec::new();
+    for pattern in patterns.iter_mut() {
+        match pattern.validate(alphabet) {
+            Ok(None) => (),
+            Ok(Some(msg)) | Err(msg) => pattern_warnings.push(msg),
+        }
     }
 
     if let Some(command) = args.command {
+        for msg in &pattern_warnings {
+            eprintln!("Warning: {}", msg);
+        }
+        // patterns disabled in the viewer and saved as such are not used
+        patterns.retain(|x| x.enabled);
         match command {
-            Commands::Summarize { counts } => {
+            Commands::Coordinates { output, bed } => {
+                if patterns.is_empty() {
+                    return Err(CliError::NoPatterns.into());
+                }
+                let records = fastq::Reader::new(open_fastq(&file)?).records();
+                match output {
+                    Some(path) => {
+                        let mut writer = OutputWriter::create(&path)?;
+                        match_coordinates::write_match_coordinates(
+                            records,
+                            &patterns,
+                            &mut writer,
+                            bed,
+                            molecule,
+                        )?;
+                        writer.finish()?;
+                    }
+                    None => match_coordinates::write_match_coordinates(
+                        records,
+                        &patterns,
+                        std::io::stdout().lock(),
+                        bed,
+                        molecule,
+                    )?,
+                }
+            }
+            Commands::Profile { output, bins } => {
+                if patterns.is_empty() {
+                    return Err(CliError::NoPatterns.into());
+                }
+                let records = fastq::Reader::new(open_fastq(&file)?).records();
+                let profile = match_profile::MatchProfile::from_records(
+                    records,
+                    &patterns,
+                    bins.into(),
+                    molecule,
+                )?;
+                match output {
+                    Some(path) => {
+                        let mut writer = OutputWriter::create(&path)?;
+                        profile.write_csv(&mut writer)?;
+                        writer.finish()?;
+                    }
+                    None => profile.write_csv(std::io::stdout().lock())?,
+                }
+            }
+            Commands::Trim {
+                output,
+                trim_patterns,
+                min_length,
+                fasta,
+                fasta_width,
+            } => {
+                if patterns.is_empty() {
+                    return Err(CliError::NoPatterns.into());
+                }
+                let mut trimmable: Vec<SearchPattern> = if trim_patterns.is_empty() {
+                    patterns.clone()
+                } else {
+                    Vec::new()
+                };
+                for name in &trim_patterns {
+                    let named: Vec<SearchPattern> = patterns
+                        .iter()
+                        .filter(|x| x.search_string == *name || x.comment == *name)
+                        .cloned()
+                        .collect();
+                    if named.is_empty() {
+                        return Err(
+                            CliError::InvalidInput(format!("No pattern {} to trim", name)).into(),
+                        );
+                    }
+                    trimmable.extend(named);
+                }
+                let records = fastq::Reader::new(open_fastq(&file)?).records();
+                let fasta_width = fasta.then_some(fasta_width);
+                let stats = match output {
+                    Some(path) => {
+                        let mut writer = OutputWriter::create(&path)?;
+                        let stats = trimming::write_trimmed_reads(
+                            records,
+                            &trimmable,
+                            args.trim_window,
+                            &mut writer,
+                            min_length,
+                            fasta_width,
+                            molecule,
+                        )?;
+                        writer.finish()?;
+                        stats
+                    }
+                    None => trimming::write_trimmed_reads(
+                        records,
+                        &trimmable,
+                        args.trim_window,
+                        std::io::stdout().lock(),
+                        min_length,
+                        fasta_width,
+                        molecule,
+                    )?,
+                };
+                eprintln!(
+                    "{} reads written, {} shorter than {} after trimming dropped",
+                    stats.kept, stats.dropped, min_length
+                );
+                if stats.no_quality > 0 {
+                    eprintln!(
+                        "{} reads without quality scores skipped, write them with --fasta",
+                        stats.no_quality
+                    );
+                }
+            }
+            Commands::PrimerSites {
+                primer,
+                edit_distance,
+                output,
+            } => {
+                if molecule == Molecule::Protein {
+                    return Err(CliError::InvalidInput(String::from(
+                        "primer-sites searches both strands of DNA, not with --protein",
+                    ))
+                    .into());
+                }
+                let mut primer = SearchPattern::new(primer, Color::Red, edit_distance, "primer");
+                primer.validate(alphabet).map_err(CliError::InvalidInput)?;
+                if primer.members().count() > 1 {
+                    return Err(CliError::InvalidInput(format!(
+                        "Primer {} must be a single sequence",
+                        primer.search_string
+                    ))
+                    .into());
+                }
+                let records = bio::io::fasta::Reader::new(open_fastq(&file)?).records();
+                let counts = match output {
+                    Some(path) => {
+                        let mut writer = OutputWriter::create(&path)?;
+                        let counts = primer_sites::write_binding_sites(
+                            records,
+                            &primer,
+                            &mut writer,
+                            molecule,
+                        )?;
+                        writer.finish()?;
+                        counts
+                    }
+                    None => primer_sites::write_binding_sites(
+                        records,
+                        &primer,
+                        std::io::stdout().lock(),
+                        molecule,
+                    )?,
+                };
+                for (dist, count) in counts.iter().enumerate() {
+                    eprintln!("{} binding sites at edit distance {}", count, dist);
+                }
+            }
+            Commands::Summarize {
+                counts,
+                constraints,
+                filter,
+            } => {
                 if patterns.is_empty() {
-                    println!("Must specify --patterns or --adapter-3p or --adapter-5p to use the summarize subcommand, e.g. ./SeqSizzle my.fastq -p my_patterns.csv --adapter-3p summarize");
-                    return Err(anyhow::anyhow!("No patterns to summarize with"));
+                    return Err(CliError::NoPatterns.into());
                 }
-                let fastqs: Vec<fastq::Record> = fastq::Reader::from_file(args.file.clone())?
-                    .records()
-                    .collect::<Result<Vec<_>, _>>()?;
-                println!("number_of_read\tpattern_combination");
+                let records = fastq::Reader::new(open_fastq(&file)?).records();
+                if let Some(combination) = filter {
+                    let written = match_summarizing::write_combination_reads(
+                        records,
+                        &patterns,
+                        &combination,
+                        std::io::stdout().lock(),
+                        molecule,
+                    )?;
+                    eprintln!("{} reads of {} written", written, combination);
+                    return Ok(());
+                }
+                let summary =
+                    match_summarizing::summarise_reads(records, &patterns, counts, molecule)?;
+                println!(
+                    "number_of_read\tpattern_combination{}",
+                    if constraints.is_empty() {
+                        ""
+                    } else {
+                        "\tvalid_structure"
+                    }
+                );
                 print!(
                     "{}",
-                    match_summarizing::fmt_summarised_reads(&match_summarizing::summarise_reads(
-                        &fastqs, &patterns, counts
-                    ), counts)
+                    match_summarizing::fmt_summarised_reads(&summary, counts, &constraints)
                 );
             }
         }
         return Ok(());
     }
 
-    let mut app = App::new(&args.file, patterns);
+    let decompress_options = DecompressOptions {
+        cache_dir: args
+            .keep_decompressed
+            .map(|dir| dir.unwrap_or_else(|| std::env::temp_dir().join("seqsizzle"))),
+        temp_dir: args.temp_dir.clone(),
+    };
+    if args.build_index && (io::fastq::is_stdin(&file) || io::fastq::is_compressed(&file)) {
+        return Err(CliError::InvalidInput(String::from(
+            "--build-index needs an uncompressed file, decompress it first",
+        ))
+        .into());
+    }
+    let mut app = App::new(&file, patterns, config.keymap()?, &decompress_options)?;
+    if args.build_index {
+        eprintln!("Indexing {}", file.display());
+        let records = app.build_index()?;
+        eprintln!(
+            "{} records indexed in {}",
+            records,
+            io::fastq::index_path(&file).display()
+        );
+    }
+    if args.autosave {
+        std::fs::create_dir_all(autosave_path.parent().unwrap_or(&autosave_path))
+            .with_context(|| format!("Error creating {}", autosave_path.display()))?;
+        app.autosave_path = Some(autosave_path);
+    }
+    if args.affine_gaps {
+        app.default_algorithm = MatchAlgorithm::AffineGap;
+    }
+    app.alphabet = alphabet.to_string();
+    if let Some(path) = &args.coordinate_offsets {
+        app.coordinate_offsets = read_coordinate_offsets(path)?;
+        app.update();
+    }
+    app.scroll_config = scroll_config;
+    if let Some(size) = args.buffer_size {
+        app.set_render_buf_size(size as usize);
+    }
+    if !pattern_warnings.is_empty() {
+        app.set_message(pattern_warnings.join("; "));
+    }
+    if let Some(group_by) = &args.group_by {
+        eprintln!("Scanning {} to group the reads", file.display());
+        let records = FastqReader::new(io::fastq::open_file(app.input_path())?)?.into_records()?;
+        let order =
+            match_summarizing::group_records(records, &app.search_patterns, group_by, molecule)
+                .map_err(CliError::InvalidInput)?;
+        app.set_record_order(order);
+    }
+    if let Some(mode) = args.collapse {
+        eprintln!("Scanning {} to collapse identical reads", file.display());
+        let records = FastqReader::new(io::fastq::open_file(app.input_path())?)?.into_records()?;
+        let collapsed =
+            collapsing::collapse_records(records, mode).map_err(CliError::InvalidInput)?;
+        app.collapse_records(collapsed);
+    }
+    app.set_styling_config(app::StylingConfig {
+        chimera_junctions: args.chimera_junctions,
+        rna: args.rna,
+        protein: args.protein,
+        match_confidence: args.match_confidence,
+        match_markers: args.match_markers,
+        lowercase_italic: args.lowercase_italic,
+        base_composition: args.base_composition,
+        base_colors: args.base_colors,
+        show_quality_line: args.quality_line,
+        quality_styling: args.quality_style.is_some(),
+        quality_mode: args.quality_style.unwrap_or_default(),
+        quality_threshold: args.quality_threshold,
+        trim_window: args.trim_window,
+        length_gradient: args.length_gradient,
+        length_scale: args.length_scale,
+        record_separator: args.record_separator,
+        match_style: args.match_style,
+        ..app.styling_config.clone()
+    });
+    if let Some(offset) = args.start_offset {
+        app.start_at_offset(offset)
+            .map_err(|e| CliError::InvalidInput(e.to_string()))?;
+    }
+    if let Some(n) = args.start_at {
+        app.go_to_record(n.saturating_sub(1));
+    } else if let Some(id) = args.start_id {
+        match app.find_record_by_id(&id)? {
+            Some(index) => app.go_to_record(index),
+            None => app.set_message(format!("No record with ID {} found", id)),
+        }
+    }
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(std::io::stderr());
     let terminal = Terminal::new(backend)?;
-    if crossterm::style::available_color_count() < 256 {
-        app.set_message(String::from("Warning: your terminal does not support 256 colors"));
+    app.color_count = crossterm::style::available_color_count();
+    if read_stylizing::approximates_colors(app.color_count) {
+        app.set_message(format!(
+            "Warning: your terminal supports {} colors, other colors are approximated",
+            app.color_count
+        ));
     }
     let events = EventHandler::new(250);
     let mut tui = Tui::new(terminal, events);
     tui.enter()?;
+    if let Some(state) = &replay {
+        state.restore(&mut app, tui.size());
+    }
     tui.draw(&mut app)?;
 
     // Start the main loop.
+    let mut redraw_pending = false;
     while !app.quit {
         // Handle events.
         let updates: Update = handle_input(&app, &tui, tui.events.next()?);
+        app.autosave_if_due();
+        let scrolled = matches!(
+            updates,
+            Update::ScrollViewer(_) | Update::ScrollViewerHorizontal(_)
+        );
         match updates {
-            Update::None => continue, // no need to re-draw
+            Update::None => {
+                if !redraw_pending {
+                    continue; // no need to re-draw
+                }
+            }
             Update::ToggleUIMode => app.toggle_ui_mode(),
+            Update::ToggleStyling(toggle) => app.toggle_styling(toggle),
             Update::WindowResize(rect) => {
                 app.resized_update(rect);
             }
             Update::ScrollViewer(num) => {
                 app.scroll(num, tui.size());
             }
+            Update::ScrollViewerHorizontal(num) => {
+                app.scroll_horizontal(num, tui.size());
+            }
             Update::Quit => {
                 app.quit = true;
                 break;
@@ -209,9 +1114,48 @@ fn main() -> Result<()> {
                         app.delete_search_pattern(index);
                     }
                 }
+                SearchPatternEdit::AdjustDistance(index, widen) => {
+                    app.adjust_edit_distance(index, widen)
+                }
+                SearchPatternEdit::Toggle(index) => app.toggle_pattern(index),
+                SearchPatternEdit::Move(index, up) => app.move_search_pattern(index, up),
             },
             Update::Msg(msg) => app.set_message(msg),
             Update::CycleSearchPattern(reverse) => app.cycle_patterns_list(reverse),
+            Update::EditBookmarks(edit) => match edit {
+                BookmarkEdit::Toggle => app.toggle_bookmark(),
+                BookmarkEdit::Delete => app.delete_bookmark(),
+                BookmarkEdit::Jump => app.jump_to_bookmark(),
+            },
+            Update::ToggleBookmarksPopup => app.toggle_bookmarks_popup(),
+            Update::SaveState => {
+                let path = std::path::Path::new(session::STATE_FILE);
+                match ViewerState::capture(&app, tui.size()).save(path) {
+                    Ok(()) => app.set_message(format!("State saved to {}", path.display())),
+                    Err(e) => app.set_message(format!("{:#}", e)),
+                }
+            }
+            Update::EditSelection(edit) => match edit {
+                SelectionEdit::Start => app.select_range_edge(true),
+                SelectionEdit::End => app.select_range_edge(false),
+                SelectionEdit::Clear => app.clear_selection(),
+                SelectionEdit::Export(ids_only) => {
+                    app.open_prompt(app::PromptKind::ExportSelection(ids_only))
+                }
+                SelectionEdit::Summarize => app.summarize_selection(),
+            },
+            Update::CloseReport => app.close_report(),
+            Update::ShowHelp => app.show_help(),
+            Update::CopyRecord(full) => app.copy_record(full),
+            Update::ExplainMatches => app.explain_matches(),
+            Update::ToggleMatchStatistics => app.toggle_match_statistics(),
+            Update::JumpToMatch(forward) => app.jump_to_match(forward),
+            Update::OpenPrompt(kind) => app.open_prompt(kind),
+            Update::PromptInput(input) => {
+                app.prompt.input(input);
+            }
+            Update::SubmitPrompt => app.submit_prompt(),
+            Update::CycleBookmarks(reverse) => app.cycle_bookmarks(reverse),
             Update::SaveFilePopupInput(input) => {
                 app.search_panel.file_popup_input(input);
             }
@@ -229,27 +1173,25 @@ fn main() -> Result<()> {
             }
         };
 
+        // skip drawing intermediate positions while scroll keys are queued, e.g. holding `j`
+        redraw_pending = scrolled && tui.events.key_pending();
+        if redraw_pending {
+            continue;
+        }
         // Render the user interface.
         tui.draw(&mut app)?;
     }
 
     // Save the search panel to a CSV file
-    if args.save_patterns_path.is_some() {
-        let mut writer = csv::Writer::from_path(args.save_patterns_path.unwrap())?;
-        writer
-            .write_record(["pattern", "color", "editdistance", "comment"])
-            .expect("Error writing pattern CSV file headers");
-        app.search_patterns.iter().for_each(|pattern| {
-            writer
-                .write_record(&[
-                    pattern.search_string.clone(),
-                    pattern.color.to_string(),
-                    pattern.edit_distance.to_string(),
-                    pattern.comment.to_string(),
-                ])
-                .expect("Error writing pattern CSV file record");
-        });
-        writer.flush()?;
+    if let Some(save_patterns_path) = args.save_patterns_path {
+        app::save_patterns_csv(&app.search_patterns, &save_patterns_path)?;
+    }
+    // exited cleanly, nothing to restore next time
+    if let Some(path) = &app.autosave_path {
+        if path.exists() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("Error removing autosave file {}", path.display()))?;
+        }
     }
 
     // Exit the user interface.
@@ -0,0 +1,120 @@
+use crate::app::{App, Molecule, SearchPattern};
+use bio::io::fastq;
+use std::io::Write;
+
+/// Write the coordinates of every match of every pattern in every read
+///
+/// Coordinates are 0-based, end exclusive.
+/// TSV (with header): read_id, pattern, comment, start, end, edit_distance
+/// BED (no header): read_id, start, end, name (comment or pattern), edit_distance, strand
+pub fn write_match_coordinates<W: Write>(
+    records: impl Iterator<Item = Result<fastq::Record, fastq::Error>>,
+    search_patterns: &[SearchPattern],
+    writer: W,
+    bed: bool,
+    molecule: Molecule,
+) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .from_writer(writer);
+    if !bed {
+        writer.write_record([
+            "read_id",
+            "pattern",
+            "comment",
+            "start",
+            "end",
+            "edit_distance",
+        ])?;
+    }
+    for record in records {
+        let record = record?;
+        for pattern in search_patterns {
+            // BED has a strand column, so reverse complement matches are searched apart
+            let strands = if bed {
+                pattern.strands(molecule)
+            } else {
+                vec![(pattern.clone(), '+')]
+            };
+            for (strand_pattern, strand) in &strands {
+                for (start, end, dist, _) in App::best_hits(App::search_with_alignment(
+                    &record,
+                    strand_pattern,
+                    molecule,
+                )) {
+                    let (start, end, dist) =
+                        (start.to_string(), (end + 1).to_string(), dist.to_string());
+                    if bed {
+                        let name = if pattern.comment.is_empty() {
+                            &pattern.search_string
+                        } else {
+                            &pattern.comment
+                        };
+                        let strand = strand.to_string();
+                        writer.write_record([record.id(), &start, &end, name, &dist, &strand])?;
+                    } else {
+                        writer.write_record([
+                            record.id(),
+                            &pattern.search_string,
+                            &pattern.comment,
+                            &start,
+                            &end,
+                            &dist,
+                        ])?;
+                    }
+                }
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_write_match_coordinates() {
+    use ratatui::style::Color;
+    let records = vec![Ok(fastq::Record::with_attrs(
+        "id",
+        None,
+        b"TTTTATCGTTTTATGGTT",
+        b"IIIIIIIIIIIIIIIIII",
+    ))];
+    let patterns = vec![SearchPattern::new(
+        String::from("ATCG"),
+        Color::Red,
+        1,
+        "motif",
+    )];
+    let mut out: Vec<u8> = Vec::new();
+    write_match_coordinates(
+        records.into_iter(),
+        &patterns,
+        &mut out,
+        true,
+        Molecule::Dna,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "id\t4\t8\tmotif\t0\t+\nid\t12\t16\tmotif\t1\t+\n"
+    );
+    let records = vec![Ok(fastq::Record::with_attrs(
+        "id",
+        None,
+        b"TTCCATTT",
+        b"IIIIIIII",
+    ))];
+    let patterns =
+        vec![SearchPattern::new(String::from("ATGG"), Color::Red, 0, "").with_rev_comp(true)];
+    let mut out: Vec<u8> = Vec::new();
+    write_match_coordinates(
+        records.into_iter(),
+        &patterns,
+        &mut out,
+        true,
+        Molecule::Dna,
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "id\t2\t6\tATGG\t0\t-\n");
+}
@@ -0,0 +1,118 @@
+use crate::app::{App, Molecule, SearchPattern};
+use bio::io::fastq;
+use std::io::Write;
+
+/// Number of reads matched by any pattern at each relative read position, in `bins` equal
+/// bins from the start (0%) to the end (100%) of the reads
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchProfile {
+    pub reads: usize,
+    pub matched: Vec<usize>, // reads with a match overlapping each bin
+}
+
+impl MatchProfile {
+    /// Stream the records once, a read counts at most once per bin however many patterns
+    /// or matches overlap it
+    pub fn from_records(
+        records: impl Iterator<Item = Result<fastq::Record, fastq::Error>>,
+        search_patterns: &[SearchPattern],
+        bins: usize,
+        molecule: Molecule,
+    ) -> anyhow::Result<Self> {
+        let mut profile = MatchProfile {
+            reads: 0,
+            matched: vec![0; bins],
+        };
+        let mut covered = vec![false; bins];
+        for record in records {
+            let record = record?;
+            profile.reads += 1;
+            let len = record.seq().len();
+            if len == 0 {
+                continue;
+            }
+            covered.fill(false);
+            for pattern in search_patterns {
+                for (start, end) in App::search(&record, pattern, molecule) {
+                    let end = end.min(len - 1);
+                    for bin in covered
+                        .iter_mut()
+                        .take(end * bins / len + 1)
+                        .skip(start * bins / len)
+                    {
+                        *bin = true;
+                    }
+                }
+            }
+            for (count, covered) in profile.matched.iter_mut().zip(&covered) {
+                *count += *covered as usize;
+            }
+        }
+        Ok(profile)
+    }
+
+    /// CSV with a header: bin_start, bin_end (percent of the read length), reads, fraction
+    pub fn write_csv<W: Write>(&self, writer: W) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["bin_start", "bin_end", "reads", "fraction"])?;
+        let bins = self.matched.len();
+        for (i, count) in self.matched.iter().enumerate() {
+            let fraction = if self.reads == 0 {
+                0.0
+            } else {
+                *count as f64 / self.reads as f64
+            };
+            writer.write_record([
+                format!("{:.1}", 100.0 * i as f64 / bins as f64),
+                format!("{:.1}", 100.0 * (i + 1) as f64 / bins as f64),
+                count.to_string(),
+                format!("{:.4}", fraction),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_match_profile() {
+    use ratatui::style::Color;
+    let record = |seq: &[u8]| {
+        Ok(fastq::Record::with_attrs(
+            "id",
+            None,
+            seq,
+            &vec![b'I'; seq.len()],
+        ))
+    };
+    let records = vec![
+        record(b"ACGTTTTTTTTTTTTTTTTT"),
+        record(b"TTTTTTTTTTACGTACGTTT"),
+        record(b"TTTTTTTTTT"),
+        record(b""),
+    ];
+    let patterns = vec![
+        SearchPattern::new(String::from("ACGT"), Color::Red, 0, ""),
+        SearchPattern::new(String::from("ACGTACGT"), Color::Blue, 0, ""),
+    ];
+    let profile =
+        MatchProfile::from_records(records.into_iter(), &patterns, 4, Molecule::Dna).unwrap();
+    // overlapping matches of both patterns count the second read once per bin
+    assert_eq!(
+        profile,
+        MatchProfile {
+            reads: 4,
+            matched: vec![1, 0, 1, 1]
+        }
+    );
+    let mut out: Vec<u8> = Vec::new();
+    profile.write_csv(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "bin_start,bin_end,reads,fraction\n\
+         0.0,25.0,1,0.2500\n\
+         25.0,50.0,0,0.0000\n\
+         50.0,75.0,1,0.2500\n\
+         75.0,100.0,1,0.2500\n"
+    );
+}
@@ -1,8 +1,10 @@
-use crate::app::{App, SearchPattern};
+use crate::app::{App, Molecule, SearchPattern};
 use bio::io::fastq;
+#[cfg(test)]
 use ratatui::style::Color;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::io::Write;
 
 #[derive(PartialEq, Eq, Clone, Hash)]
 pub enum ReadParts {
@@ -20,8 +22,96 @@ impl std::fmt::Display for ReadParts {
     }
 }
 
+/// Allowed number of occurrences of a pattern in a read
+/// parsed from `PATTERN=MIN..MAX` (either bound optional) or `PATTERN=N` (exactly N),
+/// where PATTERN is the search string or comment of a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccurrenceConstraint {
+    pub pattern: String,
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl std::str::FromStr for OccurrenceConstraint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            format!(
+                "Invalid occurrence constraint '{}', expected PATTERN=MIN..MAX or PATTERN=N",
+                s
+            )
+        };
+        let (pattern, range) = s.rsplit_once('=').ok_or_else(err)?;
+        let parse_bound = |x: &str| -> Result<Option<usize>, String> {
+            if x.is_empty() {
+                Ok(None)
+            } else {
+                x.parse::<usize>().map(Some).map_err(|_| err())
+            }
+        };
+        let (min, max) = match range.split_once("..") {
+            Some((min, max)) => (parse_bound(min)?.unwrap_or(0), parse_bound(max)?),
+            None => {
+                let n = parse_bound(range)?.ok_or_else(err)?;
+                (n, Some(n))
+            }
+        };
+        if pattern.is_empty() || max.is_some_and(|max| max < min) {
+            return Err(err());
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            min,
+            max,
+        })
+    }
+}
+
+impl OccurrenceConstraint {
+    /// Whether a categorised read satisfies the constraint
+    pub fn is_satisfied(&self, read_parts: &[ReadParts]) -> bool {
+        let count = read_parts
+            .iter()
+            .filter(|x| match x {
+                ReadParts::Match(p) => p.search_string == self.pattern || p.comment == self.pattern,
+                _ => false,
+            })
+            .count();
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+#[test]
+fn test_occurrence_constraint() {
+    let r1 = SearchPattern::new(String::from("ATCG"), Color::Red, 0, "R1");
+    let parts = vec![
+        ReadParts::Match(r1.clone()),
+        ReadParts::Space,
+        ReadParts::Match(r1.clone()),
+    ];
+    let exactly_two: OccurrenceConstraint = "R1=2".parse().unwrap();
+    assert_eq!(exactly_two.max, Some(2));
+    assert!(exactly_two.is_satisfied(&parts));
+    assert!(!exactly_two.is_satisfied(&parts[0..1]));
+    assert!("ATCG=2.."
+        .parse::<OccurrenceConstraint>()
+        .unwrap()
+        .is_satisfied(&parts));
+    assert!(!"R1=..1"
+        .parse::<OccurrenceConstraint>()
+        .unwrap()
+        .is_satisfied(&parts));
+    assert!("R1=3..1".parse::<OccurrenceConstraint>().is_err());
+    assert!("R1".parse::<OccurrenceConstraint>().is_err());
+}
+
 /// Categorise a read
-fn categorise_read(record: &fastq::Record, search_patterns: &[SearchPattern]) -> Vec<ReadParts> {
+fn categorise_read(
+    record: &fastq::Record,
+    search_patterns: &[SearchPattern],
+    molecule: Molecule,
+) -> Vec<ReadParts> {
     // merge overlapping intervals
     fn merge_overlap(mut intervals: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
         intervals.sort_by_key(|x| x.0);
@@ -44,7 +134,7 @@ fn categorise_read(record: &fastq::Record, search_patterns: &[SearchPattern]) ->
     // matched regions for each pattern as an IntervalSet
     let mut matches: Vec<VecDeque<(usize, usize)>> = search_patterns
         .iter()
-        .map(|x| VecDeque::from(merge_overlap(App::search(record, x))))
+        .map(|x| VecDeque::from(merge_overlap(App::search(record, x, molecule))))
         .collect();
 
     let mut ret: Vec<ReadParts> =
@@ -78,6 +168,7 @@ fn categorise_read(record: &fastq::Record, search_patterns: &[SearchPattern]) ->
 }
 
 #[test]
+#[allow(clippy::single_char_add_str)]
 fn test_categorise_read() {
     let fastq: fastq::Record = fastq::Record::with_attrs(
         "id",
@@ -90,7 +181,7 @@ fn test_categorise_read() {
         SearchPattern::new(String::from("GATC"), Color::Red, 0, ""),
     ];
     let mut result = String::new();
-    for i in categorise_read(&fastq, &patterns) {
+    for i in categorise_read(&fastq, &patterns, Molecule::Dna) {
         match i {
             ReadParts::Match(x) => result.push_str(x.search_string.as_str()),
             ReadParts::Space => result.push_str(".."),
@@ -103,17 +194,230 @@ fn test_categorise_read() {
     );
 }
 
+/// Whether the read parts form the combination as printed by `summarize`, e.g.
+/// `ATCG..GATC`, or with pattern comments in place of search strings, e.g. `R1..TSO`
+fn is_combination(read_parts: &[ReadParts], combination: &str) -> bool {
+    let by_search_string: String = read_parts.iter().map(|x| x.to_string()).collect();
+    let by_comment: String = read_parts
+        .iter()
+        .map(|x| match x {
+            ReadParts::Match(p) if !p.comment.is_empty() => p.comment.clone(),
+            _ => x.to_string(),
+        })
+        .collect();
+    by_search_string == combination || by_comment == combination
+}
+
+/// Write the reads of one pattern combination (see `is_combination`) as FASTQ, returns the
+/// number of reads written
+pub fn write_combination_reads<W: Write>(
+    records: impl Iterator<Item = Result<fastq::Record, fastq::Error>>,
+    search_patterns: &[SearchPattern],
+    combination: &str,
+    writer: W,
+    molecule: Molecule,
+) -> anyhow::Result<usize> {
+    let mut fastq_writer = fastq::Writer::new(writer);
+    let mut written = 0;
+    for record in records {
+        let record = record?;
+        if is_combination(
+            &categorise_read(&record, search_patterns, molecule),
+            combination,
+        ) {
+            fastq_writer.write_record(&record)?;
+            written += 1;
+        }
+    }
+    fastq_writer.flush()?;
+    Ok(written)
+}
+
+#[test]
+fn test_write_combination_reads() {
+    let records = [
+        fastq::Record::with_attrs(
+            "fixture",
+            None,
+            b"ATCGCCATCGCCATCGCCATCGATCAAATCGGATC",
+            b"!!!!!!!!!!!!!!!!!!!!!!",
+        ),
+        fastq::Record::with_attrs("other", None, b"CCATCGCC", b"IIIIIIII"),
+    ];
+    let patterns = vec![
+        SearchPattern::new(String::from("ATCG"), Color::Red, 0, "R1"),
+        SearchPattern::new(String::from("GATC"), Color::Red, 0, ""),
+    ];
+    let filter = |combination: &str| {
+        let mut out: Vec<u8> = Vec::new();
+        let records = records.iter().cloned().map(Ok);
+        let n = write_combination_reads(records, &patterns, combination, &mut out, Molecule::Dna)
+            .unwrap();
+        (n, String::from_utf8(out).unwrap())
+    };
+    let (n, out) = filter("ATCG..ATCG..ATCG..ATCG-GATC..ATCGGATC");
+    assert_eq!(n, 1);
+    assert!(out.starts_with("@fixture\nATCGCC"));
+    // comments in place of search strings
+    assert_eq!(filter("..R1..").0, 1);
+    assert_eq!(filter("..ATCG..").1, "@other\nCCATCGCC\n+\nIIIIIIII\n");
+    assert_eq!(filter("GATC").0, 0);
+}
+
+/// How to reorder reads in the viewer, parsed from `combination` or a pattern (search string
+/// or comment)
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupBy {
+    /// group reads by the combination of patterns they match, largest group first
+    Combination,
+    /// reads matching the pattern first
+    Pattern(String),
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err(String::from("Expected combination or a pattern")),
+            "combination" => Ok(GroupBy::Combination),
+            _ => Ok(GroupBy::Pattern(s.to_string())),
+        }
+    }
+}
+
+/// Record indexes in the order given by `group_by`, file order within each group. The reads
+/// are numbered as they come, so they should be read as the viewer reads them, see
+/// `FastqReader::into_records`
+pub fn group_records(
+    reads: impl Iterator<Item = Result<fastq::Record, std::io::Error>>,
+    search_patterns: &[SearchPattern],
+    group_by: &GroupBy,
+    molecule: Molecule,
+) -> Result<Vec<usize>, String> {
+    let pattern = match group_by {
+        GroupBy::Combination => None,
+        GroupBy::Pattern(name) => Some(
+            search_patterns
+                .iter()
+                .find(|x| x.search_string == *name || x.comment == *name)
+                .ok_or_else(|| format!("No pattern {} to group reads by", name))?,
+        ),
+    };
+    // groups in order of first occurrence
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: HashMap<Vec<ReadParts>, usize> = HashMap::new();
+    let (mut matching, mut rest): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+    for (index, read) in reads.enumerate() {
+        let read = read.map_err(|e| format!("Invalid FASTQ record {}: {}", index + 1, e))?;
+        match pattern {
+            Some(pattern) => {
+                if App::search(&read, pattern, molecule).is_empty() {
+                    rest.push(index);
+                } else {
+                    matching.push(index);
+                }
+            }
+            None => {
+                let group = *group_of
+                    .entry(categorise_read(&read, search_patterns, molecule))
+                    .or_insert_with(|| {
+                        groups.push(Vec::new());
+                        groups.len() - 1
+                    });
+                groups[group].push(index);
+            }
+        }
+    }
+    if pattern.is_some() {
+        matching.extend(rest);
+        return Ok(matching);
+    }
+    // stable, ties keep the order of first occurrence
+    groups.sort_by_key(|x| std::cmp::Reverse(x.len()));
+    Ok(groups.into_iter().flatten().collect())
+}
+
+#[test]
+fn test_group_records() {
+    let reads: Vec<fastq::Record> = ["ATCGCC", "CCCCCC", "ATCGGA", "CCATCG", "GGGGGG"]
+        .iter()
+        .enumerate()
+        .map(|(i, seq)| fastq::Record::with_attrs(&i.to_string(), None, seq.as_bytes(), b"IIIIII"))
+        .collect();
+    let patterns = vec![SearchPattern::new(
+        String::from("ATCG"),
+        Color::Red,
+        0,
+        "motif",
+    )];
+    let group = |group_by: GroupBy| {
+        group_records(
+            reads.iter().cloned().map(Ok),
+            &patterns,
+            &group_by,
+            Molecule::Dna,
+        )
+        .unwrap()
+    };
+    assert_eq!(group("motif".parse().unwrap()), vec![0, 2, 3, 1, 4]);
+    // ATCG.. (2 reads) before .. (2 reads) before ..ATCG
+    assert_eq!(group(GroupBy::Combination), vec![0, 2, 1, 4, 3]);
+    let unknown: GroupBy = "TSO".parse().unwrap();
+    assert!(group_records(
+        reads.into_iter().map(Ok),
+        &patterns,
+        &unknown,
+        Molecule::Dna
+    )
+    .is_err());
+}
+
+#[test]
+fn test_group_records_malformed() {
+    use crate::io::fastq::FastqReader;
+    use crate::test_utils::TempFileGuard;
+    // the malformed record is skipped, so the viewer shows ATCGGA at index 1
+    let file = TempFileGuard::with_content(
+        "fastq",
+        "@a\nCCCCCC\n+\nIIIIII\n@bad\nATCG\n+\nII\n@b\nATCGGA\n+\nIIIIII\n@c\nGGGG\n+\nIIII\n",
+    );
+    let patterns = vec![SearchPattern::new(
+        String::from("ATCG"),
+        Color::Red,
+        0,
+        "motif",
+    )];
+    let reads = FastqReader::new(std::fs::File::open(&file).unwrap())
+        .unwrap()
+        .into_records()
+        .unwrap();
+    let order = group_records(
+        reads,
+        &patterns,
+        &GroupBy::Pattern("motif".into()),
+        Molecule::Dna,
+    );
+    assert_eq!(order.unwrap(), vec![1, 0, 2]);
+    let mut reader = FastqReader::new(std::fs::File::open(&file).unwrap()).unwrap();
+    assert_eq!(reader.get_index(1).unwrap().unwrap().id(), "b");
+}
+
 /// Catagories reads and reutrn counts for each category
-pub fn summarise_reads(
-    reads: &[fastq::Record],
+/// streams the reads once, only the counts of each category are kept in memory
+pub fn summarise_reads<E>(
+    reads: impl Iterator<Item = Result<fastq::Record, E>>,
     search_patterns: &[SearchPattern],
-    as_counts: bool
-) -> Vec<(Vec<ReadParts>, usize)> {
+    as_counts: bool,
+    molecule: Molecule,
+) -> Result<Vec<(Vec<ReadParts>, usize)>, E> {
     let mut map: HashMap<Vec<ReadParts>, usize> = HashMap::new();
+    let mut reads_total: usize = 0;
     for read in reads {
-        let read_parts = categorise_read(read, search_patterns);
+        let read_parts = categorise_read(&read?, search_patterns, molecule);
         let count = map.entry(read_parts).or_insert(0);
         *count += 1;
+        reads_total += 1;
     }
 
     // sort by count and return
@@ -121,26 +425,57 @@ pub fn summarise_reads(
     ret.sort_by_key(|x| x.1);
     // into percentage
     if !as_counts {
-        let total: f64 = reads.len() as f64 / 100.0;
+        let total: f64 = reads_total as f64 / 100.0;
         ret.iter_mut().for_each(|(_, count)| *count = (*count as f64 / total).round() as usize);
     }
-    ret
+    Ok(ret)
+}
+
+#[test]
+fn test_summarise_reads() {
+    let reads: Vec<fastq::Record> = ["ATCGCC", "CCCCCC", "ATCGGA", "CCATCG"]
+        .iter()
+        .map(|seq| fastq::Record::with_attrs("id", None, seq.as_bytes(), b"IIIIII"))
+        .collect();
+    let patterns = vec![SearchPattern::new(
+        String::from("ATCG"),
+        Color::Red,
+        0,
+        "motif",
+    )];
+    let reads = reads.into_iter().map(Ok::<_, fastq::Error>);
+    let summary = summarise_reads(reads, &patterns, false, Molecule::Dna).unwrap();
+    let percentages: Vec<usize> = summary.iter().map(|x| x.1).collect();
+    assert_eq!(percentages, vec![25, 25, 50]);
 }
 
 /// format summrised catagories
-pub fn fmt_summarised_reads(summarised_reads: &[(Vec<ReadParts>, usize)], as_counts: bool) -> String {
+/// with a valid_structure column if any occurrence constraints are given
+pub fn fmt_summarised_reads(
+    summarised_reads: &[(Vec<ReadParts>, usize)],
+    as_counts: bool,
+    constraints: &[OccurrenceConstraint],
+) -> String {
     let mut ret = String::new();
     for (read_parts, count) in summarised_reads {
         ret.push_str(
             format!(
-                "{}{}\t{}\n",
+                "{}{}\t{}{}\n",
                 count,
                 if as_counts { "" } else { "%" },
                 read_parts
                     .iter()
                     .map(|x| x.to_string())
                     .collect::<Vec<String>>()
-                    .join("")
+                    .join(""),
+                if constraints.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        "\t{}",
+                        constraints.iter().all(|x| x.is_satisfied(read_parts))
+                    )
+                }
             )
             .as_str(),
         );
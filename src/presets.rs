@@ -0,0 +1,131 @@
+use crate::app::SearchPattern;
+use ratatui::prelude::Color;
+
+/// Colors assigned in turn to patterns without one, e.g. preset adaptors or patterns from
+/// FASTA files, replaced by `palette` in the config file
+pub const DEFAULT_PALETTE: [Color; 6] = [
+    Color::Blue,
+    Color::Green,
+    Color::Red,
+    Color::Yellow,
+    Color::Cyan,
+    Color::Magenta,
+];
+
+/// Poly-A/T patterns added to every preset, in a neutral color so the adaptors stand out
+const POLY_TAILS: &[(&str, u8, &str)] = &[("TTTTTTTTTTTT", 1, ""), ("AAAAAAAAAAAA", 1, "")];
+const POLY_TAIL_COLOR: Color = Color::Gray;
+
+/// Adaptor sets of sequencing kits, selected with the `--adapter-*` flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    TenX3p,
+    TenX5p,
+    Ont,
+    PacBio,
+}
+
+impl Preset {
+    /// (search string, edit distance, comment) of the adaptors, colored in turn from the palette
+    fn adaptors(self) -> &'static [(&'static str, u8, &'static str)] {
+        match self {
+            Preset::TenX3p => &[
+                ("CTACACGACGCTCTTCCGATCT", 3, "R1"),
+                ("AGATCGGAAGAGCGTCGTGTAG", 3, "TSO"),
+                ("TGGTATCAACGCAGAGTACATGGG", 3, "R1 rev"),
+                ("CCCATGTACTCTGCGTTGATACCA", 3, "TSO rev"),
+            ],
+            Preset::TenX5p => &[
+                ("CTACACGACGCTCTTCCGATCT", 3, "R1"),
+                ("TTTCTTATATGGG", 2, "TSO"),
+                ("TGGTATCAACGCAGAGTACATGGG", 3, "R1 rev"),
+                ("CCCATATAAGAAA", 2, "TSO rev"),
+                ("AGATCGGAAGAGCACACGTCTGAA", 3, "R2"),
+                ("TTCAGACGTGTGCTCTTCCGATCT", 3, "R2 rev"),
+            ],
+            Preset::Ont => &[
+                ("AATGTACTTCGTTCAGTTACGTATTGCT", 4, "ONT Y top"),
+                ("AGCAATACGTAACTGAACGAAGTACATT", 4, "ONT Y top rev"),
+                ("GCAATACGTAACTGAACGAAGT", 3, "ONT Y bottom"),
+                ("ACTTCGTTCAGTTACGTATTGC", 3, "ONT Y bottom rev"),
+            ],
+            Preset::PacBio => &[
+                (
+                    "ATCTCTCTCAACAACAACAACGGAGGAGGAGGAAAAGAGAGAGAT",
+                    5,
+                    "SMRTbell",
+                ),
+                (
+                    "ATCTCTCTCTTTTCCTCCTCCTCCGTTGTTGTTGTTGAGAGAGAT",
+                    5,
+                    "SMRTbell rev",
+                ),
+            ],
+        }
+    }
+
+    /// Patterns of the adaptors followed by the poly-A/T tails
+    pub fn patterns(self, palette: &[Color]) -> Vec<SearchPattern> {
+        let adaptors = self
+            .adaptors()
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x, palette[i % palette.len()]));
+        let tails = POLY_TAILS.iter().map(|x| (x, POLY_TAIL_COLOR));
+        adaptors
+            .chain(tails)
+            .map(|((search_string, edit_distance, comment), color)| {
+                SearchPattern::new(search_string.to_string(), color, *edit_distance, comment)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_presets() {
+    let summary = |preset: Preset| -> Vec<(String, Color)> {
+        preset
+            .patterns(&DEFAULT_PALETTE)
+            .into_iter()
+            .map(|x| (x.search_string, x.color))
+            .collect()
+    };
+    // sequences listed in the --adapter-3p / --adapter-5p help
+    let tenx_3p = summary(Preset::TenX3p);
+    assert_eq!(tenx_3p[0].0, "CTACACGACGCTCTTCCGATCT");
+    assert_eq!(tenx_3p[1].0, "AGATCGGAAGAGCGTCGTGTAG");
+    let tenx_5p = summary(Preset::TenX5p);
+    assert_eq!(tenx_5p[1].0, "TTTCTTATATGGG");
+    assert_eq!(tenx_5p[4].0, "AGATCGGAAGAGCACACGTCTGAA");
+    // long read adaptors come with their reverse complements
+    for preset in [Preset::Ont, Preset::PacBio] {
+        let adaptors = preset.adaptors();
+        for (x, _, _) in adaptors {
+            let revcomp = bio::alphabets::dna::revcomp(x.as_bytes());
+            assert!(
+                adaptors.iter().any(|(y, _, _)| y.as_bytes() == revcomp),
+                "{}",
+                x
+            );
+        }
+    }
+    assert_eq!(summary(Preset::Ont)[0].0, "AATGTACTTCGTTCAGTTACGTATTGCT");
+    let all = [Preset::TenX3p, Preset::TenX5p, Preset::Ont, Preset::PacBio].map(summary);
+    for preset in all {
+        let adaptors = &preset[..preset.len() - POLY_TAILS.len()];
+        let mut colors: Vec<String> = adaptors
+            .iter()
+            .map(|(_, color)| color.to_string())
+            .collect();
+        colors.sort();
+        colors.dedup();
+        assert_eq!(colors.len(), adaptors.len());
+        assert!(preset[adaptors.len()..]
+            .iter()
+            .all(|(x, color)| x.len() == 12 && *color == POLY_TAIL_COLOR));
+    }
+    // a custom palette is used in turn
+    let patterns = Preset::TenX3p.patterns(&[Color::White, Color::Black]);
+    assert_eq!(patterns[2].color, Color::White);
+    assert_eq!(patterns[3].color, Color::Black);
+}
@@ -0,0 +1,164 @@
+use crate::app::{revcomp, App, Molecule, SearchPattern};
+use bio::alignment::AlignmentOperation;
+use bio::io::{fasta, fastq};
+use std::io::Write;
+
+/// A site of a reference the primer (or its reverse complement) could bind
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingSite {
+    pub strand: char,
+    pub start: usize, // 0-based on the forward strand
+    pub end: usize,   // exclusive
+    pub edit_distance: usize,
+    /// edits in primer coordinates (1-based from the 5' end), bases as seen by the primer:
+    /// `5A>G` substitution, `5A>-` primer base without a site base, `5^T` extra site base
+    /// after primer base 5
+    pub edits: Vec<String>,
+    pub site: String, // bound strand, 5' to 3' like the primer
+}
+
+/// Edits of an alignment of `primer` to `site` (both 5' to 3'), see `BindingSite::edits`
+fn primer_edits(primer: &[u8], site: &[u8], ops: &[AlignmentOperation]) -> Vec<String> {
+    let (mut p, mut s) = (0, 0);
+    let mut edits = Vec::new();
+    for op in ops {
+        match op {
+            AlignmentOperation::Match => {
+                p += 1;
+                s += 1;
+            }
+            AlignmentOperation::Subst => {
+                edits.push(format!(
+                    "{}{}>{}",
+                    p + 1,
+                    primer[p] as char,
+                    site[s] as char
+                ));
+                p += 1;
+                s += 1;
+            }
+            AlignmentOperation::Del => {
+                edits.push(format!("{}^{}", p, site[s] as char));
+                s += 1;
+            }
+            AlignmentOperation::Ins => {
+                edits.push(format!("{}{}>-", p + 1, primer[p] as char));
+                p += 1;
+            }
+            _ => {}
+        }
+    }
+    edits
+}
+
+/// Binding sites of the primer on both strands of the sequence, up to the primer's edit
+/// distance, overlapping candidates reduced to the best one per strand
+pub fn find_binding_sites(
+    seq: &[u8],
+    primer: &SearchPattern,
+    molecule: Molecule,
+) -> Vec<BindingSite> {
+    let record = fastq::Record::with_attrs("", None, seq, &[]);
+    let primer_seq = primer.search_string.as_bytes();
+    let mut sites = Vec::new();
+    for strand in ['+', '-'] {
+        let pattern = match strand {
+            '+' => primer.clone(),
+            _ => SearchPattern {
+                search_string: String::from_utf8_lossy(&revcomp(primer_seq, molecule)).to_string(),
+                ..primer.clone()
+            },
+        };
+        let hits = App::best_hits(App::search_with_alignment(&record, &pattern, molecule));
+        for (start, end, dist, ops) in hits {
+            let forward = &seq[start..=end];
+            let (site, ops) = match strand {
+                '+' => (forward.to_vec(), ops),
+                // read the other strand 5' to 3', i.e. the alignment backwards
+                _ => (revcomp(forward, molecule), ops.into_iter().rev().collect()),
+            };
+            sites.push(BindingSite {
+                strand,
+                start,
+                end: end + 1,
+                edit_distance: dist,
+                edits: primer_edits(primer_seq, &site, &ops),
+                site: String::from_utf8_lossy(&site).to_string(),
+            });
+        }
+    }
+    sites.sort_by_key(|x| (x.start, x.strand));
+    sites
+}
+
+/// Write the binding sites of the primer in each reference record as TSV with a header:
+/// record_id, strand, start, end (0-based, end exclusive), edit_distance, edits, site
+/// Returns the number of sites at each edit distance
+pub fn write_binding_sites<W: Write>(
+    records: impl Iterator<Item = Result<fasta::Record, std::io::Error>>,
+    primer: &SearchPattern,
+    writer: W,
+    molecule: Molecule,
+) -> anyhow::Result<Vec<usize>> {
+    let mut counts = vec![0; primer.edit_distance as usize + 1];
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(writer);
+    writer.write_record([
+        "record_id",
+        "strand",
+        "start",
+        "end",
+        "edit_distance",
+        "edits",
+        "site",
+    ])?;
+    for record in records {
+        let record = record?;
+        for site in find_binding_sites(record.seq(), primer, molecule) {
+            let last = counts.len() - 1;
+            counts[site.edit_distance.min(last)] += 1;
+            writer.write_record([
+                record.id(),
+                &site.strand.to_string(),
+                &site.start.to_string(),
+                &site.end.to_string(),
+                &site.edit_distance.to_string(),
+                &site.edits.join(","),
+                &site.site,
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(counts)
+}
+
+#[test]
+fn test_find_binding_sites() {
+    use ratatui::style::Color;
+    let primer = SearchPattern::new(String::from("ACGGTCAA"), Color::Red, 1, "");
+    // exact on the forward strand, one substitution on the reverse strand
+    let seq = b"TTACGGTCAATTTTTTGACCTTTT";
+    let sites = find_binding_sites(seq, &primer, Molecule::Dna);
+    assert_eq!(
+        sites,
+        vec![
+            BindingSite {
+                strand: '+',
+                start: 2,
+                end: 10,
+                edit_distance: 0,
+                edits: Vec::new(),
+                site: String::from("ACGGTCAA"),
+            },
+            BindingSite {
+                strand: '-',
+                start: 14,
+                end: 22,
+                edit_distance: 1,
+                edits: vec![String::from("2C>A")],
+                site: String::from("AAGGTCAA"),
+            },
+        ]
+    );
+}
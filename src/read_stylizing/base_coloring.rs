@@ -0,0 +1,89 @@
+use interval::interval_set::{IntervalSet, ToIntervalSet};
+use ratatui::prelude::Color;
+
+/// Colors of the bases when coloring by identity; other symbols stay uncolored
+const BASE_COLORS: [(&[u8], Color); 5] = [
+    (b"A", Color::Green),
+    (b"C", Color::Blue),
+    (b"G", Color::Yellow),
+    (b"TU", Color::Red),
+    (b"N", Color::DarkGray),
+];
+
+/// Colors of the amino acids by property, after the Clustal scheme
+const AMINO_ACID_COLORS: [(&[u8], Color); 8] = [
+    (b"AILMFWV", Color::Blue), // hydrophobic
+    (b"KR", Color::Red),       // positive
+    (b"DE", Color::Magenta),   // negative
+    (b"NQST", Color::Green),   // polar
+    (b"C", Color::LightRed),
+    (b"G", Color::LightYellow),
+    (b"P", Color::Yellow),
+    (b"HY", Color::Cyan), // aromatic
+];
+
+/// Read positions of each base (amino acid if `protein`) of `seq`, the part of a read starting
+/// at `offset`, by color. Case-insensitive, runs of a symbol (homopolymers) as single intervals
+pub fn base_color_intervals(
+    seq: &[u8],
+    offset: usize,
+    protein: bool,
+) -> Vec<(IntervalSet<usize>, Color)> {
+    let colors: &[(&[u8], Color)] = if protein {
+        &AMINO_ACID_COLORS
+    } else {
+        &BASE_COLORS
+    };
+    let mut runs: Vec<Vec<(usize, usize)>> = vec![Vec::new(); colors.len()];
+    let mut start = 0;
+    for (i, base) in seq.iter().enumerate() {
+        let run_ends = seq
+            .get(i + 1)
+            .is_none_or(|next| !next.eq_ignore_ascii_case(base));
+        if !run_ends {
+            continue;
+        }
+        let base = base.to_ascii_uppercase();
+        if let Some(index) = colors
+            .iter()
+            .position(|(symbols, _)| symbols.contains(&base))
+        {
+            runs[index].push((offset + start, offset + i));
+        }
+        start = i + 1;
+    }
+    runs.into_iter()
+        .zip(colors)
+        .filter(|(runs, _)| !runs.is_empty())
+        .map(|(runs, (_, color))| (runs.to_interval_set(), *color))
+        .collect()
+}
+
+#[test]
+fn test_base_color_intervals() {
+    let intervals = base_color_intervals(b"AAacGGGtUnX", 0, false);
+    assert_eq!(
+        intervals,
+        vec![
+            (vec![(0, 2)].to_interval_set(), Color::Green),
+            (vec![(3, 3)].to_interval_set(), Color::Blue),
+            (vec![(4, 6)].to_interval_set(), Color::Yellow),
+            (vec![(7, 7), (8, 8)].to_interval_set(), Color::Red),
+            (vec![(9, 9)].to_interval_set(), Color::DarkGray),
+        ]
+    );
+    assert_eq!(
+        base_color_intervals(b"CC", 10, false),
+        vec![(vec![(10, 11)].to_interval_set(), Color::Blue)]
+    );
+    assert!(base_color_intervals(b"", 0, false).is_empty());
+    // amino acids colored by property, N polar rather than unknown
+    assert_eq!(
+        base_color_intervals(b"MKkNX", 0, true),
+        vec![
+            (vec![(0, 0)].to_interval_set(), Color::Blue),
+            (vec![(1, 2)].to_interval_set(), Color::Red),
+            (vec![(3, 3)].to_interval_set(), Color::Green),
+        ]
+    );
+}
@@ -0,0 +1,169 @@
+use ratatui::prelude::Color;
+
+/// Approximate RGB values of the 16 ANSI colors (xterm defaults)
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// RGB value of a color, None for the terminal's default colors
+pub fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    let ansi = |i: usize| Some(ANSI_RGB[i]);
+    match color {
+        Color::Reset => None,
+        Color::Black => ansi(0),
+        Color::Red => ansi(1),
+        Color::Green => ansi(2),
+        Color::Yellow => ansi(3),
+        Color::Blue => ansi(4),
+        Color::Magenta => ansi(5),
+        Color::Cyan => ansi(6),
+        Color::Gray => ansi(7),
+        Color::DarkGray => ansi(8),
+        Color::LightRed => ansi(9),
+        Color::LightGreen => ansi(10),
+        Color::LightYellow => ansi(11),
+        Color::LightBlue => ansi(12),
+        Color::LightMagenta => ansi(13),
+        Color::LightCyan => ansi(14),
+        Color::White => ansi(15),
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i @ 0..=15) => ansi(i as usize),
+        Color::Indexed(i @ 16..=231) => {
+            // 6x6x6 color cube
+            let level = |x: u8| if x == 0 { 0 } else { 55 + 40 * x };
+            let i = i - 16;
+            Some((level(i / 36), level(i / 6 % 6), level(i % 6)))
+        }
+        Color::Indexed(i) => {
+            let gray = 8 + 10 * (i - 232);
+            Some((gray, gray, gray))
+        }
+    }
+}
+
+/// The 16 ANSI colors in the order of `ANSI_RGB`
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Whether a terminal with `colors` colors needs colors approximated by `quantize_color`,
+/// i.e. lacks truecolor
+pub fn approximates_colors(colors: u16) -> bool {
+    colors <= 256
+}
+
+/// Nearest color a terminal with `colors` colors can show: one of the 16 ANSI colors, or of
+/// the first 8 on terminals with fewer than 16. On 256-color terminals RGB colors are mapped to
+/// the 256-color palette, truecolor terminals (more than 256 colors) show any color
+pub fn quantize_color(color: Color, colors: u16) -> Color {
+    let palette = if !approximates_colors(colors) {
+        return color;
+    } else if colors == 256 {
+        return match color {
+            Color::Rgb(r, g, b) => Color::Indexed(nearest_256_index(r, g, b)),
+            _ => color,
+        };
+    } else if colors >= 16 {
+        &ANSI_COLORS[..]
+    } else {
+        &ANSI_COLORS[..8]
+    };
+    if palette.contains(&color) {
+        return color;
+    }
+    let Some((r, g, b)) = to_rgb(color) else {
+        return color;
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    palette
+        .iter()
+        .zip(ANSI_RGB)
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(color, |(ansi, _)| *ansi)
+}
+
+/// Index of the nearest color of the 6x6x6 cube (16-231) or the gray ramp (232-255) of the
+/// 256-color palette, leaving out the 16 ANSI colors whose RGB values depend on the terminal
+fn nearest_256_index(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    // channels are independent in the cube, so the nearest level of each
+    let level = |x: u8| {
+        (0..6u8)
+            .min_by_key(|i| (if *i == 0 { 0 } else { 55 + 40 * *i as i32 } - x as i32).abs())
+            .unwrap_or(0)
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let mean = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + ((mean.saturating_sub(3) / 10).min(23)) as u8;
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|i| to_rgb(Color::Indexed(*i)).map_or(i32::MAX, distance))
+        .unwrap_or(cube)
+}
+
+#[test]
+fn test_quantize_color() {
+    assert_eq!(
+        quantize_color(Color::Rgb(250, 10, 10), u16::MAX),
+        Color::Rgb(250, 10, 10)
+    );
+    assert!(approximates_colors(256) && approximates_colors(8) && !approximates_colors(u16::MAX));
+    // #1f77b4 to the cube color (0, 135, 175), grays to the gray ramp
+    assert_eq!(
+        quantize_color(Color::Rgb(0x1f, 0x77, 0xb4), 256),
+        Color::Indexed(31)
+    );
+    assert_eq!(
+        quantize_color(Color::Rgb(120, 121, 119), 256),
+        Color::Indexed(243)
+    );
+    assert_eq!(
+        quantize_color(Color::Rgb(255, 0, 0), 256),
+        Color::Indexed(196)
+    );
+    assert_eq!(quantize_color(Color::LightRed, 256), Color::LightRed);
+    assert_eq!(quantize_color(Color::Rgb(250, 10, 10), 16), Color::LightRed);
+    assert_eq!(quantize_color(Color::Rgb(250, 10, 10), 8), Color::Red);
+    assert_eq!(quantize_color(Color::Indexed(21), 16), Color::Blue);
+    assert_eq!(quantize_color(Color::LightCyan, 16), Color::LightCyan);
+    assert_eq!(quantize_color(Color::LightCyan, 8), Color::Cyan);
+    assert_eq!(quantize_color(Color::Reset, 8), Color::Reset);
+    assert_eq!(to_rgb(Color::Indexed(196)), Some((255, 0, 0)));
+    assert_eq!(to_rgb(Color::Indexed(232)), Some((8, 8, 8)));
+}
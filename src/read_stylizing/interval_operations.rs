@@ -1,6 +1,5 @@
-use gcollections::ops::set::{Intersection, Union};
-#[allow(unused_imports)]
-use gcollections::ops::{Empty, Difference};
+use gcollections::ops::set::{Difference, Intersection, Union};
+use gcollections::ops::Empty;
 #[allow(unused_imports)]
 use interval::interval_set::{IntervalSet, ToIntervalSet};
 use interval::ops::Width;
@@ -16,6 +15,26 @@ pub fn find_intersections<Bound: Width + num_traits::Num>(
         })
 }
 
+/// Parts of `full` not covered by any of the sets
+pub fn subtract_intervals<Bound: Width + num_traits::Num>(
+    full: &IntervalSet<Bound>,
+    sets: &[IntervalSet<Bound>],
+) -> IntervalSet<Bound> {
+    sets.iter()
+        .fold(full.clone(), |acc, set| acc.difference(set))
+}
+
+#[test]
+fn test_subtract_intervals() {
+    let full: IntervalSet<usize> = vec![(0, 20)].to_interval_set();
+    let a: IntervalSet<usize> = vec![(0, 2), (4, 7)].to_interval_set();
+    let b: IntervalSet<usize> = vec![(6, 9), (18, 30)].to_interval_set();
+    assert_eq!(
+        subtract_intervals(&full, &[a, b]),
+        vec![(3, 3), (10, 17)].to_interval_set()
+    );
+}
+
 #[test]
 fn test_find_intersections() {
     let a: IntervalSet<usize> = vec![(1, 2), (4, 7), (9, 10)].to_interval_set();
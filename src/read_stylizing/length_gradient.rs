@@ -0,0 +1,45 @@
+use ratatui::prelude::Color;
+
+/// Color of a read length on a blue (short) to red (long) gradient between `min` and `max`
+pub fn length_color(len: usize, min: usize, max: usize) -> Color {
+    let fraction = if max > min {
+        (len.clamp(min, max) - min) as f64 / (max - min) as f64
+    } else {
+        0.5
+    };
+    // hue from 240 (blue) through 120 (green) to 0 (red), full saturation and value
+    let hue = 240.0 * (1.0 - fraction);
+    let x = 1.0 - ((hue / 60.0) % 2.0 - 1.0).abs();
+    let (r, g, b) = match (hue / 60.0) as u8 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        _ => (0.0, x, 1.0),
+    };
+    let scale = |c: f64| (c * 255.0).round() as u8;
+    Color::Rgb(scale(r), scale(g), scale(b))
+}
+
+/// Parse a `MIN..MAX` length scale
+pub fn parse_length_scale(s: &str) -> Result<(usize, usize), String> {
+    let (min, max) = s
+        .split_once("..")
+        .ok_or_else(|| format!("Expected MIN..MAX, got {}", s))?;
+    let parse = |x: &str| x.parse::<usize>().map_err(|e| format!("{}: {}", x, e));
+    let (min, max) = (parse(min)?, parse(max)?);
+    if min >= max {
+        return Err(format!("MIN must be smaller than MAX in {}", s));
+    }
+    Ok((min, max))
+}
+
+#[test]
+fn test_length_color() {
+    assert_eq!(length_color(100, 100, 200), Color::Rgb(0, 0, 255));
+    assert_eq!(length_color(150, 100, 200), Color::Rgb(0, 255, 0));
+    assert_eq!(length_color(200, 100, 200), Color::Rgb(255, 0, 0));
+    // out of scale lengths are clamped
+    assert_eq!(length_color(1000, 100, 200), Color::Rgb(255, 0, 0));
+    assert_eq!(parse_length_scale("100..200"), Ok((100, 200)));
+    assert!(parse_length_scale("200..100").is_err());
+}
@@ -0,0 +1,40 @@
+use super::color_depth::to_rgb;
+use ratatui::prelude::Color;
+
+/// Brightness of a match at the maximum edit distance, relative to an exact match
+const MIN_INTENSITY: f64 = 0.4;
+
+/// Scale the brightness of a color, `intensity` from 0 (black) to 1 (unchanged)
+pub fn darken(color: Color, intensity: f64) -> Color {
+    match to_rgb(color) {
+        Some((r, g, b)) => {
+            let scale = |c: u8| (c as f64 * intensity.clamp(0.0, 1.0)).round() as u8;
+            Color::Rgb(scale(r), scale(g), scale(b))
+        }
+        None => color,
+    }
+}
+
+/// Color of a match with edit distance `dist` out of the pattern's `max_dist`: exact matches
+/// in the full pattern color, down to `MIN_INTENSITY` at the maximum edit distance
+pub fn confidence_color(color: Color, dist: usize, max_dist: usize) -> Color {
+    if dist == 0 || max_dist == 0 {
+        return color;
+    }
+    let fraction = dist.min(max_dist) as f64 / max_dist as f64;
+    darken(color, 1.0 - (1.0 - MIN_INTENSITY) * fraction)
+}
+
+#[test]
+fn test_confidence_color() {
+    assert_eq!(confidence_color(Color::Red, 0, 3), Color::Red);
+    assert_eq!(
+        confidence_color(Color::Rgb(200, 100, 0), 2, 2),
+        Color::Rgb(80, 40, 0)
+    );
+    assert_eq!(
+        confidence_color(Color::Rgb(200, 100, 0), 1, 2),
+        Color::Rgb(140, 70, 0)
+    );
+    assert_eq!(confidence_color(Color::Reset, 1, 2), Color::Reset);
+}
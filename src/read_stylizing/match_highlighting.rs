@@ -1,7 +1,8 @@
-use ratatui::prelude::{Color, Line, Span, Style};
+use ratatui::prelude::{Color, Line, Modifier, Span, Style};
 
 use crate::read_stylizing::interval_operations::find_intersections;
 use gcollections::ops::Bounded;
+use interval::interval_set::ToIntervalSet;
 use interval::ops::Width;
 use interval::IntervalSet;
 
@@ -27,47 +28,199 @@ fn format_overlap<Bound: Width + num_traits::Num, Meta: Copy>(
     result
 }
 
-pub fn highlight_matches<'a, T: Width + num_traits::PrimInt>(
-    intervals: &Vec<(IntervalSet<T>, Color)>,
-    input_string: String,
-    overlap_color: Color,
-) -> Line<'a>
-where
-    T: Into<usize>,
-{
-    let intervals: Vec<(IntervalSet<T>, Color)> = format_overlap(intervals, overlap_color);
-    let mut intervals: Vec<(usize, usize, Color)> = intervals
-        .into_iter()
-        .flat_map(|(set, color)| {
-            set.into_iter().map(move |interval| {
-                (
-                    interval.lower().into(),
-                    interval.upper().into(),
-                    color,
-                )
-            })
-        })
-        .collect();
-    intervals.sort_by_key(|&(start, _, _)| start);
-    let mut result: Vec<Span> = Vec::new();
-    let mut current_index: usize = 0;
+/// Styling layers of a single read, combined position by position
+#[derive(Debug, Clone)]
+pub struct StyleInput {
+    /// matched intervals and the color of the pattern that matched
+    pub fg_color_intervals: Vec<(IntervalSet<usize>, Color)>,
+    /// color for positions matched by more than one pattern
+    pub overlap_color: Color,
+    /// modifier of the matched positions besides their color, e.g. reversed
+    pub match_modifier: Modifier,
+    /// colors of the bases by identity, under the pattern colors
+    pub base_color_intervals: Vec<(IntervalSet<usize>, Color)>,
+    /// positions to underline, e.g. the exact core of fuzzy matches
+    pub underline_intervals: IntervalSet<usize>,
+    /// positions styled by base quality and the modifier to apply (e.g. dim or bold)
+    pub quality_intervals: IntervalSet<usize>,
+    pub quality_modifier: Modifier,
+    /// read positions where a match deviates from its pattern
+    pub edit_positions: Vec<(usize, EditKind)>,
+    /// positions struck through, e.g. adaptors and poly-A tails removed by trimming
+    pub crossed_out_intervals: IntervalSet<usize>,
+    /// positions in italic, e.g. lowercase (low confidence) bases
+    pub italic_intervals: IntervalSet<usize>,
+}
+
+/// How a read position deviates from the pattern in a match alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    Substitution,
+    /// read base not in the pattern
+    ExtraBase,
+    /// pattern base(s) missing from the read right before this position
+    MissingBase,
+}
+
+impl EditKind {
+    pub fn modifier(self) -> Modifier {
+        match self {
+            EditKind::Substitution => Modifier::BOLD,
+            EditKind::ExtraBase => Modifier::UNDERLINED,
+            EditKind::MissingBase => Modifier::ITALIC,
+        }
+    }
+}
+
+impl StyleInput {
+    pub fn new(overlap_color: Color) -> Self {
+        Self {
+            fg_color_intervals: Vec::new(),
+            overlap_color,
+            match_modifier: Modifier::empty(),
+            base_color_intervals: Vec::new(),
+            underline_intervals: Vec::<(usize, usize)>::new().to_interval_set(),
+            quality_intervals: Vec::<(usize, usize)>::new().to_interval_set(),
+            quality_modifier: Modifier::empty(),
+            edit_positions: Vec::new(),
+            crossed_out_intervals: Vec::<(usize, usize)>::new().to_interval_set(),
+            italic_intervals: Vec::<(usize, usize)>::new().to_interval_set(),
+        }
+    }
+}
+
+/// Style of a single position after combining all layers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CombinedStyle {
+    pub fg: Option<Color>,
+    pub underline: bool,
+    pub modifier: Modifier,
+}
+
+impl CombinedStyle {
+    pub fn to_ratatui_style(self) -> Style {
+        let mut style = Style::new();
+        if let Some(color) = self.fg {
+            style = style.fg(color);
+        }
+        if self.underline {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        style.add_modifier(self.modifier)
+    }
+}
 
-    for (start, end, color) in intervals.iter().map(|&(a, b, col)| (a, b + 1, col)) {
-        if current_index < start {
-            result.push(input_string[current_index..start].to_string().into());
+/// Style `input_string`, the part of a read starting at position `offset`
+/// style input positions are positions in the whole read, so that only the part of a long
+/// read in view has to be styled
+pub fn highlight_window<'a>(
+    style_input: &StyleInput,
+    input_string: String,
+    offset: usize,
+) -> Line<'a> {
+    let len = input_string.len();
+    let mut styles: Vec<CombinedStyle> = vec![CombinedStyle::default(); len];
+    // apply `f` to every position of the interval set within the window
+    fn for_each_position(
+        set: &IntervalSet<usize>,
+        offset: usize,
+        styles: &mut [CombinedStyle],
+        f: impl Fn(&mut CombinedStyle),
+    ) {
+        for interval in set.iter() {
+            let start = interval.lower().max(offset) - offset;
+            let end = (interval.upper() + 1)
+                .saturating_sub(offset)
+                .min(styles.len());
+            for style in styles.iter_mut().take(end).skip(start) {
+                f(style);
+            }
         }
-        if end <= input_string.len() {
-            result.push(Span::styled(
-                input_string[start..end].to_string(),
-                Style::new().fg(color),
-            ));
+    }
+
+    for (set, color) in &style_input.base_color_intervals {
+        for_each_position(set, offset, &mut styles, |style| style.fg = Some(*color));
+    }
+    for (set, color) in format_overlap(&style_input.fg_color_intervals, style_input.overlap_color) {
+        for_each_position(&set, offset, &mut styles, |style| {
+            style.fg = Some(color);
+            style.modifier |= style_input.match_modifier;
+        });
+    }
+    for_each_position(
+        &style_input.underline_intervals,
+        offset,
+        &mut styles,
+        |style| style.underline = true,
+    );
+    for_each_position(
+        &style_input.quality_intervals,
+        offset,
+        &mut styles,
+        |style| style.modifier |= style_input.quality_modifier,
+    );
+    for_each_position(
+        &style_input.crossed_out_intervals,
+        offset,
+        &mut styles,
+        |style| style.modifier |= Modifier::CROSSED_OUT,
+    );
+    for_each_position(
+        &style_input.italic_intervals,
+        offset,
+        &mut styles,
+        |style| style.modifier |= Modifier::ITALIC,
+    );
+    for (pos, kind) in &style_input.edit_positions {
+        if let Some(style) = pos.checked_sub(offset).and_then(|pos| styles.get_mut(pos)) {
+            style.modifier |= kind.modifier();
         }
-        current_index = end;
     }
 
-    if current_index < input_string.len() {
-        result.push(input_string[current_index..].to_string().into());
+    // merge runs of identical styles into spans
+    let mut result: Vec<Span> = Vec::new();
+    let mut run_start: usize = 0;
+    for i in 1..=len {
+        if i == len || styles[i] != styles[run_start] {
+            let text = input_string[run_start..i].to_string();
+            if styles[run_start] == CombinedStyle::default() {
+                result.push(text.into());
+            } else {
+                result.push(Span::styled(text, styles[run_start].to_ratatui_style()));
+            }
+            run_start = i;
+        }
     }
 
     Line::from(result)
 }
+
+#[test]
+fn test_highlight_window() {
+    let mut style_input = StyleInput::new(Color::Gray);
+    style_input
+        .fg_color_intervals
+        .push((vec![(2, 5)].to_interval_set(), Color::Red));
+    style_input.edit_positions.push((4, EditKind::Substitution));
+    let red = Style::new().fg(Color::Red);
+    // window of positions 4..8 of the read
+    assert_eq!(
+        highlight_window(&style_input, String::from("ACGT"), 4),
+        Line::from(vec![
+            Span::styled("A", red.add_modifier(Modifier::BOLD)),
+            Span::styled("C", red),
+            Span::raw("GT"),
+        ])
+    );
+    assert_eq!(
+        highlight_window(&style_input, String::from("ACGT"), 8),
+        Line::from(vec![Span::raw("ACGT")])
+    );
+    // matches emphasized with reversed colors
+    style_input.match_modifier = Modifier::REVERSED;
+    let reversed = red.add_modifier(Modifier::REVERSED);
+    assert_eq!(
+        highlight_window(&style_input, String::from("ACGT"), 0),
+        Line::from(vec![Span::raw("AC"), Span::styled("GT", reversed)])
+    );
+}
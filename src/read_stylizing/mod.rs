@@ -1,3 +1,16 @@
+pub mod base_coloring;
+pub mod color_depth;
 mod interval_operations;
+pub mod length_gradient;
+pub mod match_confidence;
 pub mod match_highlighting;
-pub use crate::read_stylizing::match_highlighting::highlight_matches;
+pub mod quality_styling;
+pub use crate::read_stylizing::base_coloring::base_color_intervals;
+pub use crate::read_stylizing::color_depth::{approximates_colors, quantize_color};
+pub use crate::read_stylizing::interval_operations::subtract_intervals;
+pub use crate::read_stylizing::length_gradient::length_color;
+pub use crate::read_stylizing::match_confidence::confidence_color;
+pub use crate::read_stylizing::match_highlighting::{highlight_window, EditKind, StyleInput};
+pub use crate::read_stylizing::quality_styling::{
+    get_quality_styling, QualityStyleMode, PHRED_OFFSET,
+};
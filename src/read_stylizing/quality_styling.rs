@@ -0,0 +1,94 @@
+use interval::interval_set::{IntervalSet, ToIntervalSet};
+use ratatui::prelude::Modifier;
+
+/// Phred+33 offset of FASTQ quality strings
+pub const PHRED_OFFSET: u8 = 33;
+
+/// Which bases to style by quality
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityStyleMode {
+    /// dim bases below the threshold
+    #[default]
+    Dim,
+    /// embolden bases at or above the threshold
+    Emphasis,
+}
+
+impl QualityStyleMode {
+    pub fn modifier(self) -> Modifier {
+        match self {
+            QualityStyleMode::Dim => Modifier::DIM,
+            QualityStyleMode::Emphasis => Modifier::BOLD,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            QualityStyleMode::Dim => QualityStyleMode::Emphasis,
+            QualityStyleMode::Emphasis => QualityStyleMode::Dim,
+        }
+    }
+}
+
+/// Positions to style given the (Phred+33) quality string and threshold
+pub fn get_quality_styling(
+    qual: &[u8],
+    threshold: u8,
+    mode: QualityStyleMode,
+) -> IntervalSet<usize> {
+    let styled = |q: u8| {
+        let below = q.saturating_sub(PHRED_OFFSET) < threshold;
+        match mode {
+            QualityStyleMode::Dim => below,
+            QualityStyleMode::Emphasis => !below,
+        }
+    };
+    let mut intervals: Vec<(usize, usize)> = Vec::new();
+    for (i, q) in qual.iter().enumerate() {
+        if styled(*q) {
+            match intervals.last_mut() {
+                Some((_, end)) if *end + 1 == i => *end = i,
+                _ => intervals.push((i, i)),
+            }
+        }
+    }
+    intervals.to_interval_set()
+}
+
+#[test]
+fn test_get_quality_styling() {
+    // '5' = 20, '+' = 10, 'I' = 40
+    let qual = b"II++I5+II";
+    assert_eq!(
+        get_quality_styling(qual, 20, QualityStyleMode::Dim),
+        vec![(2, 3), (6, 6)].to_interval_set()
+    );
+    assert_eq!(
+        get_quality_styling(b"IIII", 20, QualityStyleMode::Dim),
+        Vec::<(usize, usize)>::new().to_interval_set()
+    );
+}
+
+#[test]
+fn test_get_quality_styling_emphasis() {
+    let qual = b"II++I5+II";
+    assert_eq!(
+        get_quality_styling(qual, 20, QualityStyleMode::Emphasis),
+        vec![(0, 1), (4, 5), (7, 8)].to_interval_set()
+    );
+    assert_eq!(
+        get_quality_styling(b"++++", 20, QualityStyleMode::Emphasis),
+        Vec::<(usize, usize)>::new().to_interval_set()
+    );
+}
@@ -1,11 +1,14 @@
-use crate::app::SearchPattern;
-use crossterm::event::KeyEvent;
-use ratatui::layout::Alignment;
-use ratatui::prelude::{Buffer, Constraint, Direction, Layout, Line, Modifier, Rect, Span, Style};
-use ratatui::widgets::block::title::{Position, Title};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget};
+use crate::app::{MatchAlgorithm, SearchPattern};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::{
+    Buffer, Color, Constraint, Direction, Layout, Line, Modifier, Rect, Span, Style,
+};
+use ratatui::widgets::{
+    Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget,
+};
 use std::collections::BTreeMap;
 use std::rc::Rc;
+use std::str::FromStr;
 use tui_textarea::{CursorMove, TextArea};
 
 const ACTIVE_BOARDER_STYLE: Style = Style {
@@ -16,21 +19,51 @@ const ACTIVE_BOARDER_STYLE: Style = Style {
     sub_modifier: ratatui::style::Modifier::BOLD,
 };
 
-fn search_patterns_to_list<'a>(search_patterns: &[SearchPattern]) -> List<'a> {
+/// List of the patterns with the number of matches of each in the rendered records, if known
+fn search_patterns_to_list<'a>(
+    search_patterns: &[SearchPattern],
+    match_counts: &[usize],
+) -> List<'a> {
     List::new(
         search_patterns
             .iter()
-            .map(|x| {
+            .enumerate()
+            .map(|(i, x)| {
+                // disabled patterns greyed out
+                let color = if x.enabled { x.color } else { Color::DarkGray };
                 ListItem::new(Line::from(vec![
-                    Span::styled(x.search_string.clone(), Style::new().fg(x.color)),
+                    Span::styled(x.search_string.clone(), Style::new().fg(color)),
                     Span::from(if !x.comment.is_empty() {
                         format!(" ({}), ", x.comment)
                     } else {
                         String::from(", ")
                     }),
-                    Span::styled(x.color.to_string(), Style::new().fg(x.color)),
-                    Span::from(format!(", edit-distance: {}", x.edit_distance)),
+                    Span::styled(x.color.to_string(), Style::new().fg(color)),
+                    Span::from(if x.algorithm == MatchAlgorithm::Regex {
+                        String::from(", regex")
+                    } else {
+                        format!(", edit-distance: {}", x.edit_distance)
+                    }),
+                    Span::from(if x.search_rev_comp {
+                        ", both strands"
+                    } else {
+                        ""
+                    }),
+                    Span::from(if x.enabled { "" } else { ", disabled" }),
+                    Span::styled(
+                        match match_counts.get(i).filter(|_| x.enabled) {
+                            Some(1) => String::from(" (1 match)"),
+                            Some(count) => format!(" ({} matches)", count),
+                            None => String::new(),
+                        },
+                        Style::new().fg(Color::DarkGray),
+                    ),
                 ]))
+                .style(if x.enabled {
+                    Style::new()
+                } else {
+                    Style::new().fg(Color::DarkGray)
+                })
             })
             .collect::<Vec<ListItem>>(),
     )
@@ -58,11 +91,11 @@ impl<'a> StatefulList<'a> {
     }
     /// Create a new StatefulList from a vector of SearchPattern
     fn from_search_patterns(search_patterns: &[SearchPattern]) -> Self {
-        Self::new(search_patterns_to_list(search_patterns))
+        Self::new(search_patterns_to_list(search_patterns, &[]))
     }
     /// Update with a vector of SearchPattern, keeping the selected element if possible
-    fn update(&mut self, search_patterns: &[SearchPattern]) {
-        self.list = search_patterns_to_list(search_patterns);
+    fn update(&mut self, search_patterns: &[SearchPattern], match_counts: &[usize]) {
+        self.list = search_patterns_to_list(search_patterns, match_counts);
         if !search_patterns.is_empty()
             && self.state.selected().is_some()
             && self.state.selected().unwrap() >= search_patterns.len()
@@ -89,6 +122,11 @@ impl<'a> StatefulList<'a> {
         self.state.select(Some(i));
     }
 
+    /// select the item at the given index
+    fn select(&mut self, index: usize) {
+        self.state.select(Some(index));
+    }
+
     /// change to given block and return self
     fn block(mut self, block: Block<'a>) -> Self {
         self.list = self.list.block(block);
@@ -123,8 +161,8 @@ impl<'a> FocusableElement<'a, StatefulList<'a>> {
         }
     }
 
-    fn update(&mut self, search_patterns: &[SearchPattern]) {
-        self.element.update(search_patterns);
+    fn update(&mut self, search_patterns: &[SearchPattern], match_counts: &[usize]) {
+        self.element.update(search_patterns, match_counts);
     }
 
     fn next(&mut self, forward: bool) {
@@ -156,11 +194,11 @@ impl<'a> FocusableElement<'a, TextArea<'a>> {
     /// render the element with the focused block if focused is true
     fn render(&self, area: Rect, buf: &mut Buffer, focused: bool) {
         if !focused {
-            self.element.widget().render(area, buf);
+            self.element.render(area, buf);
         } else {
             let mut cloned = self.element.clone();
             cloned.set_block(self.focused_block.clone());
-            cloned.widget().render(area, buf);
+            cloned.render(area, buf);
         }
     }
 
@@ -177,16 +215,47 @@ impl<'a> FocusableElement<'a, TextArea<'a>> {
     }
 }
 
+/// Check box toggled with space, e.g. to also search the reverse complement
+#[derive(Debug, Clone)]
+pub struct Checkbox<'a> {
+    checked: bool,
+    label: &'static str,
+    block: Block<'a>, // block to render when not focused
+}
+
+impl<'a> FocusableElement<'a, Checkbox<'a>> {
+    fn new_checkbox_element(checkbox: Checkbox<'a>, focused_block: Block<'a>) -> Self {
+        Self {
+            element: checkbox,
+            focused_block,
+        }
+    }
+
+    /// render the element with the focused block if focused is true
+    fn render(&self, area: Rect, buf: &mut Buffer, focused: bool) {
+        let mark = if self.element.checked { "x" } else { " " };
+        Paragraph::new(format!("[{}] {}", mark, self.element.label))
+            .block(if focused {
+                self.focused_block.clone()
+            } else {
+                self.element.block.clone()
+            })
+            .render(area, buf);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum PanelElement<'a> {
     ListElement(FocusableElement<'a, StatefulList<'a>>),
     TextAreaElement(FocusableElement<'a, TextArea<'a>>),
+    CheckboxElement(FocusableElement<'a, Checkbox<'a>>),
 }
 impl PanelElement<'_> {
     fn render(&self, area: Rect, buf: &mut Buffer, focused: bool) {
         match self {
             PanelElement::ListElement(list) => list.render(area, buf, focused),
             PanelElement::TextAreaElement(textarea) => textarea.render(area, buf, focused),
+            PanelElement::CheckboxElement(checkbox) => checkbox.render(area, buf, focused),
         }
     }
 }
@@ -198,16 +267,20 @@ pub enum PanelElementName {
     InputColor,
     InputDistance,
     InputComment,
+    InputRevComp,
+    InputRegex,
 }
 impl PanelElementName {
     fn next(&self, reverse: bool) -> Self {
         if reverse {
             match self {
-                PanelElementName::PatternsList => PanelElementName::InputComment,
+                PanelElementName::PatternsList => PanelElementName::InputRegex,
                 PanelElementName::InputPattern => PanelElementName::PatternsList,
                 PanelElementName::InputColor => PanelElementName::InputPattern,
                 PanelElementName::InputDistance => PanelElementName::InputColor,
                 PanelElementName::InputComment => PanelElementName::InputDistance,
+                PanelElementName::InputRevComp => PanelElementName::InputComment,
+                PanelElementName::InputRegex => PanelElementName::InputRevComp,
             }
         } else {
             match self {
@@ -215,7 +288,9 @@ impl PanelElementName {
                 PanelElementName::InputPattern => PanelElementName::InputColor,
                 PanelElementName::InputColor => PanelElementName::InputDistance,
                 PanelElementName::InputDistance => PanelElementName::InputComment,
-                PanelElementName::InputComment => PanelElementName::PatternsList,
+                PanelElementName::InputComment => PanelElementName::InputRevComp,
+                PanelElementName::InputRevComp => PanelElementName::InputRegex,
+                PanelElementName::InputRegex => PanelElementName::PatternsList,
             }
         }
     }
@@ -230,6 +305,21 @@ impl PanelElementName {
             PanelElementName::InputColor => "Color",
             PanelElementName::InputDistance => "Edit distance",
             PanelElementName::InputComment => "Comment (optional)",
+            PanelElementName::InputRevComp => "Reverse complement",
+            PanelElementName::InputRegex => "Regex",
+        }
+    }
+
+    /// Short name for the focus chain indicator
+    fn short_title(&self) -> &'static str {
+        match self {
+            PanelElementName::PatternsList => "Patterns",
+            PanelElementName::InputPattern => "Search String",
+            PanelElementName::InputColor => "Color",
+            PanelElementName::InputDistance => "Edit distance",
+            PanelElementName::InputComment => "Comment",
+            PanelElementName::InputRevComp => "Rev comp",
+            PanelElementName::InputRegex => "Regex",
         }
     }
 }
@@ -240,6 +330,7 @@ pub struct SearchPanel<'a> {
     focused_element: PanelElementName, // must have a focused element
     layout: fn(Rect) -> Rc<[Rect]>,
     file_save_popup: TextArea<'a>,
+    match_counts: Vec<usize>, // matches of each pattern in the rendered records
 }
 
 impl<'a> SearchPanel<'a> {
@@ -260,6 +351,18 @@ impl<'a> SearchPanel<'a> {
         }
     }
 
+    /// select the pattern at the given index, e.g. to follow a moved pattern
+    pub fn select_pattern(&mut self, index: usize) {
+        match self
+            .elements
+            .get_mut(&PanelElementName::PatternsList)
+            .unwrap()
+        {
+            PanelElement::ListElement(list) => list.element.select(index),
+            _ => panic!("Wrong type of element"),
+        }
+    }
+
     /// return the name of the focused element
     pub fn focused_element(&self) -> PanelElementName {
         self.focused_element.clone()
@@ -273,6 +376,73 @@ impl<'a> SearchPanel<'a> {
         }
     }
 
+    /// Focus order of the elements with the focused one highlighted, e.g.
+    /// Patterns → [Search String] → Color → Edit distance → Comment
+    fn focus_chain_line(&self) -> Line<'static> {
+        let mut spans: Vec<Span> = Vec::new();
+        let mut element = PanelElementName::PatternsList;
+        loop {
+            if element == self.focused_element {
+                spans.push(Span::styled(
+                    format!("[{}]", element.short_title()),
+                    ACTIVE_BOARDER_STYLE,
+                ));
+            } else {
+                spans.push(Span::styled(
+                    element.short_title(),
+                    Style::new().fg(Color::DarkGray),
+                ));
+            }
+            element = element.next(false);
+            if element == PanelElementName::PatternsList {
+                break;
+            }
+            spans.push(Span::styled(" → ", Style::new().fg(Color::DarkGray)));
+        }
+        Line::from(spans).right_aligned()
+    }
+
+    /// return the current text of an input box
+    pub fn input_text(&self, element: &PanelElementName) -> String {
+        match &self.elements[element] {
+            PanelElement::TextAreaElement(textarea) => textarea.lines().join(""),
+            _ => panic!("Wrong type of element"),
+        }
+    }
+
+    /// Live validation of an input box, shown on its bottom border:
+    /// a swatch of the parsed color, or an error if the input can't be parsed
+    fn input_status(&self, element: &PanelElementName) -> Option<Line<'static>> {
+        let invalid = |msg: &'static str| Some(Line::styled(msg, Style::new().fg(Color::Red)));
+        if *element == PanelElementName::InputDistance
+            && self.checked(&PanelElementName::InputRegex)
+        {
+            return Some(Line::styled(
+                "not used for regex",
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        let text = match element {
+            PanelElementName::InputColor | PanelElementName::InputDistance => {
+                self.input_text(element)
+            }
+            _ => return None,
+        };
+        if text.is_empty() {
+            return None;
+        }
+        match element {
+            PanelElementName::InputColor => match Color::from_str(&text) {
+                Ok(color) => Some(Line::styled("██", Style::new().fg(color))),
+                Err(_) => invalid("invalid color"),
+            },
+            _ => match u8::from_str(&text) {
+                Ok(_) => None,
+                Err(_) => invalid("not a number"),
+            },
+        }
+    }
+
     /// return a reference to the elements map
     pub fn elements(&self) -> &BTreeMap<PanelElementName, PanelElement<'a>> {
         &self.elements
@@ -290,9 +460,11 @@ impl<'a> SearchPanel<'a> {
                     .direction(Direction::Horizontal)
                     .constraints(vec![
                         Constraint::Percentage(25),
-                        Constraint::Percentage(25),
-                        Constraint::Percentage(25),
-                        Constraint::Percentage(25),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(13),
+                        Constraint::Percentage(12),
                     ])
                     .split(vert_chunk[1])
                     .iter(),
@@ -338,16 +510,35 @@ impl<'a> SearchPanel<'a> {
             );
         }
 
+        for (element, label) in [
+            (PanelElementName::InputRevComp, "also search"),
+            (PanelElementName::InputRegex, "search string"),
+        ] {
+            elements.insert(
+                element.clone(),
+                PanelElement::CheckboxElement(FocusableElement::new_checkbox_element(
+                    Checkbox {
+                        checked: false,
+                        label,
+                        block: Block::default()
+                            .borders(Borders::ALL)
+                            .title(element.title()),
+                    },
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(ACTIVE_BOARDER_STYLE)
+                        .title(element.title())
+                        .title_bottom(Line::from("space to toggle").right_aligned()),
+                )),
+            );
+        }
+
         let mut file_save_popup = TextArea::default();
         file_save_popup.set_block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Save patterns as CSV to ...")
-                .title(
-                    Title::from("Esc to cancel; Enter to save")
-                        .position(Position::Bottom)
-                        .alignment(Alignment::Right),
-                ),
+                .title_bottom(Line::from("Esc to cancel; Enter to save").right_aligned()),
         );
 
         Self {
@@ -355,6 +546,7 @@ impl<'a> SearchPanel<'a> {
             focused_element: PanelElementName::PatternsList,
             layout,
             file_save_popup,
+            match_counts: Vec::new(),
         }
     }
 
@@ -365,18 +557,45 @@ impl<'a> SearchPanel<'a> {
             .get_mut(&PanelElementName::PatternsList)
             .unwrap()
         {
-            PanelElement::ListElement(list) => list.update(search_patterns),
+            PanelElement::ListElement(list) => list.update(search_patterns, &self.match_counts),
             _ => panic!("Wrong type of element"),
         }
     }
 
-    /// Clear all TextArea elements' inptus
+    /// Show the number of matches of each pattern, counted after the patterns or the rendered
+    /// records change
+    pub fn set_match_counts(
+        &mut self,
+        match_counts: Vec<usize>,
+        search_patterns: &[SearchPattern],
+    ) {
+        if match_counts != self.match_counts {
+            self.match_counts = match_counts;
+            self.update(search_patterns);
+        }
+    }
+
+    pub fn match_counts(&self) -> &[usize] {
+        &self.match_counts
+    }
+
+    /// Clear all TextArea elements' inptus and uncheck the check boxes
     pub fn clear_inputs(&mut self) {
-        self.elements.values_mut().for_each(|element| {
-            if let PanelElement::TextAreaElement(textarea) = element {
-                textarea.clear()
-            }
-        });
+        self.elements
+            .values_mut()
+            .for_each(|element| match element {
+                PanelElement::TextAreaElement(textarea) => textarea.clear(),
+                PanelElement::CheckboxElement(checkbox) => checkbox.element.checked = false,
+                PanelElement::ListElement(_) => (),
+            });
+    }
+
+    /// Whether a check box is checked
+    pub fn checked(&self, element: &PanelElementName) -> bool {
+        match &self.elements[element] {
+            PanelElement::CheckboxElement(checkbox) => checkbox.element.checked,
+            _ => panic!("Wrong type of element"),
+        }
     }
 
     /// Instert the given pattern to the input fields
@@ -405,6 +624,13 @@ impl<'a> SearchPanel<'a> {
                     _ => (),
                 }
             }
+            if let PanelElement::CheckboxElement(checkbox) = element {
+                checkbox.element.checked = match element_name {
+                    PanelElementName::InputRevComp => pattern.search_rev_comp,
+                    PanelElementName::InputRegex => pattern.algorithm == MatchAlgorithm::Regex,
+                    _ => false,
+                };
+            }
         }
 
         // move cursor to end
@@ -422,6 +648,11 @@ impl<'a> SearchPanel<'a> {
             PanelElement::TextAreaElement(textarea) => {
                 textarea.element.input(keyevent);
             }
+            PanelElement::CheckboxElement(checkbox) => {
+                if keyevent.code == KeyCode::Char(' ') {
+                    checkbox.element.checked = !checkbox.element.checked;
+                }
+            }
             // slightly inconsistent, but
             // keep list operations in main.rs
             _ => panic!("Wrong type of element"),
@@ -444,7 +675,7 @@ impl<'a> SearchPanel<'a> {
 
     /// Re-export widget method for rendering the file save popup
     pub fn file_save_popup_widget(&self) -> impl Widget + '_ {
-        self.file_save_popup.widget()
+        &self.file_save_popup
     }
 }
 
@@ -454,6 +685,33 @@ impl Widget for &SearchPanel<'_> {
         assert_eq!(chunks.len(), self.elements.len());
         for (i, (element_name, element)) in self.elements.iter().enumerate() {
             element.render(chunks[i], buf, *element_name == self.focused_element);
+            if *element_name == PanelElementName::InputDistance
+                && self.checked(&PanelElementName::InputRegex)
+            {
+                buf.set_style(chunks[i], Style::new().fg(Color::DarkGray));
+            }
+            if let Some(status) = self.input_status(element_name) {
+                if chunks[i].width > 2 && chunks[i].height > 1 {
+                    let status_area = Rect::new(
+                        chunks[i].x + 1,
+                        chunks[i].bottom() - 1,
+                        chunks[i].width - 2,
+                        1,
+                    );
+                    status.right_aligned().render(status_area, buf);
+                }
+            }
+        }
+        // focus chain on the bottom border of the patterns list
+        let list_area = chunks[0];
+        if list_area.width > 2 && list_area.height > 1 {
+            let indicator_area = Rect::new(
+                list_area.x + 1,
+                list_area.bottom() - 1,
+                list_area.width - 2,
+                1,
+            );
+            self.focus_chain_line().render(indicator_area, buf);
         }
     }
 }
@@ -0,0 +1,114 @@
+use crate::app::{App, SearchPattern, StylingConfig};
+use crate::io::output::write_replacing;
+use anyhow::Context;
+use ratatui::prelude::Size;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Default file the viewer state is saved to, in the working directory
+pub const STATE_FILE: &str = "seqsizzle-state.toml";
+
+/// Autosave file of the patterns edited while viewing `file`, under the system temp directory
+/// and keyed by the canonical path so different inputs don't restore each other's patterns
+pub fn autosave_path(file: &Path) -> PathBuf {
+    let canonical = std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let hash = crate::io::fastq::stable_hash(canonical.as_os_str().as_encoded_bytes());
+    std::env::temp_dir()
+        .join("seqsizzle")
+        .join(format!("autosave-{:016x}.csv", hash))
+}
+
+/// Snapshot of the viewer, saved as TOML to reproduce what was on screen, e.g. for bug
+/// reports, and restored with `--replay`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewerState {
+    pub file: PathBuf,
+    pub scroll_status: (usize, usize),
+    pub horizontal_scroll: usize,
+    pub terminal_size: (u16, u16), // (width, height) when saved, rows wrap differently otherwise
+    pub styling_config: StylingConfig,
+    pub search_patterns: Vec<SearchPattern>,
+}
+
+impl ViewerState {
+    pub fn capture(app: &App, terminal_size: Size) -> Self {
+        Self {
+            file: app.file.clone(),
+            scroll_status: app.scroll_status,
+            horizontal_scroll: app.horizontal_scroll,
+            terminal_size: (terminal_size.width, terminal_size.height),
+            styling_config: app.styling_config.clone(),
+            search_patterns: app.search_patterns.clone(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let content = toml::to_string(self).context("Error serializing the viewer state")?;
+        write_replacing(path, |writer| writer.write_all(content.as_bytes()))
+            .with_context(|| format!("Error writing state file {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Error reading state file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Error parsing state file {}", path.display()))
+    }
+
+    /// Restore the scroll position and styling, the patterns are passed to `App::new`
+    pub fn restore(&self, app: &mut App, terminal_size: Size) {
+        app.set_styling_config(self.styling_config.clone());
+        app.go_to_record(self.scroll_status.0);
+        app.scroll_status.1 = self.scroll_status.1;
+        app.horizontal_scroll = self.horizontal_scroll;
+        if (terminal_size.width, terminal_size.height) != self.terminal_size {
+            app.set_message(format!(
+                "State saved in a {}x{} terminal, lines may wrap differently",
+                self.terminal_size.0, self.terminal_size.1
+            ));
+        }
+    }
+}
+
+/// (De)serialize colors by name, as in pattern CSV files
+pub mod color {
+    use ratatui::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(color)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Color::from_str(&name)
+            .map_err(|_| serde::de::Error::custom(format!("invalid color {}", name)))
+    }
+}
+
+#[test]
+fn test_state_round_trip() {
+    use crate::app::MatchAlgorithm;
+    use ratatui::prelude::Color;
+    let state = ViewerState {
+        file: PathBuf::from("reads.fastq"),
+        scroll_status: (12, 3),
+        horizontal_scroll: 0,
+        terminal_size: (100, 30),
+        styling_config: StylingConfig {
+            invert_match: true,
+            length_scale: Some((100, 2000)),
+            ..StylingConfig::default()
+        },
+        search_patterns: vec![
+            SearchPattern::new(String::from("ACGT"), Color::Rgb(255, 0, 0), 1, "rgb"),
+            SearchPattern::new(String::from("TTTT"), Color::Blue, 0, "")
+                .with_algorithm(MatchAlgorithm::AffineGap),
+        ],
+    };
+    let content = toml::to_string(&state).unwrap();
+    assert!(content.contains("algorithm = \"affine\""));
+    assert_eq!(toml::from_str::<ViewerState>(&content).unwrap(), state);
+}
@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+/// Test file or directory removed when dropped, also when an assertion fails first
+pub struct TempFileGuard(pub PathBuf);
+
+impl TempFileGuard {
+    /// A new path in the temporary directory, with `extension` unless empty
+    pub fn new(extension: &str) -> Self {
+        let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        TempFileGuard(path.with_extension(extension))
+    }
+
+    /// A new temporary file of `content`
+    pub fn with_content(extension: &str, content: impl AsRef<[u8]>) -> Self {
+        let file = Self::new(extension);
+        std::fs::write(&file, content).unwrap();
+        file
+    }
+
+    /// A new empty temporary directory
+    pub fn dir() -> Self {
+        let dir = Self::new("");
+        std::fs::create_dir(&dir).unwrap();
+        dir
+    }
+}
+
+impl std::ops::Deref for TempFileGuard {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempFileGuard {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFileGuard {
+    fn drop(&mut self) {
+        let _ = if self.0.is_dir() {
+            std::fs::remove_dir_all(&self.0)
+        } else {
+            std::fs::remove_file(&self.0)
+        };
+    }
+}
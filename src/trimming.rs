@@ -0,0 +1,210 @@
+use crate::app::{has_quality, App, Molecule, SearchPattern};
+use crate::io::output::FastaWriter;
+use bio::io::fastq;
+use std::io::Write;
+
+/// Matches starting or ending this close to the read ends are trimmed as adaptors by default,
+/// see `StylingConfig::trim_window`
+pub const TRIM_END_WINDOW: usize = 10;
+
+/// Shortest run of trailing As (or leading Ts) trimmed as a poly-A (poly-T) tail
+const TRIM_POLY_A_MIN: usize = 10;
+
+/// Bounds [start, end) of a read after trimming matches (start, end (inclusive)) starting
+/// within `window` bases of the read start or ending within it of the read end, then a poly-A
+/// tail left in front of the 3' match and a poly-T tail left after the 5' match, as in reads of
+/// the reverse strand
+pub(crate) fn trim_bounds(seq: &[u8], hits: &[(usize, usize)], window: usize) -> (usize, usize) {
+    let mut start = hits
+        .iter()
+        .filter(|(hit_start, _)| *hit_start < window)
+        .map(|(_, hit_end)| hit_end + 1)
+        .max()
+        .unwrap_or(0);
+    let mut end = hits
+        .iter()
+        .filter(|(hit_start, hit_end)| *hit_start >= start && hit_end + window >= seq.len())
+        .map(|(hit_start, _)| *hit_start)
+        .min()
+        .unwrap_or(seq.len());
+    let poly_a = seq[start..end]
+        .iter()
+        .rev()
+        .take_while(|base| base.eq_ignore_ascii_case(&b'A'))
+        .count();
+    if poly_a >= TRIM_POLY_A_MIN {
+        end -= poly_a;
+    }
+    let poly_t = seq[start..end.max(start)]
+        .iter()
+        .take_while(|base| base.eq_ignore_ascii_case(&b'T'))
+        .count();
+    if poly_t >= TRIM_POLY_A_MIN {
+        start += poly_t;
+    }
+    (start, end.max(start))
+}
+
+#[test]
+fn test_trim_bounds() {
+    let seq = b"GGGGGGCCCCCCCCCCCCAAAAAAAAAAAATTTT";
+    // 5' adaptor 0..=5, poly-A and 3' adaptor 30..=33
+    assert_eq!(
+        trim_bounds(seq, &[(0, 5), (30, 33)], TRIM_END_WINDOW),
+        (6, 18)
+    );
+    // matches away from the ends are kept, poly-A is trimmed from the read end
+    assert_eq!(
+        trim_bounds(&seq[..30], &[(12, 15)], TRIM_END_WINDOW),
+        (0, 18)
+    );
+    assert_eq!(trim_bounds(b"ACGT", &[], TRIM_END_WINDOW), (0, 4));
+    // poly-T after the 5' adaptor of a reverse strand read
+    let seq = b"GGGGGGTTTTTTTTTTTTCCCCCC";
+    assert_eq!(trim_bounds(seq, &[(0, 5)], TRIM_END_WINDOW), (18, 24));
+    // a motif a few bases into the read is kept with a narrower window, at either end
+    let seq = b"CCCCACGTCCCCCCCCCCCCACGTCCCC";
+    assert_eq!(
+        trim_bounds(seq, &[(4, 7), (20, 23)], TRIM_END_WINDOW),
+        (8, 20)
+    );
+    assert_eq!(trim_bounds(seq, &[(4, 7), (20, 23)], 2), (0, seq.len()));
+    assert_eq!(trim_bounds(seq, &[(4, 7)], 0), (0, seq.len()));
+}
+
+/// Number of reads written, dropped for being shorter than the minimum length and skipped
+/// for lacking the quality scores FASTQ output needs (written as FASTA)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrimStats {
+    pub kept: usize,
+    pub dropped: usize,
+    pub no_quality: usize,
+}
+
+/// The insert of a read: matches of the patterns within `window` bases of either end and
+/// poly-A / poly-T tails removed as in the trim preview (`t`), qualities sliced to match (left
+/// empty if missing)
+pub fn trim_record(
+    record: &fastq::Record,
+    search_patterns: &[SearchPattern],
+    window: usize,
+    molecule: Molecule,
+) -> fastq::Record {
+    let hits: Vec<(usize, usize)> = search_patterns
+        .iter()
+        .flat_map(|pattern| App::search(record, pattern, molecule))
+        .collect();
+    let (start, end) = trim_bounds(record.seq(), &hits, window);
+    let qual = if has_quality(record) {
+        &record.qual()[start..end]
+    } else {
+        &[]
+    };
+    fastq::Record::with_attrs(record.id(), record.desc(), &record.seq()[start..end], qual)
+}
+
+/// Write the trimmed reads at least `min_length` long as FASTQ, or as FASTA wrapped at
+/// `fasta_width` columns if given. Reads without quality scores are skipped in FASTQ, where
+/// they would be invalid records
+pub fn write_trimmed_reads<W: Write>(
+    records: impl Iterator<Item = Result<fastq::Record, fastq::Error>>,
+    search_patterns: &[SearchPattern],
+    window: usize,
+    writer: W,
+    min_length: usize,
+    fasta_width: Option<usize>,
+    molecule: Molecule,
+) -> anyhow::Result<TrimStats> {
+    let mut stats = TrimStats::default();
+    let (mut fastq_writer, mut fasta_writer) = match fasta_width {
+        Some(width) => (None, Some(FastaWriter::new(writer, width))),
+        None => (Some(fastq::Writer::new(writer)), None),
+    };
+    for record in records {
+        let trimmed = trim_record(&record?, search_patterns, window, molecule);
+        if trimmed.seq().len() < min_length {
+            stats.dropped += 1;
+            continue;
+        }
+        if let Some(writer) = &mut fastq_writer {
+            if trimmed.qual().len() != trimmed.seq().len() {
+                stats.no_quality += 1;
+                continue;
+            }
+            writer.write_record(&trimmed)?;
+        }
+        stats.kept += 1;
+        if let Some(writer) = &mut fasta_writer {
+            writer.write(trimmed.id(), trimmed.desc(), trimmed.seq())?;
+        }
+    }
+    if let Some(writer) = &mut fastq_writer {
+        writer.flush()?;
+    }
+    if let Some(writer) = &mut fasta_writer {
+        writer.flush()?;
+    }
+    Ok(stats)
+}
+
+#[test]
+fn test_write_trimmed_reads() {
+    use ratatui::style::Color;
+    let records = vec![
+        fastq::Record::with_attrs(
+            "insert",
+            None,
+            b"ACGTACGTCCGGCCGGAAAAAAAAAAAA",
+            b"!!!!!!!!IIIIIIII############",
+        ),
+        fastq::Record::with_attrs("short", None, b"ACGTACGTCC", b"IIIIIIIIII"),
+        fastq::Record::with_attrs("noqual", None, b"ACGTACGTCCC", b""),
+    ];
+    let patterns = vec![SearchPattern::new(
+        String::from("ACGTACGT"),
+        Color::Red,
+        0,
+        "",
+    )];
+    let mut out: Vec<u8> = Vec::new();
+    let reads = records.iter().cloned().map(Ok);
+    let stats =
+        write_trimmed_reads(reads, &patterns, 10, &mut out, 3, None, Molecule::Dna).unwrap();
+    assert_eq!(
+        stats,
+        TrimStats {
+            kept: 1,
+            dropped: 1,
+            no_quality: 1
+        }
+    );
+    // no record without quality scores, invalid in FASTQ
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "@insert\nCCGGCCGG\n+\nIIIIIIII\n"
+    );
+    let mut out: Vec<u8> = Vec::new();
+    let reads = records.iter().cloned().map(Ok);
+    let stats =
+        write_trimmed_reads(reads, &patterns, 10, &mut out, 0, Some(4), Molecule::Dna).unwrap();
+    assert_eq!(
+        stats,
+        TrimStats {
+            kept: 3,
+            dropped: 0,
+            no_quality: 0
+        }
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        ">insert\nCCGG\nCCGG\n>short\nCC\n>noqual\nCCC\n"
+    );
+    // the adaptor isn't within a window of 0 bases, only the poly-A tail is trimmed
+    let mut out: Vec<u8> = Vec::new();
+    let reads = records.into_iter().take(1).map(Ok);
+    write_trimmed_reads(reads, &patterns, 0, &mut out, 0, None, Molecule::Dna).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "@insert\nACGTACGTCCGGCCGG\n+\n!!!!!!!!IIIIIIII\n"
+    );
+}
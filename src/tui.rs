@@ -47,7 +47,7 @@ impl Tui {
     /// [`Draw`]: tui::Terminal::draw
     /// [`rendering`]: crate::ui:render
     pub fn draw(&mut self, app: &mut App) -> Result<()> {
-        if app.rendered_lines.is_empty() {
+        if app.rendered_records.is_empty() {
             panic!("No lines in app.line_buf!\n{:?}", app)
         }
         self.terminal.draw(|frame| render(app, frame))?;
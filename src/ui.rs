@@ -1,48 +1,390 @@
 use crate::app::{App, UIMode};
-
+use crate::keymap::{Action, KeyContext};
+use crate::read_stylizing::{approximates_colors, quantize_color};
 
 use ratatui::{
-    prelude::{Color, Constraint, Direction, Frame, Layout, Line, Rect, Span, Style},
+    prelude::{Color, Constraint, Direction, Frame, Layout, Line, Modifier, Rect, Span, Style},
     widgets::{
-        block::title::{Position, Title},
-        Block, Borders, Clear, Paragraph, Wrap,
+        Bar, BarChart, BarGroup, Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap,
     },
 };
 
 pub fn render(app: &mut App, frame: &mut Frame) {
-    let viewer_block = match app.get_message() {
-        Some(msg) => Block::default()
-            .borders(Borders::ALL)
-            .title(app.file.to_str().unwrap_or("SeqSizzle"))
-            .title(
-                Title::from(Span::styled(msg, Style::default().fg(Color::Red)))
-                    .position(Position::Bottom),
-            ),
-        None => Block::default()
-            .borders(Borders::ALL)
-            .title(app.file.to_str().unwrap_or("SeqSizzle")),
+    let message = app.get_message();
+    let viewer_block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.file.to_str().unwrap_or("SeqSizzle"))
+        .title(Line::from(app.position_indicator()).right_aligned());
+    let viewer_block = match message {
+        Some(msg) => viewer_block.title_bottom(Span::styled(msg, Style::default().fg(Color::Red))),
+        None => viewer_block,
     };
 
-    frame.render_widget(
-        Paragraph::new(
-            app.rendered_lines
-                .clone()
-                .into_iter()
+    // the match marker column sits inside the borders, right of the lines
+    let inner = viewer_block.inner(frame.area());
+    frame.render_widget(viewer_block, frame.area());
+    let [lines_area, marker_area] = Layout::horizontal([
+        Constraint::Min(0),
+        Constraint::Length(app.marker_column_width() as u16),
+    ])
+    .areas(inner);
+
+    if app.styling_config.no_wrap {
+        // columns sliced here, as the offset of `Paragraph::scroll` is a u16 and long reads
+        // are wider than that
+        let width = lines_area.width as usize;
+        let paragraph = Paragraph::new(
+            app.rendered_records
+                .lines()
+                .map(|line| scrolled_line(line, app.horizontal_scroll, width))
                 .collect::<Vec<Line>>(),
-        )
-        .block(viewer_block)
-        .wrap(Wrap { trim: false })
-        .scroll((app.scroll_status.1 as u16, 0)),
-        frame.size(),
-    );
+        );
+        frame.render_widget(
+            paragraph.scroll((app.scroll_status.1 as u16, 0)),
+            lines_area,
+        );
+        render_truncation_markers(app, frame);
+    } else {
+        let paragraph =
+            Paragraph::new(app.rendered_records.lines().cloned().collect::<Vec<Line>>());
+        frame.render_widget(
+            paragraph
+                .wrap(Wrap { trim: false })
+                .scroll((app.scroll_status.1 as u16, 0)),
+            lines_area,
+        );
+    }
+    if app.styling_config.match_markers {
+        render_match_markers(app, frame, marker_area);
+    }
+    if app.selection.is_some() {
+        render_selection(app, frame);
+    }
     if let UIMode::SearchPanel(save_file_popup) = app.mode {
-        let center_area = centered_rect(80, 80, frame.size());
+        let center_area = centered_rect(80, 80, frame.area());
         frame.render_widget(Clear, center_area);
         frame.render_widget(&app.search_panel, center_area);
         if save_file_popup {
-            let save_file_popup_area = centered_rect(80, 5, frame.size());
+            let save_file_popup_area = centered_rect(80, 5, frame.area());
             frame.render_widget(Clear, save_file_popup_area);
-            frame.render_widget(app.search_panel.file_save_popup_widget() , save_file_popup_area);
+            frame.render_widget(
+                app.search_panel.file_save_popup_widget(),
+                save_file_popup_area,
+            );
+        }
+    }
+    if app.mode == UIMode::Bookmarks {
+        render_bookmarks(app, frame);
+    }
+    if app.mode == UIMode::Report {
+        render_report(app, frame);
+    }
+    if app.mode == UIMode::MatchStatistics {
+        render_match_statistics(app, frame);
+    }
+    if app.mode == UIMode::Help {
+        render_help(app, frame);
+    }
+    if let UIMode::Prompt(_) = app.mode {
+        let area = centered_rect(60, 5, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(&app.prompt, area);
+    }
+    if approximates_colors(app.color_count) {
+        // pattern, quality and UI colors alike, after everything is drawn
+        for cell in frame.buffer_mut().content.iter_mut() {
+            cell.fg = quantize_color(cell.fg, app.color_count);
+            cell.bg = quantize_color(cell.bg, app.color_count);
+        }
+    }
+}
+
+/// A bar group per pattern, bars as a percentage of the pattern's matches labelled with the
+/// number of matches at each edit distance
+fn render_match_statistics(app: &App, frame: &mut Frame) {
+    let area = centered_rect(80, 60, frame.area());
+    let stats = app.match_statistics();
+    let hint = app
+        .keymap
+        .keys_for(KeyContext::Report, Action::CloseReport)
+        .first()
+        .map(|key| format!("{} to close", key))
+        .unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            "Edit distances of matches in {} records",
+            stats.records
+        ))
+        .title_bottom(Line::from(hint).right_aligned());
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    let [chart_area, note_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+
+    let name = |index: usize| {
+        let pattern = &app.search_patterns[index];
+        if pattern.comment.is_empty() {
+            pattern.search_string.clone()
+        } else {
+            pattern.comment.clone()
+        }
+    };
+    let mut chart = BarChart::default()
+        .bar_width(3)
+        .bar_gap(1)
+        .group_gap(3)
+        .max(100);
+    for (index, histogram) in stats.histograms.iter().enumerate() {
+        let total: usize = histogram.iter().sum::<usize>().max(1);
+        let color = app.search_patterns[index].color;
+        let bars: Vec<Bar> = histogram
+            .iter()
+            .enumerate()
+            .map(|(dist, count)| {
+                Bar::default()
+                    .value((100 * count / total) as u64)
+                    .text_value(count.to_string())
+                    .label(Line::from(dist.to_string()))
+                    .style(Style::new().fg(color))
+            })
+            .collect();
+        chart = chart.data(
+            BarGroup::default()
+                .label(Line::from(name(index)))
+                .bars(&bars),
+        );
+    }
+    frame.render_widget(chart, chart_area);
+
+    let note = match stats.over_permissive().as_slice() {
+        [] if stats.histograms.is_empty() => String::from("No patterns"),
+        [] => String::from("Bars: share of each pattern's matches at each edit distance"),
+        indexes => format!(
+            "Most matches at the largest edit distance, maybe too permissive: {}",
+            indexes
+                .iter()
+                .map(|x| name(*x))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    };
+    frame.render_widget(Paragraph::new(note), note_area);
+}
+
+/// Keybindings from the keymap, in as many columns as needed to fit the popup's height
+fn render_help(app: &App, frame: &mut Frame) {
+    let area = centered_rect(90, 90, frame.area());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Keybindings")
+        .title_bottom(Line::from("any key to close").right_aligned());
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let lines = app.keymap.help_lines();
+    let columns: Vec<&[String]> = lines.chunks(inner.height.max(1) as usize).collect();
+    let areas = Layout::horizontal(vec![Constraint::Fill(1); columns.len()]).split(inner);
+    for (column, column_area) in columns.iter().zip(areas.iter()) {
+        let text: Vec<Line> = column
+            .iter()
+            .map(|line| {
+                if line.starts_with('[') {
+                    Line::styled(line.as_str(), Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    Line::from(line.as_str())
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(text), *column_area);
+    }
+}
+
+fn render_report(app: &App, frame: &mut Frame) {
+    let area = centered_rect(80, 60, frame.area());
+    let hint = app
+        .keymap
+        .keys_for(KeyContext::Report, Action::CloseReport)
+        .first()
+        .map(|key| format!("{} to close", key))
+        .unwrap_or_default();
+    let report = Paragraph::new(app.report.text.as_str()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.report.title.as_str())
+            .title_bottom(Line::from(hint).right_aligned()),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(report, area);
+}
+
+fn render_bookmarks(app: &App, frame: &mut Frame) {
+    let area = centered_rect(60, 50, frame.area());
+    let hint = |action: Action, what: &str| {
+        app.keymap
+            .keys_for(KeyContext::Bookmarks, action)
+            .first()
+            .map(|key| format!("{} to {}", key, what))
+    };
+    let hints = [
+        hint(Action::JumpToBookmark, "jump"),
+        hint(Action::DeleteBookmark, "delete"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>()
+    .join("; ");
+    let list = List::new(
+        app.bookmarks
+            .iter()
+            .map(|x| ListItem::new(format!("{:>8}  {}", x.index + 1, x.id))),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Bookmarks")
+            .title_bottom(Line::from(hints).right_aligned()),
+    )
+    .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+    .highlight_symbol("> ");
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(
+        list,
+        area,
+        &mut ListState::default().with_selected(Some(app.selected_bookmark)),
+    );
+}
+
+/// The `width` columns of a line from column `offset` on, keeping the styles of its spans
+fn scrolled_line<'a>(line: &Line<'a>, offset: usize, width: usize) -> Line<'a> {
+    let mut column = 0;
+    let mut spans: Vec<Span> = Vec::new();
+    for span in &line.spans {
+        let (start, end) = (column, column + span.content.chars().count());
+        column = end;
+        if end <= offset {
+            continue;
+        }
+        if start >= offset + width {
+            break;
+        }
+        let content: String = span
+            .content
+            .chars()
+            .skip(offset.saturating_sub(start))
+            .take(offset + width - start.max(offset))
+            .collect();
+        spans.push(Span::styled(content, span.style));
+    }
+    Line::from(spans).style(line.style)
+}
+
+#[test]
+fn test_scrolled_line() {
+    let line = Line::from(vec![
+        Span::raw("A".repeat(70_000)),
+        Span::styled("CGT", Style::new().fg(Color::Red)),
+        Span::raw("TT"),
+    ]);
+    assert_eq!(
+        scrolled_line(&line, 69_998, 4),
+        Line::from(vec![
+            Span::raw("AA"),
+            Span::styled("CG", Style::new().fg(Color::Red)),
+        ])
+    );
+    assert_eq!(
+        scrolled_line(&line, 70_004, 10),
+        Line::from(vec![Span::raw("T")])
+    );
+    assert_eq!(
+        scrolled_line(&line, 0, 3),
+        Line::from(vec![Span::raw("AAA")])
+    );
+}
+
+/// Draw `←` / `→` on the viewer borders of rows with content hidden by horizontal scrolling
+/// drawn over the rendered cells, so they never become part of a match span
+fn render_truncation_markers(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let inner_width = app.viewer_width(area.width);
+    let marker_style = Style::default().fg(Color::DarkGray);
+    for (row, line) in app
+        .rendered_records
+        .lines()
+        .skip(app.scroll_status.1)
+        .take(area.height.saturating_sub(2) as usize)
+        .enumerate()
+    {
+        let y = area.y + 1 + row as u16;
+        if app.horizontal_scroll > 0 && line.width() > 0 {
+            frame.buffer_mut()[(area.x, y)]
+                .set_symbol("←")
+                .set_style(marker_style);
+        }
+        if line.width() > app.horizontal_scroll + inner_width {
+            frame.buffer_mut()[(area.x + area.width - 1, y)]
+                .set_symbol("→")
+                .set_style(marker_style);
+        }
+    }
+}
+
+/// Rows of the viewer (0 for the first row inside the borders) covered by each rendered
+/// record on screen, as the record's place in the rendered records and its first and last row
+fn visible_records(app: &App, frame: &Frame) -> Vec<(usize, u16, u16)> {
+    let width = app.viewer_width(frame.area().width);
+    let rows = frame.area().height.saturating_sub(2) as isize;
+    let mut row = -(app.scroll_status.1 as isize);
+    let mut visible = Vec::new();
+    for (i, height) in app
+        .rendered_records
+        .heights(width, app.styling_config.no_wrap)
+        .enumerate()
+    {
+        if row >= rows {
+            break;
+        }
+        let end = row + height as isize;
+        if end > 0 {
+            visible.push((i, row.max(0) as u16, (end.min(rows) - 1) as u16));
+        }
+        row = end;
+    }
+    visible
+}
+
+/// Draw the match marker of each record on the row of its first line, or the top row if
+/// scrolled past, after a one column gap
+fn render_match_markers(app: &App, frame: &mut Frame, area: Rect) {
+    let markers = app.match_markers();
+    for (i, first_row, _) in visible_records(app, frame) {
+        let marker_area = Rect {
+            x: area.x + 1,
+            y: area.y + first_row,
+            width: area.width.saturating_sub(1),
+            height: 1,
+        };
+        frame.render_widget(Paragraph::new(markers[i].clone()), marker_area);
+    }
+}
+
+/// Mark the rows of the selected records on the left border
+fn render_selection(app: &App, frame: &mut Frame) {
+    let Some((first, last)) = app.selection else {
+        return;
+    };
+    let area = frame.area();
+    let style = Style::default().fg(Color::Yellow);
+    for (i, first_row, last_row) in visible_records(app, frame) {
+        if (first..=last).contains(&(app.scroll_status.0 + i)) {
+            for row in first_row..=last_row {
+                frame.buffer_mut()[(area.x, area.y + 1 + row)]
+                    .set_symbol("┃")
+                    .set_style(style);
+            }
         }
     }
 }